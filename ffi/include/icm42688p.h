@@ -0,0 +1,120 @@
+#ifndef ICM42688P_H
+#define ICM42688P_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define ICM_OK 0
+
+// [`icm42688p_read_sample`] found no new sample.
+#define ICM_NO_DATA 1
+
+#define ICM_ERR_BUS -1
+
+#define ICM_ERR_BAD_DEVICE_ID -2
+
+#define ICM_ERR_DATA_CORRUPTED -3
+
+#define ICM_ERR_INVALID_ARGUMENT -4
+
+#define ICM_ERR_INVALID_FOR_POWER_MODE -5
+
+#define ICM_ERR_FIFO_DESYNC -6
+
+#define ICM_ERR_OTHER -7
+
+#define ICM_ERR_BUSY -8
+
+// Bus callbacks, `ctx` is passed back unchanged to every callback.
+//
+// `read` and `write` return 0 on success.
+typedef struct IcmBus {
+  void *ctx;
+  // Reads `len` consecutive registers starting at `address` into `buf`.
+  int32_t (*read)(void *ctx, uint8_t address, uint8_t *buf, size_t len);
+  // Writes `data` to the register at `address`.
+  int32_t (*write)(void *ctx, uint8_t address, uint8_t data);
+  // Blocks for at least `us` microseconds.
+  void (*delay_us)(void *ctx, uint32_t us);
+} IcmBus;
+
+// Sample in the default units, g and °/s.
+typedef struct IcmSample {
+  float accel[3];
+  float gyro[3];
+  // See [`crate::Sample::sequence`].
+  uint32_t sequence;
+} IcmSample;
+
+// Raw FIFO packet.
+typedef struct IcmFifoPacket {
+  uint8_t header;
+  // The 16 most significant bits in 20 byte packets.
+  int16_t accel[3];
+  // The 16 most significant bits in 20 byte packets.
+  int16_t gyro[3];
+  int16_t temperature;
+  uint16_t timestamp;
+  // 18-bit accel data of 20 byte packets, 0 in the other packets.
+  //
+  // Unit: 1 / [`crate::fifo::HIRES_ACCEL_SENSITIVITY`] g
+  int32_t hires_accel[3];
+  // 19-bit gyro data of 20 byte packets, 0 in the other packets.
+  //
+  // Unit: 1 / [`crate::fifo::HIRES_GYRO_SENSITIVITY`] °/s
+  int32_t hires_gyro[3];
+} IcmFifoPacket;
+
+// Size of the memory to pass to [`icm42688p_init`], in bytes.
+size_t icm42688p_size(void);
+
+// Alignment of the memory to pass to [`icm42688p_init`], in bytes.
+size_t icm42688p_align(void);
+
+// Initializes a driver in `dev`, turning both sensors on in low noise mode.
+//
+// # Safety
+//
+// `dev` must point to writable memory of [`icm42688p_size`] bytes aligned to
+// [`icm42688p_align`]. The callbacks of `bus` must stay valid as long as the driver is used.
+int32_t icm42688p_init(void *dev, struct IcmBus bus);
+
+// Reads a sample into `out`, returns [`ICM_NO_DATA`] if no new sample is available.
+//
+// # Safety
+//
+// `dev` must have been initialized by [`icm42688p_init`] and `out` must be valid for writes.
+int32_t icm42688p_read_sample(void *dev, struct IcmSample *out);
+
+// Sets the accel full scale range, `range` is the ACCEL_FS_SEL register value.
+//
+// # Safety
+//
+// `dev` must have been initialized by [`icm42688p_init`].
+int32_t icm42688p_set_accel_range(void *dev, uint8_t range);
+
+// Sets the gyro full scale range, `range` is the GYRO_FS_SEL register value.
+//
+// # Safety
+//
+// `dev` must have been initialized by [`icm42688p_init`].
+int32_t icm42688p_set_gyro_range(void *dev, uint8_t range);
+
+// Drains the FIFO into `out`, storing the number of packets written in `count`.
+//
+// Packets beyond `capacity` are read from the FIFO but dropped. Returns
+// [`ICM_ERR_FIFO_DESYNC`] when a packet header did not match the enabled sources, `count` then
+// holds the packets written before it.
+//
+// # Safety
+//
+// `dev` must have been initialized by [`icm42688p_init`], `out` must be valid for `capacity`
+// writes and `count` must be valid for writes.
+int32_t icm42688p_fifo_drain(void *dev,
+                             struct IcmFifoPacket *out,
+                             size_t capacity,
+                             size_t *count);
+
+#endif  /* ICM42688P_H */
@@ -0,0 +1,17 @@
+//! C static and dynamic libraries of the ICM-42688-P driver, declared in
+//! `include/icm42688p.h`.
+//!
+//! Firmware with its own panic handler should wrap [`icm42688p::ffi`] in its own static library
+//! instead.
+
+// Hosted targets link std for its unwinding personality, bare-metal targets bring the panic
+// handler below.
+#![cfg_attr(target_os = "none", no_std)]
+
+pub use icm42688p::ffi::*;
+
+#[cfg(target_os = "none")]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
@@ -0,0 +1,71 @@
+//! Host bindings of the ICM-42688-P driver for Linux bench setups.
+//!
+//! [`HostBus`] runs the production driver over spidev or i2c-dev, the `python` feature exposes it
+//! as a Python extension module for scripting characterization runs (range sweeps, noise tests).
+
+use embedded_hal::spi::{Operation, SpiDevice};
+use icm42688p::{BusAccess, Error, I2cAddress, I2cInterface, Interface, RegAddr};
+use linux_embedded_hal::{
+    spidev::{SpiModeFlags, SpidevOptions},
+    I2cdev, SpidevDevice,
+};
+
+#[cfg(feature = "python")]
+mod python;
+
+const SPI_READ_OPERATION: u8 = 0b10000000;
+
+/// Bus of the host, chip select is driven by the spidev kernel driver.
+pub enum HostBus {
+    Spi(SpidevDevice),
+    I2c(I2cInterface<I2cdev>),
+}
+
+impl HostBus {
+    /// Opens a spidev device, e.g. `/dev/spidev0.0`, in SPI mode 0.
+    pub fn open_spi(path: &str, max_speed_hz: u32) -> std::io::Result<Self> {
+        let mut spi = SpidevDevice::open(path)?;
+        let options = SpidevOptions::new()
+            .bits_per_word(8)
+            .max_speed_hz(max_speed_hz.min(icm42688p::limits::SPI_MAX_FREQ_HZ))
+            .mode(SpiModeFlags::SPI_MODE_0)
+            .build();
+        spi.configure(&options)?;
+        Ok(Self::Spi(spi))
+    }
+
+    /// Opens an i2c-dev bus, e.g. `/dev/i2c-1`.
+    pub fn open_i2c(path: &str, address: I2cAddress) -> std::io::Result<Self> {
+        let i2c = I2cdev::new(path).map_err(std::io::Error::other)?;
+        Ok(Self::I2c(I2cInterface::new(i2c, address)))
+    }
+}
+
+impl Interface for HostBus {
+    fn read_register(&mut self, address: RegAddr) -> Result<u8, Error> {
+        let mut byte = [0];
+        self.read_registers(address, &mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn read_registers(&mut self, address: RegAddr, buf: &mut [u8]) -> Result<(), Error> {
+        match self {
+            HostBus::Spi(spi) => spi
+                .transaction(&mut [
+                    Operation::Write(&[address.0 | SPI_READ_OPERATION]),
+                    Operation::Read(buf),
+                ])
+                .map_err(|_| Error::Spi(BusAccess::read(address))),
+            HostBus::I2c(i2c) => i2c.read_registers(address, buf),
+        }
+    }
+
+    fn write_register(&mut self, address: RegAddr, data: u8) -> Result<(), Error> {
+        match self {
+            HostBus::Spi(spi) => spi
+                .write(&[address.0, data])
+                .map_err(|_| Error::Spi(BusAccess::write(address))),
+            HostBus::I2c(i2c) => i2c.write_register(address, data),
+        }
+    }
+}
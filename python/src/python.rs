@@ -0,0 +1,150 @@
+use icm42688p::{
+    AccelODR, AccelRange, Error, GyroODR, GyroRange, I2cAddress, Icm42688p, PowerMode, Units,
+};
+use linux_embedded_hal::Delay;
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+};
+
+use crate::HostBus;
+
+fn py_err(error: Error) -> PyErr {
+    PyIOError::new_err(format!("{error:?}"))
+}
+
+fn accel_range(g: u32) -> PyResult<AccelRange> {
+    Ok(match g {
+        16 => AccelRange::G16,
+        8 => AccelRange::G8,
+        4 => AccelRange::G4,
+        2 => AccelRange::G2,
+        _ => return Err(PyValueError::new_err("accel range must be 2, 4, 8 or 16 g")),
+    })
+}
+
+fn gyro_range(dps: f32) -> PyResult<GyroRange> {
+    let ranges = [
+        (2000.0, GyroRange::Dps2000),
+        (1000.0, GyroRange::Dps1000),
+        (500.0, GyroRange::Dps500),
+        (250.0, GyroRange::Dps250),
+        (125.0, GyroRange::Dps125),
+        (62.5, GyroRange::Dps62_5),
+        (31.25, GyroRange::Dps31_25),
+        (15.625, GyroRange::Dps15_625),
+    ];
+    ranges
+        .into_iter()
+        .find(|(value, _)| *value == dps)
+        .map(|(_, range)| range)
+        .ok_or_else(|| PyValueError::new_err("unsupported gyro range"))
+}
+
+/// Looks up an ODR by its frequency among the register values.
+fn odr<T: TryFrom<u8>>(hz: f32, odr_hz: impl Fn(&T) -> f32) -> PyResult<T> {
+    (1..=15)
+        .filter_map(|bits| T::try_from(bits).ok())
+        .find(|odr| odr_hz(odr) == hz)
+        .ok_or_else(|| PyValueError::new_err("unsupported ODR"))
+}
+
+/// ICM-42688-P on a Linux host, readings in g, °/s and °C.
+#[pyclass(name = "Icm42688p", unsendable)]
+struct PyIcm42688p {
+    imu: Icm42688p<HostBus>,
+}
+
+impl PyIcm42688p {
+    fn open(bus: std::io::Result<HostBus>) -> PyResult<Self> {
+        let bus = bus.map_err(|error| PyIOError::new_err(error.to_string()))?;
+        let imu = Icm42688p::with_interface(bus, Units::default(), &mut Delay).map_err(py_err)?;
+        Ok(Self { imu })
+    }
+}
+
+#[pymethods]
+impl PyIcm42688p {
+    /// Opens the sensor on a spidev device, e.g. `/dev/spidev0.0`.
+    #[new]
+    #[pyo3(signature = (path, max_speed_hz = 1_000_000))]
+    fn new(path: &str, max_speed_hz: u32) -> PyResult<Self> {
+        Self::open(HostBus::open_spi(path, max_speed_hz))
+    }
+
+    /// Opens the sensor on an i2c-dev bus, e.g. `/dev/i2c-1`.
+    #[staticmethod]
+    #[pyo3(signature = (path, ad0_high = false))]
+    fn i2c(path: &str, ad0_high: bool) -> PyResult<Self> {
+        let address = if ad0_high {
+            I2cAddress::Ad0High
+        } else {
+            I2cAddress::Ad0Low
+        };
+        Self::open(HostBus::open_i2c(path, address))
+    }
+
+    fn device_id(&mut self) -> PyResult<u8> {
+        self.imu.device_id().map_err(py_err)
+    }
+
+    /// Returns `((ax, ay, az), (gx, gy, gz))`, or `None` if no new sample is available.
+    #[allow(clippy::type_complexity)]
+    fn read_sample(&mut self) -> PyResult<Option<((f32, f32, f32), (f32, f32, f32))>> {
+        let sample = self.imu.read_sample().map_err(py_err)?;
+        Ok(sample.map(|sample| (sample.acceleration, sample.angular_velocity)))
+    }
+
+    fn raw_acceleration(&mut self) -> PyResult<(i16, i16, i16)> {
+        self.imu.raw_acceleration().map_err(py_err)
+    }
+
+    fn raw_angular_velocity(&mut self) -> PyResult<(i16, i16, i16)> {
+        self.imu.raw_angular_velocity().map_err(py_err)
+    }
+
+    fn temperature(&mut self) -> PyResult<f32> {
+        self.imu.temperature().map_err(py_err)
+    }
+
+    /// Sets the accel full scale range, in g.
+    fn set_accel_range(&mut self, g: u32) -> PyResult<()> {
+        self.imu.set_accel_range(accel_range(g)?).map_err(py_err)
+    }
+
+    /// Sets the gyro full scale range, in °/s.
+    fn set_gyro_range(&mut self, dps: f32) -> PyResult<()> {
+        self.imu.set_gyro_range(gyro_range(dps)?).map_err(py_err)
+    }
+
+    /// Sets the accel ODR, in Hz.
+    fn set_accel_odr(&mut self, hz: f32) -> PyResult<()> {
+        let odr = odr(hz, AccelODR::hz)?;
+        self.imu.set_accel_odr(odr).map_err(py_err)
+    }
+
+    /// Sets the gyro ODR, in Hz.
+    fn set_gyro_odr(&mut self, hz: f32) -> PyResult<()> {
+        let odr = odr(hz, GyroODR::hz)?;
+        self.imu.set_gyro_odr(odr).map_err(py_err)
+    }
+
+    /// Turns off both sensors.
+    fn sleep(&mut self) -> PyResult<()> {
+        self.imu
+            .set_power_mode(PowerMode::Sleep, &mut Delay)
+            .map_err(py_err)
+    }
+
+    /// Turns on both sensors in low noise mode.
+    fn wake(&mut self) -> PyResult<()> {
+        self.imu
+            .set_power_mode(PowerMode::SixAxisLowNoise, &mut Delay)
+            .map_err(py_err)
+    }
+}
+
+#[pymodule]
+fn icm42688p(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyIcm42688p>()
+}
@@ -0,0 +1,86 @@
+use crate::{error::Error, register::APEX_DATA4};
+
+/// Accelerometer axis on which the tap energy is concentrated
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TapAxis {
+    X,
+    Y,
+    Z,
+}
+
+/// Polarity of the tap pulse
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TapDirection {
+    Positive,
+    Negative,
+}
+
+/// Number of taps in a tap event
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TapKind {
+    Single,
+    Double,
+}
+
+/// Axes whose threshold must be crossed to raise a wake on motion event
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum WomAxes {
+    /// Any axis, the default.
+    #[default]
+    Any = 0,
+    /// All three axes at once, which ignores motions along a single axis.
+    All = 1,
+}
+
+/// Sample the accel data is compared to for wake on motion
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum WomReference {
+    /// The first sample after wake on motion was enabled, detecting a slow drift away from the
+    /// rest position, e.g. a tilt.
+    Initial = 0,
+    /// The previous sample, detecting sudden changes only, the default.
+    #[default]
+    Previous = 1,
+}
+
+/// Wake on motion comparison options
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct WomConfig {
+    pub axes: WomAxes,
+    pub reference: WomReference,
+}
+
+/// Tap reported by the APEX tap detection.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TapEvent {
+    pub axis: TapAxis,
+    pub direction: TapDirection,
+    pub kind: TapKind,
+}
+
+impl TapEvent {
+    /// Decodes the APEX_DATA4 register, `None` if it reports no tap.
+    pub(crate) fn from_apex_data4(value: u8) -> Result<Option<Self>, Error> {
+        let kind = match APEX_DATA4::TAP_NUM.get(value) {
+            0b00 => return Ok(None),
+            0b01 => TapKind::Single,
+            0b10 => TapKind::Double,
+            _ => return Err(Error::DataCorrupted),
+        };
+        let axis = match APEX_DATA4::TAP_AXIS.get(value) {
+            0b00 => TapAxis::X,
+            0b01 => TapAxis::Y,
+            0b10 => TapAxis::Z,
+            _ => return Err(Error::DataCorrupted),
+        };
+        let direction = match APEX_DATA4::TAP_DIR.get(value) {
+            0 => TapDirection::Positive,
+            _ => TapDirection::Negative,
+        };
+        Ok(Some(Self {
+            axis,
+            direction,
+            kind,
+        }))
+    }
+}
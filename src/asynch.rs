@@ -0,0 +1,242 @@
+use core::{
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use embedded_hal_async::{delay::DelayNs, digital::Wait};
+use futures_core::Stream;
+
+use crate::{Error, Icm42688p, Interface, InterruptPin, PinAction, PinFault, SensorData, TapEvent};
+
+impl<I> Icm42688p<I>
+where
+    I: Interface,
+{
+    /// Enables APEX tap detection on `pin` and returns the taps signalled on it.
+    ///
+    /// `int` is the host input connected to `pin`. See [`Self::enable_tap_detection`] for the
+    /// configuration applied to the sensor.
+    pub async fn tap_events<'a, P>(
+        &'a mut self,
+        pin: InterruptPin,
+        int: &'a mut P,
+        delay: &mut impl DelayNs,
+    ) -> Result<TapEvents<'a, I, P>, Error>
+    where
+        P: Wait,
+    {
+        self.driver().prepare_tap_detection()?;
+        delay.delay_ms(1).await;
+        self.driver().configure_tap_detection()?;
+        delay.delay_ms(1).await;
+        self.driver().route_tap_interrupt(pin)?;
+        delay.delay_ms(50).await;
+        self.driver().enable_tap_detection()?;
+        Ok(TapEvents { imu: self, int })
+    }
+}
+
+/// Stream of taps, created by [`Icm42688p::tap_events`].
+pub struct TapEvents<'a, I, P> {
+    imu: &'a mut Icm42688p<I>,
+    int: &'a mut P,
+}
+
+impl<I, P> TapEvents<'_, I, P>
+where
+    I: Interface,
+    P: Wait,
+{
+    /// Waits for the next tap.
+    pub async fn next(&mut self) -> Result<TapEvent, Error> {
+        loop {
+            self.int.wait_for_high().await.map_err(|_| {
+                Error::Pin(PinFault {
+                    action: PinAction::InterruptWait,
+                    access: None,
+                })
+            })?;
+            if let Some(event) = self.imu.tap_event()? {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+impl<I> Icm42688p<I>
+where
+    I: Interface,
+{
+    /// Routes the data ready interrupt to `pin` and returns the samples signalled on it, see
+    /// [`Self::sample`].
+    ///
+    /// `int` is the host input connected to `pin`. The ranges are read once, they must not change
+    /// while streaming.
+    pub fn sample_stream<'a, P>(
+        &'a mut self,
+        pin: InterruptPin,
+        int: &'a mut P,
+    ) -> Result<
+        SampleStream<'a, I, PinWakeup<'a, P>, impl SampleRead<'a, I, PinWakeup<'a, P>>>,
+        Error,
+    >
+    where
+        P: Wait,
+    {
+        self.driver().route_data_ready_interrupt(pin)?;
+        SampleStream::new(self, PinWakeup(int), next_sample)
+    }
+
+    /// Same as [`Self::sample_stream`], polling the data ready flag every 100µs instead of
+    /// waiting for an interrupt.
+    pub fn sample_stream_polled<'a, D>(
+        &'a mut self,
+        delay: &'a mut D,
+    ) -> Result<
+        SampleStream<'a, I, DelayWakeup<'a, D>, impl SampleRead<'a, I, DelayWakeup<'a, D>>>,
+        Error,
+    >
+    where
+        D: DelayNs,
+    {
+        SampleStream::new(self, DelayWakeup(delay), next_sample)
+    }
+}
+
+/// Event a [`SampleStream`] waits for before checking the data ready flag.
+pub trait Wakeup {
+    fn wait(&mut self) -> impl Future<Output = Result<(), Error>>;
+}
+
+/// Data ready interrupt input, see [`Icm42688p::sample_stream`].
+pub struct PinWakeup<'a, P>(&'a mut P);
+
+impl<P> Wakeup for PinWakeup<'_, P>
+where
+    P: Wait,
+{
+    async fn wait(&mut self) -> Result<(), Error> {
+        self.0.wait_for_high().await.map_err(|_| {
+            Error::Pin(PinFault {
+                action: PinAction::InterruptWait,
+                access: None,
+            })
+        })
+    }
+}
+
+/// Polling interval, see [`Icm42688p::sample_stream_polled`].
+pub struct DelayWakeup<'a, D>(&'a mut D);
+
+impl<D> Wakeup for DelayWakeup<'_, D>
+where
+    D: DelayNs,
+{
+    async fn wait(&mut self) -> Result<(), Error> {
+        self.0.delay_us(100).await;
+        Ok(())
+    }
+}
+
+/// Outcome of the read of a [`SampleStream`], handing the driver and the wakeup back to the
+/// stream.
+pub type Step<'a, I, W> = (Result<SensorData, Error>, &'a mut Icm42688p<I>, W);
+
+/// Future of the read in progress of a [`SampleStream`].
+pub trait SampleRead<'a, I: 'a, W>: Future<Output = Step<'a, I, W>> {}
+
+impl<'a, I: 'a, W, F> SampleRead<'a, I, W> for F where F: Future<Output = Step<'a, I, W>> {}
+
+/// Waits for the next sample, `factors` being the accel and gyro sensitivities.
+async fn next_sample<'a, I, W>(
+    imu: &'a mut Icm42688p<I>,
+    mut wakeup: W,
+    (accel_factor, gyro_factor): (f32, f32),
+) -> Step<'a, I, W>
+where
+    I: Interface,
+    W: Wakeup,
+{
+    let result = async {
+        while !imu.data_ready()? {
+            wakeup.wait().await?;
+        }
+        imu.driver().sample_scaled(accel_factor, gyro_factor)
+    }
+    .await;
+    (result, imu, wakeup)
+}
+
+/// Stream of new samples, created by [`Icm42688p::sample_stream`] or
+/// [`Icm42688p::sample_stream_polled`].
+///
+/// Implements `futures_core::Stream`, so it can be consumed with the `futures` combinators, e.g.
+/// `while let Some(sample) = stream.next().await` with `StreamExt` in an embassy task. The
+/// stream never ends, a failed read yields the error and the next poll reads again. `F` is the
+/// future of the read in progress, named by the constructors. It is not `Unpin`, pin the stream
+/// first, e.g. with `core::pin::pin!`.
+pub struct SampleStream<'a, I, W, F> {
+    read: fn(&'a mut Icm42688p<I>, W, (f32, f32)) -> F,
+    factors: (f32, f32),
+    state: StreamState<'a, I, W, F>,
+}
+
+enum StreamState<'a, I, W, F> {
+    Idle(&'a mut Icm42688p<I>, W),
+    Reading(F),
+    /// Transient, while moving the driver from `Idle` to `Reading`.
+    Moving,
+}
+
+impl<'a, I, W, F> SampleStream<'a, I, W, F>
+where
+    I: Interface,
+{
+    fn new(
+        imu: &'a mut Icm42688p<I>,
+        wakeup: W,
+        read: fn(&'a mut Icm42688p<I>, W, (f32, f32)) -> F,
+    ) -> Result<Self, Error> {
+        let accel_factor = imu.driver().accel_sensitivity()?;
+        let gyro_factor = imu.driver().gyro_sensitivity()?;
+        Ok(Self {
+            read,
+            factors: (accel_factor, gyro_factor),
+            state: StreamState::Idle(imu, wakeup),
+        })
+    }
+}
+
+impl<'a, I, W, F> Stream for SampleStream<'a, I, W, F>
+where
+    F: SampleRead<'a, I, W>,
+{
+    type Item = Result<SensorData, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // SAFETY: the read future is only accessed pinned and is never moved out of `state`, it
+        // is dropped in place when `state` is overwritten.
+        let this = unsafe { self.get_unchecked_mut() };
+        if let StreamState::Idle(..) = this.state {
+            let StreamState::Idle(imu, wakeup) = mem::replace(&mut this.state, StreamState::Moving)
+            else {
+                unreachable!()
+            };
+            this.state = StreamState::Reading((this.read)(imu, wakeup, this.factors));
+        }
+        let StreamState::Reading(read) = &mut this.state else {
+            unreachable!()
+        };
+        // SAFETY: see above.
+        let read = unsafe { Pin::new_unchecked(read) };
+        match read.poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready((result, imu, wakeup)) => {
+                this.state = StreamState::Idle(imu, wakeup);
+                Poll::Ready(Some(result))
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    error::BusOperation,
+    interface::{Bank, RegAddr},
+};
+
+/// Number of register accesses kept by the audit log.
+pub const AUDIT_LOG_LEN: usize = 32;
+
+/// Register access recorded by the audit log, see [`crate::Icm42688p::audit_log`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RegisterAccess {
+    /// Bank the register was accessed in, REG_BANK_SEL writes record the selected bank.
+    pub bank: Bank,
+    /// Address of the first register accessed.
+    pub address: RegAddr,
+    pub operation: BusOperation,
+    /// Number of bytes transferred, more than one for burst reads.
+    pub length: usize,
+    /// Byte written, or first byte read, undefined for a failed read.
+    pub value: u8,
+    /// Whether the interface reported success.
+    pub ok: bool,
+}
+
+/// Ring buffer of the last [`AUDIT_LOG_LEN`] register accesses.
+#[derive(Clone, Copy)]
+pub(crate) struct AuditLog {
+    entries: [Option<RegisterAccess>; AUDIT_LOG_LEN],
+    /// Index of the next entry to overwrite.
+    next: usize,
+}
+
+impl AuditLog {
+    pub const fn new() -> Self {
+        Self {
+            entries: [None; AUDIT_LOG_LEN],
+            next: 0,
+        }
+    }
+
+    pub fn record(&mut self, access: RegisterAccess) {
+        self.entries[self.next] = Some(access);
+        self.next = (self.next + 1) % AUDIT_LOG_LEN;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Recorded accesses, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = RegisterAccess> + '_ {
+        let (newer, older) = self.entries.split_at(self.next);
+        older.iter().chain(newer).flatten().copied()
+    }
+}
@@ -0,0 +1,82 @@
+//! Bus time measurements.
+//!
+//! [`run`] times the main data path and configuration calls with a clock provided by the
+//! application, to check that the bus setup reaches the loop rate it needs.
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{Error, Icm42688p, Interface};
+
+/// Number of calls each average is computed over.
+const ITERATIONS: u32 = 16;
+
+/// Time the FIFO is left filling before each timed drain, unit: ms
+const FIFO_FILL_TIMES_MS: [u32; 3] = [1, 4, 16];
+
+/// Timing of a FIFO drain.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct DrainTiming {
+    /// Number of bytes drained.
+    pub bytes: usize,
+    /// unit: µs
+    pub duration_us: u32,
+}
+
+/// Average duration of each call, unit: µs
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct BenchmarkReport {
+    pub read_sample_us: u32,
+    pub read_sample_with_temp_us: u32,
+    /// Read-modify-write of a configuration register.
+    pub config_write_us: u32,
+    /// Drains after letting the FIFO fill for increasing times.
+    pub fifo_drains: [DrainTiming; FIFO_FILL_TIMES_MS.len()],
+}
+
+/// Times the driver calls on `imu`.
+///
+/// `clock` returns a monotonic time in µs. The FIFO must be configured by the caller for the
+/// drains to have data, the sensor configuration is otherwise left unchanged.
+pub fn run<I>(
+    imu: &mut Icm42688p<I>,
+    clock: &mut impl FnMut() -> u64,
+    delay: &mut impl DelayNs,
+) -> Result<BenchmarkReport, Error>
+where
+    I: Interface,
+{
+    let read_sample_us = average(clock, || imu.read_sample().map(|_| ()))?;
+    let read_sample_with_temp_us = average(clock, || imu.read_sample_with_temp().map(|_| ()))?;
+    let range = imu.accel_range()?;
+    let config_write_us = average(clock, || imu.set_accel_range(range))?;
+
+    let mut fifo_drains = [DrainTiming::default(); FIFO_FILL_TIMES_MS.len()];
+    for (timing, fill_ms) in fifo_drains.iter_mut().zip(FIFO_FILL_TIMES_MS) {
+        imu.drain_fifo(|_| {})?;
+        delay.delay_ms(fill_ms);
+        let start = clock();
+        let stats = imu.drain_fifo(|_| {})?;
+        *timing = DrainTiming {
+            bytes: stats.bytes,
+            duration_us: (clock() - start) as u32,
+        };
+    }
+
+    Ok(BenchmarkReport {
+        read_sample_us,
+        read_sample_with_temp_us,
+        config_write_us,
+        fifo_drains,
+    })
+}
+
+fn average(
+    clock: &mut impl FnMut() -> u64,
+    mut call: impl FnMut() -> Result<(), Error>,
+) -> Result<u32, Error> {
+    let start = clock();
+    for _ in 0..ITERATIONS {
+        call()?;
+    }
+    Ok(((clock() - start) / ITERATIONS as u64) as u32)
+}
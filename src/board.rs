@@ -0,0 +1,56 @@
+//! Defaults of common breakout boards and modules.
+
+use crate::interface::I2cAddress;
+
+/// I2C pull-up resistors of a [`Board`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PullUps {
+    /// Resistors on the board, usually disconnected by cutting a jumper, unit: Ω
+    OnBoard { ohms: u32 },
+    /// No resistors on the board, the host must provide them, e.g. 4.7kΩ to VDDIO at 100kHz
+    /// or 2.2kΩ at 400kHz.
+    External,
+}
+
+/// Breakout board or module wired to the host over I2C
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Board {
+    /// SparkFun Qwiic breakout with the ADR jumper untouched.
+    SparkFunQwiic,
+    /// SparkFun Qwiic breakout with the ADR jumper changed to the alternate address.
+    SparkFunQwiicAlternate,
+    /// Bare module exposing the AP_AD0 pin, tied low or high by the host.
+    Module(I2cAddress),
+}
+
+impl Board {
+    /// I2C address the sensor answers on.
+    pub const fn i2c_address(self) -> I2cAddress {
+        match self {
+            Board::SparkFunQwiic => I2cAddress::Ad0Low,
+            Board::SparkFunQwiicAlternate => I2cAddress::Ad0High,
+            Board::Module(address) => address,
+        }
+    }
+
+    /// Pull-up resistors of the SDA and SCL lines.
+    pub const fn pull_ups(self) -> PullUps {
+        match self {
+            Board::SparkFunQwiic | Board::SparkFunQwiicAlternate => {
+                PullUps::OnBoard { ohms: 2_200 }
+            }
+            Board::Module(_) => PullUps::External,
+        }
+    }
+
+    /// Highest I2C clock frequency the board is wired for, unit: Hz
+    ///
+    /// Qwiic cables and the on-board pull-ups are specified for fast mode, bare modules are
+    /// limited by the sensor, see [`crate::limits::I2C_MAX_FREQ_HZ`].
+    pub const fn max_i2c_freq_hz(self) -> u32 {
+        match self {
+            Board::SparkFunQwiic | Board::SparkFunQwiicAlternate => 400_000,
+            Board::Module(_) => crate::limits::I2C_MAX_FREQ_HZ,
+        }
+    }
+}
@@ -1,6 +1,15 @@
-use crate::error::Error;
+use core::ops::Neg;
+
+use bitflags::bitflags;
+
+use crate::{error::Error, limits, MicrosDurationU32};
+
+/// Standard gravity, unit: m/s²
+pub const STANDARD_GRAVITY: f32 = 9.80665;
 
 /// Accelerometer output data rate
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[non_exhaustive]
 pub enum AccelODR {
     Hz32k = 1,
     Hz16k = 2,
@@ -23,6 +32,41 @@ pub enum AccelODR {
     Hz500 = 15,
 }
 
+impl AccelODR {
+    /// Output data rate, unit: Hz
+    pub fn hz(&self) -> f32 {
+        use AccelODR as E;
+        match self {
+            E::Hz32k => 32000.0,
+            E::Hz16k => 16000.0,
+            E::Hz8k => 8000.0,
+            E::Hz4k => 4000.0,
+            E::Hz2k => 2000.0,
+            E::Hz1k => 1000.0,
+            E::Hz200 => 200.0,
+            E::Hz100 => 100.0,
+            E::Hz50 => 50.0,
+            E::Hz25 => 25.0,
+            E::Hz12_5 => 12.5,
+            E::Hz6_25 => 6.25,
+            E::Hz3_125 => 3.125,
+            E::Hz1_5625 => 1.5625,
+            E::Hz500 => 500.0,
+        }
+    }
+
+    /// Whether the accelerometer supports this ODR in `mode`.
+    ///
+    /// 1kHz and above require low noise mode, below 12.5Hz require low power mode.
+    pub fn supported_in(&self, mode: &PowerMode) -> bool {
+        match mode.bits() & 0b11 {
+            0b10 => self.hz() <= limits::ACCEL_LP_ODR_MAX_HZ,
+            0b11 => self.hz() >= limits::ACCEL_LN_ODR_MIN_HZ,
+            _ => true,
+        }
+    }
+}
+
 impl TryFrom<u8> for AccelODR {
     type Error = Error;
 
@@ -49,7 +93,8 @@ impl TryFrom<u8> for AccelODR {
 }
 
 /// Accel full-scale range
-#[derive(Default)]
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[non_exhaustive]
 pub enum AccelRange {
     /// ±16g
     #[default]
@@ -73,6 +118,17 @@ impl AccelRange {
             E::G2 => 16384.0,
         }
     }
+
+    /// Sensitivity scale factor as a `(numerator, denominator)` ratio, unit: LSB/g
+    pub fn sensitivity_ratio(&self) -> (u32, u32) {
+        use AccelRange as E;
+        match &self {
+            E::G16 => (2048, 1),
+            E::G8 => (4096, 1),
+            E::G4 => (8192, 1),
+            E::G2 => (16384, 1),
+        }
+    }
 }
 
 impl TryFrom<u8> for AccelRange {
@@ -91,6 +147,10 @@ impl TryFrom<u8> for AccelRange {
 }
 
 /// Gyroscope output data rate
+///
+/// All rates are supported in low noise mode, the only mode with the gyroscope running.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[non_exhaustive]
 pub enum GyroODR {
     Hz32k = 1,
     Hz16k = 2,
@@ -107,6 +167,27 @@ pub enum GyroODR {
     Hz500 = 15,
 }
 
+impl GyroODR {
+    /// Output data rate, unit: Hz
+    pub fn hz(&self) -> f32 {
+        use GyroODR as E;
+        match self {
+            E::Hz32k => 32000.0,
+            E::Hz16k => 16000.0,
+            E::Hz8k => 8000.0,
+            E::Hz4k => 4000.0,
+            E::Hz2k => 2000.0,
+            E::Hz1k => 1000.0,
+            E::Hz200 => 200.0,
+            E::Hz100 => 100.0,
+            E::Hz50 => 50.0,
+            E::Hz25 => 25.0,
+            E::Hz12_5 => 12.5,
+            E::Hz500 => 500.0,
+        }
+    }
+}
+
 impl TryFrom<u8> for GyroODR {
     type Error = Error;
 
@@ -131,7 +212,8 @@ impl TryFrom<u8> for GyroODR {
 }
 
 /// Gyro full-scale range
-#[derive(Default)]
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[non_exhaustive]
 pub enum GyroRange {
     /// ±2000°/s
     #[default]
@@ -167,6 +249,21 @@ impl GyroRange {
             E::Dps15_625 => 2097.2,
         }
     }
+
+    /// Sensitivity scale factor as a `(numerator, denominator)` ratio, unit: LSB/(º/s)
+    pub fn sensitivity_ratio(&self) -> (u32, u32) {
+        use GyroRange as E;
+        match &self {
+            E::Dps2000 => (164, 10),
+            E::Dps1000 => (328, 10),
+            E::Dps500 => (655, 10),
+            E::Dps250 => (131, 1),
+            E::Dps125 => (262, 1),
+            E::Dps62_5 => (5243, 10),
+            E::Dps31_25 => (10486, 10),
+            E::Dps15_625 => (20972, 10),
+        }
+    }
 }
 
 impl TryFrom<u8> for GyroRange {
@@ -189,36 +286,719 @@ impl TryFrom<u8> for GyroRange {
 }
 
 /// Standard power modes
-#[derive(PartialEq, Default)]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[non_exhaustive]
 pub enum PowerMode {
     /// Gyroscope: OFF, Accelerometer: OFF
     #[default]
-    Sleep = 0b0000,
+    Sleep,
     /// Gyroscope: DRIVE ON, Accelerometer: OFF
-    Standby = 0b0100,
+    Standby,
     /// Gyroscope: OFF, Accelerometer: DUTY-CYCLED
-    AccelLowPower = 0b0010,
+    AccelLowPower,
     /// Gyroscope: OFF, Accelerometer: ON
-    AccelLowNoise = 0b0011,
+    AccelLowNoise,
     /// Gyroscope: ON, Accelerometer: OFF
-    GyroLowNoise = 0b1100,
+    GyroLowNoise,
     /// Gyroscope: ON, Accelerometer: ON
-    SixAxisLowNoise = 0b1111,
+    SixAxisLowNoise,
+    /// Any other GYRO_MODE and ACCEL_MODE combination, e.g. gyroscope in standby with the
+    /// accelerometer running
+    Unknown(u8),
+}
+
+impl PowerMode {
+    /// GYRO_MODE and ACCEL_MODE bits of PWR_MGMT0.
+    pub const fn bits(&self) -> u8 {
+        use PowerMode as E;
+        match self {
+            E::Sleep => 0b0000,
+            E::Standby => 0b0100,
+            E::AccelLowPower => 0b0010,
+            E::AccelLowNoise => 0b0011,
+            E::GyroLowNoise => 0b1100,
+            E::SixAxisLowNoise => 0b1111,
+            E::Unknown(bits) => *bits & 0x0F,
+        }
+    }
+}
+
+impl From<u8> for PowerMode {
+    fn from(value: u8) -> Self {
+        use PowerMode as E;
+        match value {
+            0b0000 => E::Sleep,
+            0b0100 => E::Standby,
+            0b0010 => E::AccelLowPower,
+            0b0011 => E::AccelLowNoise,
+            0b1100 => E::GyroLowNoise,
+            0b1111 => E::SixAxisLowNoise,
+            _ => E::Unknown(value),
+        }
+    }
+}
+
+/// Sensor configuration applied at once by [`crate::Icm42688p::apply_config`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Config {
+    pub accel_range: AccelRange,
+    pub accel_odr: AccelODR,
+    pub gyro_range: GyroRange,
+    pub gyro_odr: GyroODR,
+    pub power_mode: PowerMode,
+}
+
+impl Config {
+    /// Closest configuration the sensor accepts, given the sensors left enabled by
+    /// `capabilities`.
+    pub(crate) fn adjusted(&self, capabilities: Capabilities) -> ConfigReport {
+        let mut applied = *self;
+        let mut adjustments = ConfigAdjustments::empty();
+
+        let mut mode = self.power_mode.bits();
+        if !capabilities.accel && mode & 0b0011 != 0 {
+            mode &= !0b0011;
+            adjustments |= ConfigAdjustments::AccelRefused;
+        }
+        if !capabilities.gyro && mode & 0b1100 != 0 {
+            mode &= !0b1100;
+            adjustments |= ConfigAdjustments::GyroRefused;
+        }
+        applied.power_mode = PowerMode::from(mode);
+
+        if !applied.accel_odr.supported_in(&applied.power_mode) {
+            if applied.accel_odr.hz() > limits::ACCEL_LP_ODR_MAX_HZ {
+                applied.accel_odr = AccelODR::Hz500;
+                adjustments |= ConfigAdjustments::AccelOdrLowered;
+            } else {
+                applied.accel_odr = AccelODR::Hz12_5;
+                adjustments |= ConfigAdjustments::AccelOdrRaised;
+            }
+        }
+        ConfigReport {
+            applied,
+            adjustments,
+        }
+    }
+}
+
+impl Default for Config {
+    /// Configuration set up by the constructors.
+    fn default() -> Self {
+        Self {
+            accel_range: AccelRange::default(),
+            accel_odr: AccelODR::Hz1k,
+            gyro_range: GyroRange::default(),
+            gyro_odr: GyroODR::Hz1k,
+            power_mode: PowerMode::SixAxisLowNoise,
+        }
+    }
+}
+
+bitflags! {
+    /// Settings of a [`Config`] changed by [`crate::Icm42688p::apply_config`].
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct ConfigAdjustments: u8 {
+        /// The accel ODR is above the low power maximum, 500Hz was applied.
+        const AccelOdrLowered = 1 << 0;
+        /// The accel ODR is below the low noise minimum, 12.5Hz was applied.
+        const AccelOdrRaised = 1 << 1;
+        /// The power mode turns on the accelerometer disabled by
+        /// [`crate::Icm42688p::restrict_capabilities`], it was left off.
+        const AccelRefused = 1 << 2;
+        /// The power mode turns on the gyroscope disabled by
+        /// [`crate::Icm42688p::restrict_capabilities`], it was left off.
+        const GyroRefused = 1 << 3;
+    }
+}
+
+/// Outcome of [`crate::Icm42688p::apply_config`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ConfigReport {
+    /// Configuration written to the sensor.
+    pub applied: Config,
+    pub adjustments: ConfigAdjustments,
+}
+
+impl ConfigReport {
+    /// Whether the requested configuration was applied as is.
+    pub fn is_exact(&self) -> bool {
+        self.adjustments.is_empty()
+    }
+}
+
+/// Acceleration output unit
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[non_exhaustive]
+pub enum AccelUnit {
+    /// Standard gravity (g)
+    #[default]
+    G,
+    /// m/s²
+    MetersPerSecondSquared,
+}
+
+impl AccelUnit {
+    /// Converts a value in g to this unit.
+    pub fn from_g(&self, value: f32) -> f32 {
+        match self {
+            AccelUnit::G => value,
+            AccelUnit::MetersPerSecondSquared => value * STANDARD_GRAVITY,
+        }
+    }
+}
+
+/// Angular velocity output unit
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[non_exhaustive]
+pub enum GyroUnit {
+    /// °/s
+    #[default]
+    DegreesPerSecond,
+    /// rad/s
+    RadiansPerSecond,
+}
+
+impl GyroUnit {
+    /// Converts a value in °/s to this unit.
+    pub fn from_dps(&self, value: f32) -> f32 {
+        match self {
+            GyroUnit::DegreesPerSecond => value,
+            GyroUnit::RadiansPerSecond => value.to_radians(),
+        }
+    }
+}
+
+/// Temperature output unit
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[non_exhaustive]
+pub enum TempUnit {
+    /// °C
+    #[default]
+    Celsius,
+    /// °F
+    Fahrenheit,
+    /// K
+    Kelvin,
+}
+
+impl TempUnit {
+    /// Converts a value in °C to this unit.
+    pub fn from_celsius(&self, value: f32) -> f32 {
+        match self {
+            TempUnit::Celsius => value,
+            TempUnit::Fahrenheit => value * 1.8 + 32.0,
+            TempUnit::Kelvin => value + 273.15,
+        }
+    }
+}
+
+/// Output units of the scaled sensor readings.
+///
+/// Chosen once at construction and applied to every scaled output of the driver.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Units {
+    pub accel: AccelUnit,
+    pub gyro: GyroUnit,
+    pub temperature: TempUnit,
+}
+
+/// Orientation of the sensor axes in the body frame
+///
+/// Each row gives a body axis as ±1 times one sensor axis, e.g. a sensor rotated 90° about Z
+/// maps body X to sensor Y and body Y to -sensor X:
+///
+/// ```
+/// # use icm42688p::MountingMatrix;
+/// let mounting = MountingMatrix::new([[0, 1, 0], [-1, 0, 0], [0, 0, 1]]).unwrap();
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MountingMatrix([[i8; 3]; 3]);
+
+impl MountingMatrix {
+    pub const IDENTITY: Self = Self([[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+
+    /// Returns `None` unless every row and every column holds a single ±1, and the determinant
+    /// is +1: a matrix of determinant -1 mirrors the axes, which no mounting of the sensor does.
+    pub const fn new(rows: [[i8; 3]; 3]) -> Option<Self> {
+        let mut i = 0;
+        while i < 3 {
+            let mut row_count = 0;
+            let mut column_count = 0;
+            let mut j = 0;
+            while j < 3 {
+                match rows[i][j] {
+                    0 => {}
+                    1 | -1 => row_count += 1,
+                    _ => return None,
+                }
+                if rows[j][i] != 0 {
+                    column_count += 1;
+                }
+                j += 1;
+            }
+            if row_count != 1 || column_count != 1 {
+                return None;
+            }
+            i += 1;
+        }
+        let [[a, b, c], [d, e, f], [g, h, k]] = rows;
+        let determinant = a * (e * k - f * h) - b * (d * k - f * g) + c * (d * h - e * g);
+        if determinant != 1 {
+            return None;
+        }
+        Some(Self(rows))
+    }
+
+    pub fn rows(&self) -> [[i8; 3]; 3] {
+        self.0
+    }
+
+    /// Mounting applying `self`, then `next`, e.g. a board mounting then a [`Frame`].
+    pub const fn then(self, next: Self) -> Self {
+        let mut rows = [[0; 3]; 3];
+        let mut i = 0;
+        while i < 3 {
+            let mut j = 0;
+            while j < 3 {
+                let mut k = 0;
+                while k < 3 {
+                    rows[i][j] += next.0[i][k] * self.0[k][j];
+                    k += 1;
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+        Self(rows)
+    }
+
+    /// Converts a vector from the sensor frame to the body frame.
+    pub fn apply<T>(&self, (x, y, z): (T, T, T)) -> (T, T, T)
+    where
+        T: Copy + Neg<Output = T>,
+    {
+        let sensor = [x, y, z];
+        let axis = |body: Axis| {
+            let (axis, negated) = self.source(body);
+            if negated {
+                -sensor[axis as usize]
+            } else {
+                sensor[axis as usize]
+            }
+        };
+        (axis(Axis::X), axis(Axis::Y), axis(Axis::Z))
+    }
+
+    /// Sensor axis a body axis is read from, and whether it is negated.
+    pub fn source(&self, body: Axis) -> (Axis, bool) {
+        match self.0[body as usize] {
+            [s, 0, 0] => (Axis::X, s < 0),
+            [0, s, 0] => (Axis::Y, s < 0),
+            [_, _, s] => (Axis::Z, s < 0),
+        }
+    }
+
+    /// Same as [`Self::apply`], saturating -32768 when it is negated.
+    pub fn apply_raw(&self, (x, y, z): (i16, i16, i16)) -> (i16, i16, i16) {
+        let saturate = |value: i32| value.clamp(i16::MIN.into(), i16::MAX.into()) as i16;
+        let (x, y, z) = self.apply((i32::from(x), i32::from(y), i32::from(z)));
+        (saturate(x), saturate(y), saturate(z))
+    }
+}
+
+impl Default for MountingMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
 }
 
-impl TryFrom<u8> for PowerMode {
+/// Sensors the driver is allowed to use
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Capabilities {
+    pub accel: bool,
+    pub gyro: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            accel: true,
+            gyro: true,
+        }
+    }
+}
+
+/// Axis convention of the outputs
+///
+/// The sensor frame has X to the right, Y forward and Z up when the package lies flat. A
+/// [`MountingMatrix`] brings it to the body in the same convention, the frame then converts it.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Frame {
+    /// X east (right), Y north (forward), Z up, the sensor convention.
+    #[default]
+    Enu,
+    /// X north (forward), Y east (right), Z down.
+    Ned,
+}
+
+impl From<Frame> for MountingMatrix {
+    fn from(frame: Frame) -> Self {
+        match frame {
+            Frame::Enu => MountingMatrix::IDENTITY,
+            Frame::Ned => MountingMatrix([[0, 1, 0], [1, 0, 0], [0, 0, -1]]),
+        }
+    }
+}
+
+/// Measurement axis
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Axis {
+    X = 0,
+    Y = 1,
+    Z = 2,
+}
+
+/// Interrupt output pin
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum InterruptPin {
+    Int1,
+    Int2,
+}
+
+/// Pulse duration of the pulsed interrupt mode
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[non_exhaustive]
+pub enum InterruptPulse {
+    /// 100µs, only for ODRs below 4kHz
+    #[default]
+    Us100 = 0,
+    /// 8µs
+    Us8 = 1,
+}
+
+/// Pulsed interrupt timing
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct InterruptTiming {
+    pub pulse: InterruptPulse,
+    /// Keeps the pin de-asserted for at least 100µs between pulses, only for ODRs below 4kHz.
+    pub min_deassert: bool,
+}
+
+impl InterruptTiming {
+    /// Fastest ODR supporting 100µs pulses and de-assertion, unit: Hz
+    const SLOW_TIMING_MAX_ODR_HZ: f32 = 4000.0;
+
+    /// Selects a timing a host detecting pulses of at least `min_pulse` can follow at
+    /// `odr_hz`, the fastest ODR of the enabled interrupt sources.
+    ///
+    /// Pulses are as long as possible. Returns [`Error::InterruptTiming`] when no pulse is long
+    /// enough, e.g. at 4kHz and above where only 8µs pulses are allowed. Latched interrupts
+    /// have no such limit.
+    pub fn for_host(min_pulse: MicrosDurationU32, odr_hz: f32) -> Result<Self, Error> {
+        let (pulse, pulse_us, min_deassert) = if odr_hz < Self::SLOW_TIMING_MAX_ODR_HZ {
+            (InterruptPulse::Us100, 100, true)
+        } else {
+            (InterruptPulse::Us8, 8, false)
+        };
+        if min_pulse.to_micros() > pulse_us {
+            return Err(Error::InterruptTiming);
+        }
+        Ok(Self {
+            pulse,
+            min_deassert,
+        })
+    }
+}
+
+/// Handling of the register bank after a failed bank switch
+///
+/// Either way the bank is marked unknown and selected again before the next register access, so
+/// bank 0 registers are never accessed while another bank is selected.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[non_exhaustive]
+pub enum BankRecovery {
+    /// Try to select bank 0 right away.
+    #[default]
+    Immediate,
+    /// Leave the recovery to the next register access.
+    Deferred,
+}
+
+/// Handling of the accel user offset on an accel range change, by
+/// [`crate::Icm42688p::set_accel_range`] or [`crate::Icm42688p::apply_config`]
+///
+/// The OFFSET_USER registers hold the offset in 0.5mg steps whatever the range, so a calibration
+/// in g stays valid across range switches, while its effect on the raw data scales with the
+/// sensitivity, see [`crate::Icm42688p::accel_offset_lsb`].
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[non_exhaustive]
+pub enum AccelOffsetOnRangeChange {
+    /// Write the offset set with [`crate::Icm42688p::set_accel_offset_g`] again.
+    #[default]
+    Reapply,
+    /// Clear the offset, e.g. before calibrating again at the new range.
+    Clear,
+}
+
+/// Source of the samples returned by [`crate::Icm42688p::read_sample`]
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[non_exhaustive]
+pub enum DataSource {
+    /// Latest data from the sensor data registers.
+    #[default]
+    Registers,
+    /// Oldest packet holding both accel and gyro data from the FIFO.
+    #[cfg(feature = "fifo")]
+    Fifo,
+}
+
+/// Host interface disabled through UI_SIFS_CFG
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DisabledInterface {
+    Spi = 0b10,
+    I2c = 0b11,
+}
+
+/// Host interfaces the sensor honors, read by [`crate::Icm42688p::active_interface`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ActiveInterface {
+    /// SPI is not disabled in UI_SIFS_CFG.
+    pub spi: bool,
+    /// I2C is not disabled in UI_SIFS_CFG.
+    pub i2c: bool,
+    /// The I3C slave is enabled.
+    pub i3c: bool,
+    /// SPI runs in 4-wire mode, otherwise in 3-wire mode.
+    pub spi_4wire: bool,
+}
+
+/// Byte order of 16-bit words
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum Endian {
+    Little = 0,
+    #[default]
+    Big = 1,
+}
+
+impl Endian {
+    /// Decodes a word from its bytes in address order.
+    pub(crate) fn decode(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        }
+    }
+
+    /// Decodes a two's complement sensor word from its bytes in address order.
+    pub(crate) fn decode_i16(self, bytes: [u8; 2]) -> i16 {
+        self.decode(bytes) as i16
+    }
+}
+
+impl From<u8> for Endian {
+    fn from(value: u8) -> Self {
+        if value & 1 == 0 {
+            Endian::Little
+        } else {
+            Endian::Big
+        }
+    }
+}
+
+/// Timestamp resolution
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[non_exhaustive]
+pub enum TimestampResolution {
+    /// 1µs
+    #[default]
+    Us1 = 0,
+    /// 16µs, or one RTC clock period when the RTC clock on CLKIN is used
+    Us16 = 1,
+}
+
+impl TimestampResolution {
+    /// Nominal duration of one timestamp tick, unit: µs
+    ///
+    /// Only exact with an RTC clock input at [`Self::Us1`], see [`Self::ticks_us`].
+    pub fn micros(&self) -> u32 {
+        match self {
+            TimestampResolution::Us1 => 1,
+            TimestampResolution::Us16 => 16,
+        }
+    }
+
+    /// Duration of `ticks` timestamp ticks, unit: µs
+    ///
+    /// `rtc_clock` tells whether the counter runs from an RTC clock on CLKIN, see
+    /// [`ClockStatus::uses_clkin`]. A [`Self::Us16`] tick then lasts one RTC clock period, about
+    /// 30.52µs at [`crate::limits::RTC_FREQ_HZ`]. Without it, the counter runs from the internal
+    /// clock and one tick lasts 32/30 of the nominal resolution (datasheet §12.7).
+    pub fn ticks_us(&self, ticks: u64, rtc_clock: bool) -> u64 {
+        match (self, rtc_clock) {
+            (TimestampResolution::Us1, true) => ticks,
+            (TimestampResolution::Us16, true) => {
+                ticks * 1_000_000 / crate::limits::RTC_FREQ_HZ as u64
+            }
+            (_, false) => ticks * self.micros() as u64 * 32 / 30,
+        }
+    }
+
+    /// Time elapsed between two 16-bit timestamps, unit: µs
+    ///
+    /// The timestamp counter wraps, so `cur` is assumed to be less than one wrap after `prev`.
+    /// See [`Self::ticks_us`] for `rtc_clock`.
+    pub fn delta_us(&self, prev: u16, cur: u16, rtc_clock: bool) -> u32 {
+        self.ticks_us(cur.wrapping_sub(prev) as u64, rtc_clock) as u32
+    }
+}
+
+impl TryFrom<u8> for TimestampResolution {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TimestampResolution::Us1),
+            1 => Ok(TimestampResolution::Us16),
+            _ => Err(Error::DataCorrupted),
+        }
+    }
+}
+
+/// Clock source selected in INTF_CONFIG1
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[non_exhaustive]
+pub enum ClockSource {
+    /// Internal RC oscillator
+    Rc = 0,
+    /// PLL when available, else internal RC oscillator
+    #[default]
+    PllOrRc = 1,
+    /// All clocks disabled
+    Disabled = 3,
+}
+
+impl TryFrom<u8> for ClockSource {
     type Error = Error;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        use PowerMode as E;
         match value {
-            0b0000 => Ok(E::Sleep),
-            0b0100 => Ok(E::Standby),
-            0b0010 => Ok(E::AccelLowPower),
-            0b0011 => Ok(E::AccelLowNoise),
-            0b1100 => Ok(E::GyroLowNoise),
-            0b1111 => Ok(E::SixAxisLowNoise),
+            0 => Ok(ClockSource::Rc),
+            1 => Ok(ClockSource::PllOrRc),
+            3 => Ok(ClockSource::Disabled),
             _ => Err(Error::DataCorrupted),
         }
     }
 }
+
+/// Clock of the accelerometer in low power mode
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AccelLpClock {
+    /// Wake-up oscillator, required by the APEX features
+    WakeUpOscillator = 0,
+    /// RC oscillator
+    RcOscillator = 1,
+}
+
+impl From<u8> for AccelLpClock {
+    fn from(value: u8) -> Self {
+        if value == 0 {
+            AccelLpClock::WakeUpOscillator
+        } else {
+            AccelLpClock::RcOscillator
+        }
+    }
+}
+
+/// Clock configuration as read back from the device
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ClockStatus {
+    /// Clock source
+    pub source: ClockSource,
+    /// Clock of the accelerometer in low power mode
+    pub accel_lp_clock: AccelLpClock,
+    /// An RTC clock input is required
+    pub rtc_mode: bool,
+    /// Pin 9 is configured as CLKIN
+    pub clkin_pin: bool,
+}
+
+impl ClockStatus {
+    /// Whether the device is set up to run from an external clock on CLKIN.
+    ///
+    /// The device has no lock indicator, so this only reports that both RTC_MODE and the
+    /// pin 9 function agree.
+    pub fn uses_clkin(&self) -> bool {
+        self.rtc_mode && self.clkin_pin
+    }
+}
+
+/// Resolution of accel and gyro data
+///
+/// The data registers are always 16-bit, the FIFO holds 20-bit data when FIFO_HIRES_EN is set.
+/// Both are scaled to the same units, see [`crate::Sample`].
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Resolution {
+    #[default]
+    Bits16,
+    Bits20,
+}
+
+/// Low latency option of the gyro UI filter
+///
+/// Both options replace the UI low-pass filter by a trivial decimation of the Dec2 filter
+/// output, trading noise and aliasing rejection for group delay.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[non_exhaustive]
+pub enum LowLatencyFilter {
+    /// Dec2 runs at max(400Hz, ODR), lowest delay but no rejection of noise above ODR/2.
+    #[default]
+    Dec2AtOdr = 14,
+    /// Dec2 runs at max(200Hz, 8 * ODR), slightly more delay but less aliasing.
+    Dec2At8xOdr = 15,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endian_decode() {
+        assert_eq!(Endian::Big.decode([0x12, 0x34]), 0x1234);
+        assert_eq!(Endian::Little.decode([0x12, 0x34]), 0x3412);
+        assert_eq!(Endian::Big.decode_i16([0xFF, 0xFE]), -2);
+        assert_eq!(Endian::Little.decode_i16([0xFE, 0xFF]), -2);
+        assert_eq!(Endian::Big.decode_i16([0x80, 0x00]), i16::MIN);
+    }
+
+    #[test]
+    fn timestamp_ticks_duration() {
+        assert_eq!(TimestampResolution::Us1.ticks_us(30, false), 32);
+        assert_eq!(TimestampResolution::Us1.ticks_us(30, true), 30);
+        assert_eq!(TimestampResolution::Us16.ticks_us(15, false), 256);
+        // One RTC clock period per tick.
+        assert_eq!(TimestampResolution::Us16.ticks_us(15, true), 457);
+        assert_eq!(TimestampResolution::Us16.ticks_us(32_768, true), 1_000_000);
+        // Wraps from 0xFFF0 to 0x000E.
+        assert_eq!(TimestampResolution::Us1.delta_us(0xFFF0, 0x000E, false), 32);
+    }
+
+    #[test]
+    fn mounting_matrix_is_a_rotation() {
+        assert!(MountingMatrix::new([[0, 1, 0], [-1, 0, 0], [0, 0, 1]]).is_some());
+        assert!(MountingMatrix::new([[-1, 0, 0], [0, -1, 0], [0, 0, 1]]).is_some());
+        // Mirrored axes.
+        assert_eq!(MountingMatrix::new([[0, 1, 0], [1, 0, 0], [0, 0, 1]]), None);
+        assert_eq!(
+            MountingMatrix::new([[-1, 0, 0], [0, 1, 0], [0, 0, 1]]),
+            None
+        );
+        // Two body axes read from the same sensor axis.
+        assert_eq!(MountingMatrix::new([[1, 0, 0], [1, 0, 0], [0, 0, 1]]), None);
+        assert_eq!(
+            MountingMatrix::from(Frame::Ned),
+            MountingMatrix::new(MountingMatrix::from(Frame::Ned).rows()).unwrap()
+        );
+    }
+}
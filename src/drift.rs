@@ -0,0 +1,76 @@
+use crate::{error::Error, Icm42688p, Interface, MicrosDurationU64};
+
+/// Snapshot recorded by [`DriftLogger`]
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct DriftRecord {
+    /// Time passed to [`DriftLogger::poll`], unit: µs
+    pub time_us: u64,
+    /// Unit: °C
+    pub temperature_c: f32,
+    /// Gyro bias estimate, `None` until one is available, see
+    /// [`Icm42688p::set_gyro_bias_tracking`], unit: °/s
+    pub gyro_bias_dps: Option<(f32, f32, f32)>,
+}
+
+/// Recorder of the temperature and gyro bias over time, to fit thermal bias models.
+///
+/// [`Self::poll`] is meant to be called from the application loop, it records a snapshot into
+/// the buffer once `interval` has elapsed since the previous one. The time is provided by the
+/// caller, e.g. `Icm42688p::device_time_us` when draining the FIFO, or a host clock. Recording
+/// stops once the buffer is full, [`Self::clear`] restarts it.
+pub struct DriftLogger<'a> {
+    records: &'a mut [DriftRecord],
+    len: usize,
+    interval: MicrosDurationU64,
+    /// Time of the last record.
+    last_us: Option<u64>,
+}
+
+impl<'a> DriftLogger<'a> {
+    pub fn new(records: &'a mut [DriftRecord], interval: MicrosDurationU64) -> Self {
+        Self {
+            records,
+            len: 0,
+            interval,
+            last_us: None,
+        }
+    }
+
+    /// Records a snapshot if one is due at `now_us`, and returns whether one was recorded.
+    ///
+    /// Only reads the temperature when a snapshot is due.
+    pub fn poll<I>(&mut self, imu: &mut Icm42688p<I>, now_us: u64) -> Result<bool, Error>
+    where
+        I: Interface,
+    {
+        let due = match self.last_us {
+            None => true,
+            Some(last) => now_us.wrapping_sub(last) >= self.interval.to_micros(),
+        };
+        if !due || self.is_full() {
+            return Ok(false);
+        }
+        self.records[self.len] = DriftRecord {
+            time_us: now_us,
+            temperature_c: imu.driver().temperature_celsius()?,
+            gyro_bias_dps: imu.state.gyro_bias.bias(),
+        };
+        self.len += 1;
+        self.last_us = Some(now_us);
+        Ok(true)
+    }
+
+    /// Snapshots recorded so far, oldest first.
+    pub fn records(&self) -> &[DriftRecord] {
+        &self.records[..self.len]
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.records.len()
+    }
+
+    /// Drops the recorded snapshots, e.g. once they were stored, the cadence is kept.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
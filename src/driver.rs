@@ -0,0 +1,2301 @@
+#[cfg(feature = "fixed")]
+use fixed::types::I16F16;
+
+#[cfg(feature = "apex")]
+use crate::apex::{TapEvent, WomConfig};
+#[cfg(feature = "audit")]
+use crate::audit::{AuditLog, RegisterAccess};
+#[cfg(feature = "fifo")]
+use crate::fifo::{
+    DrainBudget, DrainStats, FifoCountUnit, FifoEnables, FifoHeader, FifoMode, FifoPacket,
+    FifoPacketConfig, FifoPackets, FifoSources, FifoStatus, InvalidSampleMode, OdrEstimator,
+    HIRES_ACCEL_SENSITIVITY, HIRES_GYRO_SENSITIVITY,
+};
+#[cfg(feature = "latency")]
+use crate::latency::{aaf_delay_us, ui_filter_delay_us, GroupDelay};
+#[cfg(feature = "motion")]
+use crate::motion::{GyroBiasTracker, MotionDetector, MotionState};
+#[cfg(feature = "snapshot")]
+use crate::snapshot::{ConfigSnapshot, CONFIG_REGISTERS};
+use crate::{
+    config::*,
+    error::{BusOperation, Error, InterruptConflicts, Maintenance},
+    interface::{Interface, RegAddr, RegisterWriteObserver},
+    reader::{RawSensorData, RawTemperature, Sample, SampleWithTemp, SensorData},
+    register::*,
+};
+
+const ICM42688P_DEVICE_ID: u8 = 0x47;
+
+fn widen((x, y, z): (i16, i16, i16)) -> (i32, i32, i32) {
+    (x.into(), y.into(), z.into())
+}
+
+/// Converts raw data to Q16.16 with a `(numerator, denominator)` sensitivity in LSB per unit.
+#[cfg(feature = "fixed")]
+fn to_fixed((x, y, z): (i16, i16, i16), (num, den): (u32, u32)) -> (I16F16, I16F16, I16F16) {
+    let fixed =
+        |raw: i16| I16F16::from_bits((((raw as i64 * den as i64) << 16) / num as i64) as i32);
+    (fixed(x), fixed(y), fixed(z))
+}
+
+/// Driver state that is independent of the host bus.
+pub(crate) struct State {
+    pub units: Units,
+    /// Shadow of REG_BANK_SEL, `None` when unknown.
+    pub bank: Option<BankSelection>,
+    pub bank_recovery: BankRecovery,
+    pub timestamp_resolution: TimestampResolution,
+    /// Whether RTC_MODE is set with pin 9 as CLKIN, which sets the timestamp tick duration.
+    pub rtc_clock: bool,
+    pub data_source: DataSource,
+    /// FIFO_LOST_PKT at the last FIFO status read.
+    #[cfg(feature = "fifo")]
+    pub fifo_lost_packets: u16,
+    /// Shadow of TMST_DELTA_EN.
+    pub timestamp_delta: bool,
+    /// Shadow of SENSOR_DATA_ENDIAN.
+    pub sensor_data_endian: Endian,
+    /// Shadow of FIFO_COUNT_ENDIAN.
+    #[cfg(feature = "fifo")]
+    pub fifo_count_endian: Endian,
+    /// Shadow of FIFO_COUNT_REC.
+    #[cfg(feature = "fifo")]
+    pub fifo_count_unit: FifoCountUnit,
+    /// Shadow of FIFO_HOLD_LAST_DATA_EN.
+    #[cfg(feature = "fifo")]
+    pub fifo_invalid_samples: InvalidSampleMode,
+    #[cfg(feature = "motion")]
+    pub motion: MotionDetector,
+    #[cfg(feature = "motion")]
+    pub gyro_bias: GyroBiasTracker,
+    /// Accel user offset set by the user, in OFFSET_USER codes.
+    pub accel_offset: Option<(i16, i16, i16)>,
+    pub accel_offset_on_range_change: AccelOffsetOnRangeChange,
+    /// Number of samples discarded by `read_sample` after a range change.
+    pub range_change_discard: u8,
+    /// Samples left to discard since the last range change.
+    pub pending_discard: u8,
+    pub write_observer: Option<RegisterWriteObserver>,
+    /// Operation in progress, or interrupted by an error, during which data reads fail.
+    pub maintenance: Option<Maintenance>,
+    pub mounting: MountingMatrix,
+    pub capabilities: Capabilities,
+    /// INT_STATUS flags cleared by a read but not reported yet.
+    pub int_status: u8,
+    /// Sequence number of the next sample returned by `read_sample` or `read_sample_with_temp`.
+    pub sequence: u32,
+    /// Extended device time in timestamp ticks, see [`State::device_time_us`], and the raw
+    /// timestamp it was last updated from.
+    #[cfg(feature = "fifo")]
+    pub device_time: Option<(u64, u16)>,
+    #[cfg(feature = "fifo")]
+    pub odr_estimator: OdrEstimator,
+    #[cfg(feature = "audit")]
+    pub audit: AuditLog,
+}
+
+impl State {
+    pub fn new(units: Units) -> Self {
+        Self {
+            units,
+            bank: None,
+            bank_recovery: BankRecovery::default(),
+            timestamp_resolution: TimestampResolution::default(),
+            rtc_clock: false,
+            data_source: DataSource::default(),
+            #[cfg(feature = "fifo")]
+            fifo_lost_packets: 0,
+            timestamp_delta: false,
+            sensor_data_endian: Endian::default(),
+            #[cfg(feature = "fifo")]
+            fifo_count_endian: Endian::default(),
+            #[cfg(feature = "fifo")]
+            fifo_count_unit: FifoCountUnit::default(),
+            #[cfg(feature = "fifo")]
+            fifo_invalid_samples: InvalidSampleMode::default(),
+            #[cfg(feature = "motion")]
+            motion: MotionDetector::new(),
+            #[cfg(feature = "motion")]
+            gyro_bias: GyroBiasTracker::new(),
+            accel_offset: None,
+            accel_offset_on_range_change: AccelOffsetOnRangeChange::default(),
+            range_change_discard: 0,
+            pending_discard: 0,
+            write_observer: None,
+            maintenance: None,
+            mounting: MountingMatrix::IDENTITY,
+            capabilities: Capabilities::default(),
+            int_status: 0,
+            sequence: 0,
+            #[cfg(feature = "fifo")]
+            device_time: None,
+            #[cfg(feature = "fifo")]
+            odr_estimator: OdrEstimator::new(),
+            #[cfg(feature = "audit")]
+            audit: AuditLog::new(),
+        }
+    }
+
+    /// Extended device time, unit: µs
+    #[cfg(feature = "fifo")]
+    pub fn device_time_us(&self) -> Option<u64> {
+        let (ticks, _) = self.device_time?;
+        Some(self.timestamp_resolution.ticks_us(ticks, self.rtc_clock))
+    }
+}
+
+/// Accel and gyro data of a [`Sample`] before it is numbered.
+struct Reading {
+    acceleration: (f32, f32, f32),
+    angular_velocity: (f32, f32, f32),
+    resolution: Resolution,
+}
+
+/// Non-generic driver core.
+///
+/// All sensor logic lives here and operates on `dyn Interface`, [`crate::Icm42688p`] only
+/// forwards to it.
+pub(crate) struct Driver<'a> {
+    pub interface: &'a mut dyn Interface,
+    pub state: &'a mut State,
+}
+
+impl Driver<'_> {
+    pub fn init(&mut self) -> Result<(), Error> {
+        if let Some(disabled) = self.interface.prepare()? {
+            self.register_set_bits(
+                &Bank0::IntfConfig0,
+                &INTF_CONFIG0::UI_SIFS_CFG,
+                disabled as u8,
+            )?;
+        }
+        if self.device_id()? != ICM42688P_DEVICE_ID {
+            return Err(Error::BadDeviceId);
+        }
+        // Another firmware may have changed the byte orders without resetting the sensor.
+        self.sensor_data_endian()?;
+        self.rtc_clock()?;
+        #[cfg(feature = "fifo")]
+        self.fifo_count_endian()?;
+        #[cfg(feature = "fifo")]
+        self.fifo_count_unit()?;
+        #[cfg(feature = "fifo")]
+        self.fifo_invalid_samples()?;
+        // TMST_VALUE stays at 0 unless the strobe is allowed to latch the counter.
+        self.register_set_bits(&Bank0::TmstConfig, &TMST_CONFIG::TMST_TO_REGS_EN, 1)?;
+
+        self.set_accel_range(AccelRange::default())?;
+        self.set_gyro_range(GyroRange::default())
+    }
+
+    pub fn acceleration(&mut self) -> Result<(f32, f32, f32), Error> {
+        let raw = self.raw_acceleration()?;
+        let factor = self.accel_sensitivity()?;
+        Ok(self.scale_acceleration(widen(raw), factor))
+    }
+
+    pub fn acceleration_g(&mut self) -> Result<(f32, f32, f32), Error> {
+        let (x, y, z) = self.raw_acceleration()?;
+        let factor = self.accel_sensitivity()?;
+        Ok((x as f32 / factor, y as f32 / factor, z as f32 / factor))
+    }
+
+    pub fn angular_velocity(&mut self) -> Result<(f32, f32, f32), Error> {
+        let (x, y, z) = self.angular_velocity_dps()?;
+        let unit = self.state.units.gyro;
+        Ok((unit.from_dps(x), unit.from_dps(y), unit.from_dps(z)))
+    }
+
+    /// Angular velocity with the tracked gyro bias removed, unit: °/s
+    ///
+    /// With bias tracking enabled, accel and gyro data are read in one burst and the accel data
+    /// feeds the motion detector.
+    pub fn angular_velocity_dps(&mut self) -> Result<(f32, f32, f32), Error> {
+        let factor = self.gyro_sensitivity()?;
+        #[cfg(feature = "motion")]
+        if self.state.gyro_bias.enabled {
+            let accel_factor = self.accel_sensitivity()?;
+            let data = self.read_sensor_data()?;
+            self.track_motion(widen(data.acceleration), accel_factor);
+            return Ok(self.correct_gyro_bias(widen(data.angular_velocity), factor));
+        }
+        let raw = self.raw_angular_velocity()?;
+        Ok(self.correct_gyro_bias(widen(raw), factor))
+    }
+
+    /// Feeds raw accel data with sensitivity `factor` to the motion detector gating the gyro bias
+    /// tracking, when tracking is enabled and the accelerometer is on.
+    #[cfg(feature = "motion")]
+    fn track_motion(&mut self, (x, y, z): (i32, i32, i32), factor: f32) {
+        if self.state.gyro_bias.enabled && self.state.capabilities.accel {
+            let sample = (x as f32 / factor, y as f32 / factor, z as f32 / factor);
+            self.state.motion.update(sample);
+        }
+    }
+
+    #[cfg(not(feature = "motion"))]
+    fn track_motion(&mut self, _accel: (i32, i32, i32), _factor: f32) {}
+
+    /// Converts raw gyro data with sensitivity `factor` to °/s, removing the tracked bias.
+    fn correct_gyro_bias(&mut self, (x, y, z): (i32, i32, i32), factor: f32) -> (f32, f32, f32) {
+        let dps = (x as f32 / factor, y as f32 / factor, z as f32 / factor);
+        #[cfg(feature = "motion")]
+        {
+            let motion = self.state.motion.state();
+            self.state.gyro_bias.correct(dps, motion)
+        }
+        #[cfg(not(feature = "motion"))]
+        dps
+    }
+
+    /// Converts raw accel and gyro data of one sample to the configured units, feeding the
+    /// motion detector and removing the tracked gyro bias.
+    pub fn scale_sample(
+        &mut self,
+        (accel, accel_factor): ((i32, i32, i32), f32),
+        (gyro, gyro_factor): ((i32, i32, i32), f32),
+    ) -> ((f32, f32, f32), (f32, f32, f32)) {
+        let acceleration = self.scale_tracked_acceleration(accel, accel_factor);
+        let angular_velocity = self.scale_corrected_angular_velocity(gyro, gyro_factor);
+        (acceleration, angular_velocity)
+    }
+
+    /// Converts raw accel data with sensitivity `factor` to the configured unit, feeding the
+    /// motion detector.
+    pub fn scale_tracked_acceleration(
+        &mut self,
+        raw: (i32, i32, i32),
+        factor: f32,
+    ) -> (f32, f32, f32) {
+        self.track_motion(raw, factor);
+        self.scale_acceleration(raw, factor)
+    }
+
+    /// Converts raw gyro data with sensitivity `factor` to the configured unit, removing the
+    /// tracked bias.
+    pub fn scale_corrected_angular_velocity(
+        &mut self,
+        raw: (i32, i32, i32),
+        factor: f32,
+    ) -> (f32, f32, f32) {
+        let (x, y, z) = self.correct_gyro_bias(raw, factor);
+        let unit = self.state.units.gyro;
+        (unit.from_dps(x), unit.from_dps(y), unit.from_dps(z))
+    }
+
+    /// Acceleration in Q16.16 fixed point, computed without floating point, unit: g
+    #[cfg(feature = "fixed")]
+    pub fn acceleration_fixed(&mut self) -> Result<(I16F16, I16F16, I16F16), Error> {
+        let raw = self.raw_acceleration()?;
+        let ratio = self.accel_range()?.sensitivity_ratio();
+        Ok(to_fixed(raw, ratio))
+    }
+
+    /// Angular velocity in Q16.16 fixed point, computed without floating point, unit: °/s
+    ///
+    /// The tracked gyro bias is not removed.
+    #[cfg(feature = "fixed")]
+    pub fn angular_velocity_fixed(&mut self) -> Result<(I16F16, I16F16, I16F16), Error> {
+        let raw = self.raw_angular_velocity()?;
+        let ratio = self.gyro_range()?.sensitivity_ratio();
+        Ok(to_fixed(raw, ratio))
+    }
+
+    /// Sensitivity of the data registers and the 16-bit FIFO packets, unit: LSB/g
+    ///
+    /// The forced ±16g range of the 20-bit FIFO format only applies to the 20 byte packets, see
+    /// [`HIRES_ACCEL_SENSITIVITY`].
+    pub fn accel_sensitivity(&mut self) -> Result<f32, Error> {
+        Ok(self.accel_range()?.sensitivity_scale_factor())
+    }
+
+    /// Sensitivity of the data registers and the 16-bit FIFO packets, unit: LSB/(°/s)
+    ///
+    /// The forced ±2000°/s range of the 20-bit FIFO format only applies to the 20 byte packets,
+    /// see [`HIRES_GYRO_SENSITIVITY`].
+    pub fn gyro_sensitivity(&mut self) -> Result<f32, Error> {
+        Ok(self.gyro_range()?.sensitivity_scale_factor())
+    }
+
+    /// Converts raw accel data with sensitivity `factor` to the configured unit.
+    pub fn scale_acceleration(&self, (x, y, z): (i32, i32, i32), factor: f32) -> (f32, f32, f32) {
+        let unit = self.state.units.accel;
+        let x = unit.from_g(x as f32 / factor);
+        let y = unit.from_g(y as f32 / factor);
+        let z = unit.from_g(z as f32 / factor);
+        (x, y, z)
+    }
+
+    /// Converts raw gyro data with sensitivity `factor` to the configured unit.
+    pub fn scale_angular_velocity(
+        &self,
+        (x, y, z): (i32, i32, i32),
+        factor: f32,
+    ) -> (f32, f32, f32) {
+        let unit = self.state.units.gyro;
+        let x = unit.from_dps(x as f32 / factor);
+        let y = unit.from_dps(y as f32 / factor);
+        let z = unit.from_dps(z as f32 / factor);
+        (x, y, z)
+    }
+
+    pub fn temperature(&mut self) -> Result<f32, Error> {
+        let temp = self.temperature_celsius()?;
+        Ok(self.state.units.temperature.from_celsius(temp))
+    }
+
+    pub fn temperature_celsius(&mut self) -> Result<f32, Error> {
+        Ok(self.raw_temperature()?.celsius())
+    }
+
+    #[cfg(feature = "motion")]
+    pub fn motion_state(&mut self) -> Result<MotionState, Error> {
+        let sample = self.acceleration_g()?;
+        Ok(self.state.motion.update(sample))
+    }
+
+    #[cfg(feature = "snapshot")]
+    pub fn config_snapshot(&mut self) -> Result<ConfigSnapshot, Error> {
+        let mut snapshot = ConfigSnapshot {
+            values: [0; CONFIG_REGISTERS.len()],
+        };
+        for (value, (_, register)) in snapshot.values.iter_mut().zip(&CONFIG_REGISTERS) {
+            *value = self.register_read(*register)?;
+        }
+        Ok(snapshot)
+    }
+
+    /// Reads and clears the data ready flag of INT_STATUS.
+    pub fn data_ready(&mut self) -> Result<bool, Error> {
+        let status = self.register_read(&Bank0::IntStatus)?;
+        let status = self.take_int_status(status, INT_STATUS::DATA_RDY_INT.mask());
+        Ok(status != 0)
+    }
+
+    /// Routes the UI data ready interrupt to `pin`.
+    pub fn route_data_ready_interrupt(&mut self, pin: InterruptPin) -> Result<(), Error> {
+        self.configure_interrupt_pin(pin)?;
+        let source = match pin {
+            InterruptPin::Int1 => Bank0::IntSource0,
+            InterruptPin::Int2 => Bank0::IntSource3,
+        };
+        self.register_set_bits(&source, &INT_SOURCE0::UI_DRDY_INT1_EN, 1)
+    }
+
+    /// Merges a fresh INT_STATUS read with the flags kept from previous reads, and returns the
+    /// `flags` being reported, keeping the others for their own query.
+    fn take_int_status(&mut self, status: u8, flags: u8) -> u8 {
+        let status = self.state.int_status | status;
+        self.state.int_status = status & !flags;
+        status & flags
+    }
+
+    /// Fails with [`Error::Busy`] while the sensor data may be corrupted by a maintenance
+    /// operation.
+    fn ensure_not_busy(&self) -> Result<(), Error> {
+        match self.state.maintenance {
+            Some(maintenance) => Err(Error::Busy(maintenance)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn read_sample_with_temp(&mut self) -> Result<SampleWithTemp, Error> {
+        let accel_factor = self.accel_sensitivity()?;
+        let gyro_factor = self.gyro_sensitivity()?;
+        let data = self.read_sensor_data()?;
+        let (acceleration, angular_velocity) = self.scale_sample(
+            (widen(data.acceleration), accel_factor),
+            (widen(data.angular_velocity), gyro_factor),
+        );
+
+        let temperature = data.temperature.celsius();
+        Ok(SampleWithTemp {
+            sample: Sample {
+                acceleration,
+                angular_velocity,
+                resolution: Resolution::Bits16,
+                sequence: self.next_sequence(),
+            },
+            temperature: self.state.units.temperature.from_celsius(temperature),
+        })
+    }
+
+    pub fn sample(&mut self) -> Result<SensorData, Error> {
+        let accel_factor = self.accel_sensitivity()?;
+        let gyro_factor = self.gyro_sensitivity()?;
+        self.sample_scaled(accel_factor, gyro_factor)
+    }
+
+    /// Same as [`Self::sample`], with the sensitivities read beforehand.
+    pub fn sample_scaled(
+        &mut self,
+        accel_factor: f32,
+        gyro_factor: f32,
+    ) -> Result<SensorData, Error> {
+        let data = self.read_sensor_data()?;
+        let timestamp = self.latch_timestamp_us()?;
+        let (accel, gyro) = self.scale_sample(
+            (widen(data.acceleration), accel_factor),
+            (widen(data.angular_velocity), gyro_factor),
+        );
+        let unit = self.state.units.temperature;
+        Ok(SensorData {
+            accel,
+            gyro,
+            temperature: unit.from_celsius(data.temperature.celsius()),
+            timestamp,
+        })
+    }
+
+    /// Restarts the ODR counter and the signal path controls, the sample in progress is lost.
+    pub fn abort_and_reset(&mut self) -> Result<(), Error> {
+        self.register_write(&Bank0::SignalPathReset, SIGNAL_PATH_RESET::ABORT_AND_RESET)?;
+        #[cfg(feature = "fifo")]
+        self.state.odr_estimator.restart();
+        Ok(())
+    }
+
+    /// Copies the timestamp counter into TMST_VALUE, the bit clears itself.
+    pub fn strobe_timestamp(&mut self) -> Result<(), Error> {
+        self.register_write(&Bank0::SignalPathReset, SIGNAL_PATH_RESET::TMST_STROBE)
+    }
+
+    /// Resets the DMP memory, wait 1ms afterwards.
+    ///
+    /// The APEX features must be configured again.
+    pub fn reset_dmp_memory(&mut self) -> Result<(), Error> {
+        self.register_write(&Bank0::SignalPathReset, SIGNAL_PATH_RESET::DMP_MEM_RESET_EN)
+    }
+
+    /// Starts the DMP with the current APEX configuration, wait 50ms afterwards.
+    pub fn init_dmp(&mut self) -> Result<(), Error> {
+        self.register_write(&Bank0::SignalPathReset, SIGNAL_PATH_RESET::DMP_INIT_EN)
+    }
+
+    /// Latches the 20-bit timestamp counter and reads it, unit: timestamp resolution
+    pub fn latch_timestamp(&mut self) -> Result<u32, Error> {
+        self.strobe_timestamp()?;
+        let mut buf = [0u8; 3];
+        self.register_read_burst(&Bank1::TmstVal0, &mut buf)?;
+        // TMST_VALUE is little endian whatever the endian settings: TMSTVAL0 holds bits 7:0,
+        // TMSTVAL2 bits 19:16 in its low nibble.
+        Ok(u32::from_le_bytes([buf[0], buf[1], buf[2] & 0x0F, 0]))
+    }
+
+    /// Latches the 20-bit timestamp counter and reads it, unit: µs
+    pub fn latch_timestamp_us(&mut self) -> Result<u32, Error> {
+        let ticks = self.latch_timestamp()?;
+        let resolution = self.state.timestamp_resolution;
+        Ok(resolution.ticks_us(ticks.into(), self.state.rtc_clock) as u32)
+    }
+
+    /// Reads TEMP_DATA1 to GYRO_DATA_Z0 in one bus transaction.
+    pub fn read_sensor_data(&mut self) -> Result<RawSensorData, Error> {
+        self.ensure_not_busy()?;
+        let mut buf = [0u8; 14];
+        self.register_read_burst(&Bank0::TempData1, &mut buf)?;
+        let endian = self.state.sensor_data_endian;
+        let word = |i: usize| endian.decode_i16([buf[i], buf[i + 1]]);
+        let mounting = self.state.mounting;
+        Ok(RawSensorData {
+            temperature: RawTemperature(word(0)),
+            acceleration: mounting.apply_raw((word(2), word(4), word(6))),
+            angular_velocity: mounting.apply_raw((word(8), word(10), word(12))),
+        })
+    }
+
+    pub fn set_accel_range(&mut self, range: AccelRange) -> Result<(), Error> {
+        let fs_sel = range as u8;
+        self.register_set_bits(&Bank0::AccelConfig0, &ACCEL_CONFIG0::FS_SEL, fs_sel)?;
+        self.accel_range_changed()?;
+        self.range_changed()
+    }
+
+    /// Applies [`AccelOffsetOnRangeChange`] to the accel user offset.
+    fn accel_range_changed(&mut self) -> Result<(), Error> {
+        match self.state.accel_offset_on_range_change {
+            AccelOffsetOnRangeChange::Reapply => {
+                if let Some(codes) = self.state.accel_offset {
+                    self.write_accel_offset(codes)?;
+                }
+                Ok(())
+            }
+            AccelOffsetOnRangeChange::Clear => {
+                self.state.accel_offset = None;
+                self.write_accel_offset((0, 0, 0))
+            }
+        }
+    }
+
+    /// Sets the accel user offset added to the accel data, unit: g
+    ///
+    /// Each axis is clamped to ±[`crate::limits::ACCEL_OFFSET_MAX_G`] and rounded to
+    /// [`crate::limits::ACCEL_OFFSET_RESOLUTION_G`].
+    pub fn set_accel_offset_g(&mut self, offset: (f32, f32, f32)) -> Result<(), Error> {
+        let code = |g: f32| {
+            let g = g.clamp(
+                -crate::limits::ACCEL_OFFSET_MAX_G,
+                crate::limits::ACCEL_OFFSET_MAX_G,
+            );
+            let code = g / crate::limits::ACCEL_OFFSET_RESOLUTION_G;
+            let code = if code < 0.0 { code - 0.5 } else { code + 0.5 } as i16;
+            code.clamp(-2048, 2047)
+        };
+        let codes = (code(offset.0), code(offset.1), code(offset.2));
+        self.write_accel_offset(codes)?;
+        self.state.accel_offset = Some(codes);
+        Ok(())
+    }
+
+    pub fn accel_offset_g(&mut self) -> Result<(f32, f32, f32), Error> {
+        let (x, y, z) = self.read_accel_offset()?;
+        let g = |code: i16| code as f32 * crate::limits::ACCEL_OFFSET_RESOLUTION_G;
+        Ok((g(x), g(y), g(z)))
+    }
+
+    /// Accel user offset in LSB of the current accel range.
+    pub fn accel_offset_lsb(&mut self) -> Result<(f32, f32, f32), Error> {
+        let (x, y, z) = self.accel_offset_g()?;
+        let sensitivity = self.accel_range()?.sensitivity_scale_factor();
+        Ok((x * sensitivity, y * sensitivity, z * sensitivity))
+    }
+
+    /// Writes 12-bit OFFSET_USER codes, keeping the gyro Z offset sharing OFFSET_USER4.
+    fn write_accel_offset(&mut self, (x, y, z): (i16, i16, i16)) -> Result<(), Error> {
+        let high = |code: i16| (code >> 8) as u8 & 0x0F;
+        let user4 = self.register_read(&Bank4::OffsetUser4)?;
+        let user4 = (user4 & !OFFSET_USER4::ACCEL_X_OFFUSER_HI.mask())
+            | OFFSET_USER4::ACCEL_X_OFFUSER_HI.put(high(x));
+        let user7 = OFFSET_USER7::ACCEL_Z_OFFUSER_HI.put(high(z))
+            | OFFSET_USER7::ACCEL_Y_OFFUSER_HI.put(high(y));
+        self.register_write(&Bank4::OffsetUser4, user4)?;
+        self.register_write(&Bank4::OffsetUser5, x as u8)?;
+        self.register_write(&Bank4::OffsetUser6, y as u8)?;
+        self.register_write(&Bank4::OffsetUser7, user7)?;
+        self.register_write(&Bank4::OffsetUser8, z as u8)
+    }
+
+    fn read_accel_offset(&mut self) -> Result<(i16, i16, i16), Error> {
+        let mut buf = [0; 5];
+        self.register_read_burst(&Bank4::OffsetUser4, &mut buf)?;
+        let [user4, x, y, user7, z] = buf;
+        // Sign extends the 12-bit codes.
+        let code = |high: u8, low: u8| (i16::from_be_bytes([high, low]) << 4) >> 4;
+        Ok((
+            code(OFFSET_USER4::ACCEL_X_OFFUSER_HI.get(user4), x),
+            code(OFFSET_USER7::ACCEL_Y_OFFUSER_HI.get(user7), y),
+            code(OFFSET_USER7::ACCEL_Z_OFFUSER_HI.get(user7), z),
+        ))
+    }
+
+    pub fn set_accel_offset_on_range_change(&mut self, handling: AccelOffsetOnRangeChange) {
+        self.state.accel_offset_on_range_change = handling;
+    }
+
+    pub fn accel_range(&mut self) -> Result<AccelRange, Error> {
+        Ok(self.accel_config0()?.range)
+    }
+
+    pub fn accel_odr(&mut self) -> Result<AccelODR, Error> {
+        Ok(self.accel_config0()?.odr)
+    }
+
+    fn accel_config0(&mut self) -> Result<AccelConfig0, Error> {
+        AccelConfig0::from_bits(self.register_read(&Bank0::AccelConfig0)?)
+    }
+
+    pub fn set_accel_odr(&mut self, odr: AccelODR) -> Result<(), Error> {
+        if !odr.supported_in(&self.power_mode()?) {
+            return Err(Error::InvalidForPowerMode);
+        }
+        self.register_set_bits(&Bank0::AccelConfig0, &ACCEL_CONFIG0::ODR, odr as u8)
+    }
+
+    pub fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), Error> {
+        let fs_sel = range as u8;
+        self.register_set_bits(&Bank0::GyroConfig0, &GYRO_CONFIG0::FS_SEL, fs_sel)?;
+        self.range_changed()
+    }
+
+    /// Drops the samples taken at the previous full-scale range.
+    ///
+    /// The FIFO is flushed so a batch never mixes scales, and the next `range_change_discard`
+    /// samples returned by [`Self::read_sample`] are discarded as they may be transient.
+    fn range_changed(&mut self) -> Result<(), Error> {
+        self.state.pending_discard = self.state.range_change_discard;
+        #[cfg(feature = "fifo")]
+        self.reset_fifo()?;
+        Ok(())
+    }
+
+    pub fn gyro_range(&mut self) -> Result<GyroRange, Error> {
+        Ok(self.gyro_config0()?.range)
+    }
+
+    pub fn gyro_odr(&mut self) -> Result<GyroODR, Error> {
+        Ok(self.gyro_config0()?.odr)
+    }
+
+    fn gyro_config0(&mut self) -> Result<GyroConfig0, Error> {
+        GyroConfig0::from_bits(self.register_read(&Bank0::GyroConfig0)?)
+    }
+
+    pub fn set_gyro_odr(&mut self, odr: GyroODR) -> Result<(), Error> {
+        self.register_set_bits(&Bank0::GyroConfig0, &GYRO_CONFIG0::ODR, odr as u8)
+    }
+
+    /// Returns whether a sensor was turned on from off, in which case no register must be
+    /// written for [`crate::limits::PWR_MGMT_WRITE_DELAY_US`].
+    pub fn set_power_mode(&mut self, mode: PowerMode) -> Result<bool, Error> {
+        if !self.accel_odr()?.supported_in(&mode) {
+            return Err(Error::InvalidForPowerMode);
+        }
+        let mut pwr_mgmt0 = PwrMgmt0::from_bits(self.register_read(&Bank0::PwrMgmt0)?);
+        let previous = pwr_mgmt0.mode;
+        pwr_mgmt0.mode = mode;
+        self.write_pwr_mgmt0(previous, pwr_mgmt0)
+    }
+
+    /// Turns the disabled sensors off and restricts the later power modes to the others.
+    ///
+    /// Returns whether a sensor was turned on, see [`Self::set_power_mode`].
+    pub fn restrict_capabilities(&mut self, capabilities: Capabilities) -> Result<bool, Error> {
+        self.state.capabilities = capabilities;
+        let mode = match (capabilities.accel, capabilities.gyro) {
+            (true, true) => return Ok(false),
+            (true, false) => PowerMode::AccelLowNoise,
+            (false, true) => PowerMode::GyroLowNoise,
+            (false, false) => PowerMode::Sleep,
+        };
+        self.set_power_mode(mode)
+    }
+
+    /// Writes PWR_MGMT0, returns whether a sensor was turned on from `previous`.
+    fn write_pwr_mgmt0(&mut self, previous: PowerMode, pwr_mgmt0: PwrMgmt0) -> Result<bool, Error> {
+        let gyro_on = |mode: PowerMode| PWR_MGMT0::GYRO_MODE.get(mode.bits()) != 0;
+        let accel_on = |mode: PowerMode| PWR_MGMT0::ACCEL_MODE.get(mode.bits()) & 0b10 != 0;
+        let capabilities = self.state.capabilities;
+        if (gyro_on(pwr_mgmt0.mode) && !capabilities.gyro)
+            || (accel_on(pwr_mgmt0.mode) && !capabilities.accel)
+        {
+            return Err(Error::SensorDisabled);
+        }
+
+        self.register_write(&Bank0::PwrMgmt0, pwr_mgmt0.to_bits())?;
+        Ok((!gyro_on(previous) && gyro_on(pwr_mgmt0.mode))
+            || (!accel_on(previous) && accel_on(pwr_mgmt0.mode)))
+    }
+
+    /// First step of [`Config`] application, call [`Self::end_config`] afterwards.
+    ///
+    /// The sensors are turned off with the RC oscillator kept running by the IDLE bit while the
+    /// configuration registers are written, then set to the configured power mode. Returns
+    /// whether a sensor was turned on, see [`Self::set_power_mode`].
+    pub fn begin_config(&mut self, config: &Config) -> Result<bool, Error> {
+        if !config.accel_odr.supported_in(&config.power_mode) {
+            return Err(Error::InvalidForPowerMode);
+        }
+        let mut pwr_mgmt0 = PwrMgmt0::from_bits(self.register_read(&Bank0::PwrMgmt0)?);
+        pwr_mgmt0.idle = true;
+        pwr_mgmt0.mode = PowerMode::Sleep;
+        self.register_write(&Bank0::PwrMgmt0, pwr_mgmt0.to_bits())?;
+
+        let accel_config0 = AccelConfig0 {
+            range: config.accel_range,
+            odr: config.accel_odr,
+        };
+        self.register_write(&Bank0::AccelConfig0, accel_config0.to_bits())?;
+        let gyro_config0 = GyroConfig0 {
+            range: config.gyro_range,
+            odr: config.gyro_odr,
+        };
+        self.register_write(&Bank0::GyroConfig0, gyro_config0.to_bits())?;
+        self.accel_range_changed()?;
+
+        pwr_mgmt0.mode = config.power_mode;
+        self.write_pwr_mgmt0(PowerMode::Sleep, pwr_mgmt0)
+    }
+
+    /// Last step of [`Config`] application, clears the IDLE bit.
+    pub fn end_config(&mut self) -> Result<(), Error> {
+        self.register_set_bits(&Bank0::PwrMgmt0, &PWR_MGMT0::IDLE, 0)?;
+        self.range_changed()
+    }
+
+    pub fn power_mode(&mut self) -> Result<PowerMode, Error> {
+        Ok(PwrMgmt0::from_bits(self.register_read(&Bank0::PwrMgmt0)?).mode)
+    }
+
+    pub fn device_id(&mut self) -> Result<u8, Error> {
+        self.register_read(&Bank0::WhoAmI)
+    }
+
+    pub fn set_timestamp_resolution(
+        &mut self,
+        resolution: TimestampResolution,
+    ) -> Result<(), Error> {
+        self.register_set_bits(&Bank0::TmstConfig, &TMST_CONFIG::TMST_RES, resolution as u8)?;
+        if resolution != self.state.timestamp_resolution {
+            // The device time is counted in ticks of the previous resolution.
+            #[cfg(feature = "fifo")]
+            {
+                self.state.device_time = None;
+                self.state.odr_estimator.restart();
+            }
+            self.state.timestamp_resolution = resolution;
+        }
+        Ok(())
+    }
+
+    pub fn timestamp_resolution(&mut self) -> Result<TimestampResolution, Error> {
+        let config = self.register_read(&Bank0::TmstConfig)?;
+        let resolution = TMST_CONFIG::TMST_RES.get(config).try_into()?;
+        self.state.timestamp_resolution = resolution;
+        Ok(resolution)
+    }
+
+    /// Actuates the sensors of all axes, `enabled` false restores normal operation.
+    #[cfg(feature = "selftest")]
+    pub fn set_self_test(&mut self, enabled: bool) -> Result<(), Error> {
+        let bits = if enabled {
+            SELF_TEST_CONFIG::ACCEL_ST_POWER.put(1)
+                | SELF_TEST_CONFIG::EN_ACCEL_ST.put(0b111)
+                | SELF_TEST_CONFIG::EN_GYRO_ST.put(0b111)
+        } else {
+            0
+        };
+        self.register_write(&Bank0::SelfTestConfig, bits)
+    }
+
+    /// Reads the self-test codes recorded during manufacturing, as (accel, gyro).
+    #[cfg(feature = "selftest")]
+    pub fn factory_self_test_codes(&mut self) -> Result<([u8; 3], [u8; 3]), Error> {
+        let mut accel = [0; 3];
+        let mut gyro = [0; 3];
+        self.register_read_burst(&Bank2::XaStData, &mut accel)?;
+        self.register_read_burst(&Bank1::XgStData, &mut gyro)?;
+        Ok((accel, gyro))
+    }
+
+    pub fn clock_status(&mut self) -> Result<ClockStatus, Error> {
+        let config = self.register_read(&Bank0::IntfConfig1)?;
+        let pin9 = self.register_read(&Bank1::IntfConfig5)?;
+        let status = ClockStatus {
+            source: INTF_CONFIG1::CLKSEL.get(config).try_into()?,
+            accel_lp_clock: INTF_CONFIG1::ACCEL_LP_CLK_SEL.get(config).into(),
+            rtc_mode: INTF_CONFIG1::RTC_MODE.get(config) != 0,
+            clkin_pin: INTF_CONFIG5::PIN9_FUNCTION.get(pin9) == 2,
+        };
+        self.state.rtc_clock = status.uses_clkin();
+        Ok(status)
+    }
+
+    /// Reads RTC_MODE and the pin 9 function, which set the duration of the timestamp ticks.
+    fn rtc_clock(&mut self) -> Result<bool, Error> {
+        let config = self.register_read(&Bank0::IntfConfig1)?;
+        let pin9 = self.register_read(&Bank1::IntfConfig5)?;
+        let rtc_clock =
+            INTF_CONFIG1::RTC_MODE.get(config) != 0 && INTF_CONFIG5::PIN9_FUNCTION.get(pin9) == 2;
+        self.state.rtc_clock = rtc_clock;
+        Ok(rtc_clock)
+    }
+
+    pub fn active_interface(&mut self) -> Result<ActiveInterface, Error> {
+        let sifs = INTF_CONFIG0::UI_SIFS_CFG.get(self.register_read(&Bank0::IntfConfig0)?);
+        let config4 = self.register_read(&Bank1::IntfConfig4)?;
+        let config6 = self.register_read(&Bank1::IntfConfig6)?;
+        Ok(ActiveInterface {
+            spi: sifs != DisabledInterface::Spi as u8,
+            i2c: sifs != DisabledInterface::I2c as u8,
+            i3c: INTF_CONFIG6::I3C_EN.get(config6) != 0,
+            spi_4wire: INTF_CONFIG4::SPI_AP_4WIRE.get(config4) != 0,
+        })
+    }
+
+    pub fn set_timestamp_delta(&mut self, enabled: bool) -> Result<(), Error> {
+        self.register_set_bits(
+            &Bank0::TmstConfig,
+            &TMST_CONFIG::TMST_DELTA_EN,
+            enabled as u8,
+        )?;
+        self.state.timestamp_delta = enabled;
+        Ok(())
+    }
+
+    pub fn timestamp_delta(&mut self) -> Result<bool, Error> {
+        let config = self.register_read(&Bank0::TmstConfig)?;
+        let enabled = TMST_CONFIG::TMST_DELTA_EN.get(config) != 0;
+        self.state.timestamp_delta = enabled;
+        Ok(enabled)
+    }
+
+    pub fn set_low_latency_mode(&mut self, filter: LowLatencyFilter) -> Result<(), Error> {
+        self.register_set_bits(
+            &Bank1::GyroConfigStatic2,
+            &GYRO_CONFIG_STATIC2::GYRO_AAF_DIS,
+            1,
+        )?;
+        self.register_set_bits(
+            &Bank1::GyroConfigStatic2,
+            &GYRO_CONFIG_STATIC2::GYRO_NF_DIS,
+            1,
+        )?;
+        self.register_set_bits(
+            &Bank0::GyroAccelConfig0,
+            &GYRO_ACCEL_CONFIG0::GYRO_UI_FILT_BW,
+            filter as u8,
+        )
+    }
+
+    /// Estimates the group delay of both signal paths from the ODR, UI filter and AAF settings.
+    #[cfg(feature = "latency")]
+    pub fn estimated_group_delay(&mut self) -> Result<GroupDelay, Error> {
+        let filter_bw = self.register_read(&Bank0::GyroAccelConfig0)?;
+
+        let accel_odr = ACCEL_CONFIG0::ODR.get(self.register_read(&Bank0::AccelConfig0)?);
+        let accel_order =
+            ACCEL_CONFIG1::ACCEL_UI_FILT_ORD.get(self.register_read(&Bank0::AccelConfig1)?);
+        let accel_bw = GYRO_ACCEL_CONFIG0::ACCEL_UI_FILT_BW.get(filter_bw);
+        let accel_aaf = self.register_read(&Bank2::AccelConfigStatic2)?;
+        let accel_aaf_us = match ACCEL_CONFIG_STATIC2::ACCEL_AAF_DIS.get(accel_aaf) {
+            0 => aaf_delay_us(ACCEL_CONFIG_STATIC2::ACCEL_AAF_DELT.get(accel_aaf)),
+            _ => Some(0),
+        };
+
+        let gyro_odr = GYRO_CONFIG0::ODR.get(self.register_read(&Bank0::GyroConfig0)?);
+        let gyro_order =
+            GYRO_CONFIG1::GYRO_UI_FILT_ORD.get(self.register_read(&Bank0::GyroConfig1)?);
+        let gyro_bw = GYRO_ACCEL_CONFIG0::GYRO_UI_FILT_BW.get(filter_bw);
+        let gyro_aaf_us = match GYRO_CONFIG_STATIC2::GYRO_AAF_DIS
+            .get(self.register_read(&Bank1::GyroConfigStatic2)?)
+        {
+            0 => aaf_delay_us(
+                GYRO_CONFIG_STATIC3::GYRO_AAF_DELT
+                    .get(self.register_read(&Bank1::GyroConfigStatic3)?),
+            ),
+            _ => Some(0),
+        };
+
+        let total = |ui: Option<u32>, aaf: Option<u32>| Some(ui? + aaf?);
+        Ok(GroupDelay {
+            accel_us: total(
+                ui_filter_delay_us(accel_odr, accel_order, accel_bw),
+                accel_aaf_us,
+            ),
+            gyro_us: total(
+                ui_filter_delay_us(gyro_odr, gyro_order, gyro_bw),
+                gyro_aaf_us,
+            ),
+        })
+    }
+
+    pub fn clear_low_latency_mode(&mut self) -> Result<(), Error> {
+        self.register_set_bits(
+            &Bank1::GyroConfigStatic2,
+            &GYRO_CONFIG_STATIC2::GYRO_AAF_DIS,
+            0,
+        )?;
+        self.register_set_bits(
+            &Bank1::GyroConfigStatic2,
+            &GYRO_CONFIG_STATIC2::GYRO_NF_DIS,
+            0,
+        )?;
+        self.register_set_bits(
+            &Bank0::GyroAccelConfig0,
+            &GYRO_ACCEL_CONFIG0::GYRO_UI_FILT_BW,
+            1,
+        )
+    }
+
+    /// Configures `pin` as push-pull, active high and latched until the status is read.
+    fn configure_interrupt_pin(&mut self, pin: InterruptPin) -> Result<(), Error> {
+        let (mode, drive, polarity) = match pin {
+            InterruptPin::Int1 => (
+                INT_CONFIG::INT1_MODE,
+                INT_CONFIG::INT1_DRIVE_CIRCUIT,
+                INT_CONFIG::INT1_POLARITY,
+            ),
+            InterruptPin::Int2 => (
+                INT_CONFIG::INT2_MODE,
+                INT_CONFIG::INT2_DRIVE_CIRCUIT,
+                INT_CONFIG::INT2_POLARITY,
+            ),
+        };
+        self.register_set_bits(&Bank0::IntConfig, &mode, 1)?;
+        self.register_set_bits(&Bank0::IntConfig, &drive, 1)?;
+        self.register_set_bits(&Bank0::IntConfig, &polarity, 1)?;
+        self.register_set_bits(&Bank0::IntConfig1, &INT_CONFIG1::INT_ASYNC_RESET, 0)
+    }
+
+    /// Checks the interrupt routing of both pins against each other and the enabled features.
+    pub fn check_interrupts(&mut self) -> Result<(), Error> {
+        let mut conflicts = InterruptConflicts::empty();
+
+        let int1 = [
+            self.register_read(&Bank0::IntSource0)?,
+            self.register_read(&Bank0::IntSource1)?,
+        ];
+        let int2 = [
+            self.register_read(&Bank0::IntSource3)?,
+            self.register_read(&Bank0::IntSource4)?,
+        ];
+        if int1.iter().zip(&int2).any(|(a, b)| a & b != 0) {
+            conflicts |= InterruptConflicts::DuplicateRouting;
+        }
+        let int1_apex = self.register_read(&Bank4::IntSource6)?;
+        let int2_apex = self.register_read(&Bank4::IntSource7)?;
+        if int1_apex & int2_apex != 0 {
+            conflicts |= InterruptConflicts::DuplicateApexRouting;
+        }
+
+        let source1 = int1[1] | int2[1];
+        let smd_mode = SMD_CONFIG::SMD_MODE.get(self.register_read(&Bank0::SmdConfig)?);
+        if INT_SOURCE1::WOM_INT1_EN.get(source1) != 0 && smd_mode == 0 {
+            conflicts |= InterruptConflicts::WomDisabled;
+        }
+        if INT_SOURCE1::SMD_INT1_EN.get(source1) != 0 && smd_mode < 2 {
+            conflicts |= InterruptConflicts::SmdDisabled;
+        }
+
+        let apex = int1_apex | int2_apex;
+        let config = self.register_read(&Bank0::ApexConfig0)?;
+        let checks = [
+            (
+                INT_SOURCE6::TAP_DET_INT_EN,
+                APEX_CONFIG0::TAP_ENABLE,
+                InterruptConflicts::TapDisabled,
+            ),
+            (
+                INT_SOURCE6::STEP_INT1_EN,
+                APEX_CONFIG0::PED_ENABLE,
+                InterruptConflicts::PedometerDisabled,
+            ),
+            (
+                INT_SOURCE6::TILT_DET_INT1_EN,
+                APEX_CONFIG0::TILT_ENABLE,
+                InterruptConflicts::TiltDisabled,
+            ),
+            (
+                INT_SOURCE6::R2W_INT1_EN,
+                APEX_CONFIG0::R2W_EN,
+                InterruptConflicts::RaiseToWakeDisabled,
+            ),
+        ];
+        for (source, enable, conflict) in checks {
+            if source.get(apex) != 0 && enable.get(config) == 0 {
+                conflicts |= conflict;
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InterruptConflict(conflicts))
+        }
+    }
+
+    /// Configures `pin` as pulsed with `timing`, which is shared by both pins.
+    pub fn set_interrupt_timing(
+        &mut self,
+        pin: InterruptPin,
+        timing: InterruptTiming,
+    ) -> Result<(), Error> {
+        let mode = match pin {
+            InterruptPin::Int1 => INT_CONFIG::INT1_MODE,
+            InterruptPin::Int2 => INT_CONFIG::INT2_MODE,
+        };
+        self.register_set_bits(&Bank0::IntConfig, &mode, 0)?;
+        self.register_set_bits(
+            &Bank0::IntConfig1,
+            &INT_CONFIG1::INT_TPULSE_DURATION,
+            timing.pulse as u8,
+        )?;
+        self.register_set_bits(
+            &Bank0::IntConfig1,
+            &INT_CONFIG1::INT_TDEASSERT_DISABLE,
+            !timing.min_deassert as u8,
+        )
+    }
+
+    /// Reads the accel data registers, in the body frame.
+    pub fn raw_acceleration(&mut self) -> Result<(i16, i16, i16), Error> {
+        if !self.state.capabilities.accel {
+            return Err(Error::SensorDisabled);
+        }
+        self.ensure_not_busy()?;
+        let raw = self.sensor_acceleration()?;
+        Ok(self.state.mounting.apply_raw(raw))
+    }
+
+    /// Reads the gyro data registers, in the body frame.
+    pub fn raw_angular_velocity(&mut self) -> Result<(i16, i16, i16), Error> {
+        if !self.state.capabilities.gyro {
+            return Err(Error::SensorDisabled);
+        }
+        self.ensure_not_busy()?;
+        let raw = self.sensor_angular_velocity()?;
+        Ok(self.state.mounting.apply_raw(raw))
+    }
+
+    /// Reads the accel data registers of a single body axis in one transaction.
+    pub fn raw_acceleration_axis(&mut self, axis: Axis) -> Result<i16, Error> {
+        if !self.state.capabilities.accel {
+            return Err(Error::SensorDisabled);
+        }
+        let (axis, negated) = self.state.mounting.source(axis);
+        let register = match axis {
+            Axis::X => Bank0::AccelDataX1,
+            Axis::Y => Bank0::AccelDataY1,
+            Axis::Z => Bank0::AccelDataZ1,
+        };
+        self.read_axis(register, negated)
+    }
+
+    /// Reads the gyro data registers of a single body axis in one transaction.
+    pub fn raw_angular_velocity_axis(&mut self, axis: Axis) -> Result<i16, Error> {
+        if !self.state.capabilities.gyro {
+            return Err(Error::SensorDisabled);
+        }
+        let (axis, negated) = self.state.mounting.source(axis);
+        let register = match axis {
+            Axis::X => Bank0::GyroDataX1,
+            Axis::Y => Bank0::GyroDataY1,
+            Axis::Z => Bank0::GyroDataZ1,
+        };
+        self.read_axis(register, negated)
+    }
+
+    fn read_axis(&mut self, register: Bank0, negated: bool) -> Result<i16, Error> {
+        self.ensure_not_busy()?;
+        let value = self.read_word(register)?;
+        Ok(if negated {
+            value.saturating_neg()
+        } else {
+            value
+        })
+    }
+
+    /// Reads the accel data registers, in the sensor frame.
+    pub fn sensor_acceleration(&mut self) -> Result<(i16, i16, i16), Error> {
+        let x = self.read_word(Bank0::AccelDataX1)?;
+        let y = self.read_word(Bank0::AccelDataY1)?;
+        let z = self.read_word(Bank0::AccelDataZ1)?;
+        Ok((x, y, z))
+    }
+
+    /// Reads the gyro data registers, in the sensor frame.
+    pub fn sensor_angular_velocity(&mut self) -> Result<(i16, i16, i16), Error> {
+        let x = self.read_word(Bank0::GyroDataX1)?;
+        let y = self.read_word(Bank0::GyroDataY1)?;
+        let z = self.read_word(Bank0::GyroDataZ1)?;
+        Ok((x, y, z))
+    }
+
+    pub fn raw_temperature(&mut self) -> Result<RawTemperature, Error> {
+        self.ensure_not_busy()?;
+        Ok(RawTemperature(self.read_word(Bank0::TempData1)?))
+    }
+
+    pub fn read_sample(&mut self) -> Result<Option<Sample>, Error> {
+        self.ensure_not_busy()?;
+        let reading = match self.state.data_source {
+            DataSource::Registers => Some(self.read_register_sample()?),
+            #[cfg(feature = "fifo")]
+            DataSource::Fifo => self.read_fifo_sample()?,
+        };
+        let Some(reading) = reading else {
+            return Ok(None);
+        };
+        if self.state.pending_discard > 0 {
+            self.state.pending_discard -= 1;
+            return Ok(None);
+        }
+        Ok(Some(Sample {
+            acceleration: reading.acceleration,
+            angular_velocity: reading.angular_velocity,
+            resolution: reading.resolution,
+            sequence: self.next_sequence(),
+        }))
+    }
+
+    /// Reads accel and gyro from the data registers in one burst, so both come from the same
+    /// ODR period.
+    fn read_register_sample(&mut self) -> Result<Reading, Error> {
+        if !self.state.capabilities.accel || !self.state.capabilities.gyro {
+            return Err(Error::SensorDisabled);
+        }
+        let accel_factor = self.accel_sensitivity()?;
+        let gyro_factor = self.gyro_sensitivity()?;
+        let data = self.read_sensor_data()?;
+        let (acceleration, angular_velocity) = self.scale_sample(
+            (widen(data.acceleration), accel_factor),
+            (widen(data.angular_velocity), gyro_factor),
+        );
+        Ok(Reading {
+            acceleration,
+            angular_velocity,
+            resolution: Resolution::Bits16,
+        })
+    }
+
+    /// Sequence number of the next returned sample, wrapping after `u32::MAX`.
+    fn next_sequence(&mut self) -> u32 {
+        let sequence = self.state.sequence;
+        self.state.sequence = sequence.wrapping_add(1);
+        sequence
+    }
+
+    /// Selects the byte order of the sensor data registers and FIFO packets.
+    pub fn set_sensor_data_endian(&mut self, endian: Endian) -> Result<(), Error> {
+        self.register_set_bits(
+            &Bank0::IntfConfig0,
+            &INTF_CONFIG0::SENSOR_DATA_ENDIAN,
+            endian as u8,
+        )?;
+        self.state.sensor_data_endian = endian;
+        Ok(())
+    }
+
+    pub fn sensor_data_endian(&mut self) -> Result<Endian, Error> {
+        let config = self.register_read(&Bank0::IntfConfig0)?;
+        let endian = INTF_CONFIG0::SENSOR_DATA_ENDIAN.get(config).into();
+        self.state.sensor_data_endian = endian;
+        Ok(endian)
+    }
+
+    /// Reads the sensor word whose first byte is at `first`, in one bus transaction so both
+    /// bytes belong to the same sample.
+    fn read_word(&mut self, first: Bank0) -> Result<i16, Error> {
+        let mut buf = [0u8; 2];
+        self.register_read_burst(&first, &mut buf)?;
+        Ok(self.state.sensor_data_endian.decode_i16(buf))
+    }
+
+    fn register_set_bits(
+        &mut self,
+        reg: &dyn Register,
+        range: &BitRange,
+        data: u8,
+    ) -> Result<(), Error> {
+        let mut buf = self.register_read(reg)?;
+        buf &= !range.mask();
+        buf |= range.put(data);
+        self.register_write(reg, buf)
+    }
+
+    fn register_read(&mut self, reg: &dyn Register) -> Result<u8, Error> {
+        debug_assert!(reg.readable());
+        let bank = reg.bank_selection();
+        self.select_user_bank(bank)?;
+        let result = self.interface.read_register(reg.address());
+        let value = *result.as_ref().unwrap_or(&0);
+        self.audit(
+            bank,
+            reg.address(),
+            BusOperation::Read,
+            1,
+            value,
+            result.is_ok(),
+        );
+        self.leave_user_bank(bank, result)
+    }
+
+    /// Reads `buf.len()` consecutive registers starting at `reg` in one transaction.
+    fn register_read_burst(&mut self, reg: &dyn Register, buf: &mut [u8]) -> Result<(), Error> {
+        debug_assert!(reg.readable());
+        let bank = reg.bank_selection();
+        self.select_user_bank(bank)?;
+        let result = self.interface.read_registers(reg.address(), buf);
+        let value = buf.first().copied().unwrap_or(0);
+        self.audit(
+            bank,
+            reg.address(),
+            BusOperation::Read,
+            buf.len(),
+            value,
+            result.is_ok(),
+        );
+        self.leave_user_bank(bank, result)
+    }
+
+    fn register_write(&mut self, reg: &dyn Register, data: u8) -> Result<(), Error> {
+        debug_assert!(reg.writable());
+        let old = match self.state.write_observer {
+            Some(_) if reg.readable() => Some(self.register_read(reg)?),
+            _ => None,
+        };
+        let bank = reg.bank_selection();
+        self.select_user_bank(bank)?;
+        let result = self.interface.write_register(reg.address(), data);
+        self.audit(
+            bank,
+            reg.address(),
+            BusOperation::Write,
+            1,
+            data,
+            result.is_ok(),
+        );
+        let result = self.leave_user_bank(bank, result);
+        if let (Ok(()), Some(observer)) = (&result, self.state.write_observer) {
+            observer(bank.into(), reg.address(), old, data);
+        }
+        result
+    }
+
+    /// Selects `bank` unless the shadow state says it is already selected.
+    ///
+    /// A failed selection leaves the device in an unknown bank, so the shadow state is marked
+    /// dirty and bank 0 is restored according to the [`BankRecovery`] policy.
+    fn select_user_bank(&mut self, bank: BankSelection) -> Result<(), Error> {
+        if self.state.bank == Some(bank) {
+            return Ok(());
+        }
+        let result = self
+            .interface
+            .write_register(Bank0::RegBankSel.address(), bank as u8);
+        self.audit(
+            bank,
+            Bank0::RegBankSel.address(),
+            BusOperation::Write,
+            1,
+            bank as u8,
+            result.is_ok(),
+        );
+        match result {
+            Ok(()) => self.state.bank = Some(bank),
+            Err(_) => {
+                self.state.bank = None;
+                if self.state.bank_recovery == BankRecovery::Immediate {
+                    self.restore_bank0();
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns to bank 0 after accessing a register of `bank`, keeping the access error if any.
+    fn leave_user_bank<T>(
+        &mut self,
+        bank: BankSelection,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        if bank == BankSelection::Bank0 {
+            return result;
+        }
+        let restored = self.select_user_bank(BankSelection::Bank0);
+        result.and_then(|value| restored.map(|_| value))
+    }
+
+    /// Best effort selection of bank 0, the error is dropped since the caller already fails.
+    fn restore_bank0(&mut self) {
+        let bank = BankSelection::Bank0;
+        let ok = self
+            .interface
+            .write_register(Bank0::RegBankSel.address(), bank as u8)
+            .is_ok();
+        self.audit(
+            bank,
+            Bank0::RegBankSel.address(),
+            BusOperation::Write,
+            1,
+            bank as u8,
+            ok,
+        );
+        if ok {
+            self.state.bank = Some(bank);
+        }
+    }
+
+    /// Records a register access into the audit log, a no-op without the `audit` feature.
+    #[allow(unused_variables)]
+    fn audit(
+        &mut self,
+        bank: BankSelection,
+        address: RegAddr,
+        operation: BusOperation,
+        length: usize,
+        value: u8,
+        ok: bool,
+    ) {
+        #[cfg(feature = "audit")]
+        self.state.audit.record(RegisterAccess {
+            bank: bank.into(),
+            address,
+            operation,
+            length,
+            value,
+            ok,
+        });
+    }
+}
+
+#[cfg(feature = "apex")]
+impl Driver<'_> {
+    /// First step of the tap detection programming sequence, wait 1ms afterwards.
+    ///
+    /// Tap detection runs on the accelerometer at 1kHz in low noise mode.
+    pub fn prepare_tap_detection(&mut self) -> Result<(), Error> {
+        self.register_set_bits(
+            &Bank0::AccelConfig0,
+            &ACCEL_CONFIG0::ODR,
+            AccelODR::Hz1k as u8,
+        )?;
+        self.register_set_bits(
+            &Bank0::AccelConfig1,
+            &ACCEL_CONFIG1::ACCEL_UI_FILT_ORD,
+            0b10,
+        )?;
+        self.register_set_bits(
+            &Bank0::GyroAccelConfig0,
+            &GYRO_ACCEL_CONFIG0::ACCEL_UI_FILT_BW,
+            0,
+        )?;
+        let mode = match self.power_mode()? {
+            PowerMode::GyroLowNoise | PowerMode::SixAxisLowNoise => PowerMode::SixAxisLowNoise,
+            _ => PowerMode::AccelLowNoise,
+        };
+        self.set_power_mode(mode)?;
+        Ok(())
+    }
+
+    /// Writes the recommended tap detection parameters, wait 1ms afterwards.
+    pub fn configure_tap_detection(&mut self) -> Result<(), Error> {
+        self.register_set_bits(&Bank4::ApexConfig8, &APEX_CONFIG8::TAP_TMAX, 2)?;
+        self.register_set_bits(&Bank4::ApexConfig8, &APEX_CONFIG8::TAP_TMIN, 3)?;
+        self.register_set_bits(&Bank4::ApexConfig8, &APEX_CONFIG8::TAP_TAVG, 3)?;
+        self.register_set_bits(&Bank4::ApexConfig7, &APEX_CONFIG7::TAP_MIN_JERK_THR, 17)?;
+        self.register_set_bits(&Bank4::ApexConfig7, &APEX_CONFIG7::TAP_MAX_PEAK_TOL, 2)
+    }
+
+    /// Routes the tap interrupt to `pin`, wait 50ms afterwards.
+    pub fn route_tap_interrupt(&mut self, pin: InterruptPin) -> Result<(), Error> {
+        self.configure_interrupt_pin(pin)?;
+        let source = match pin {
+            InterruptPin::Int1 => Bank4::IntSource6,
+            InterruptPin::Int2 => Bank4::IntSource7,
+        };
+        self.register_set_bits(&source, &INT_SOURCE6::TAP_DET_INT_EN, 1)
+    }
+
+    /// Last step of the tap detection programming sequence.
+    pub fn enable_tap_detection(&mut self) -> Result<(), Error> {
+        self.register_set_bits(&Bank0::ApexConfig0, &APEX_CONFIG0::TAP_ENABLE, 1)
+    }
+
+    /// First step of the wake on motion programming sequence, wait 1ms afterwards.
+    ///
+    /// `threshold` is the change from the reference accel sample triggering the event,
+    /// 1 LSB = 1g/256.
+    pub fn configure_wake_on_motion(&mut self, threshold: u8) -> Result<(), Error> {
+        self.register_write(&Bank4::AccelWomXThr, threshold)?;
+        self.register_write(&Bank4::AccelWomYThr, threshold)?;
+        self.register_write(&Bank4::AccelWomZThr, threshold)
+    }
+
+    /// Last step of the wake on motion programming sequence, to run 50ms after the first one.
+    pub fn enable_wake_on_motion(&mut self, config: WomConfig) -> Result<(), Error> {
+        let config = SMD_CONFIG::WOM_INT_MODE.put(config.axes as u8)
+            | SMD_CONFIG::WOM_MODE.put(config.reference as u8)
+            | SMD_CONFIG::SMD_MODE.put(1);
+        self.register_write(&Bank0::SmdConfig, config)
+    }
+
+    /// Whether a wake on motion event occurred since the last call.
+    pub fn wake_on_motion_event(&mut self) -> Result<bool, Error> {
+        let status = self.register_read(&Bank0::IntStatus2)?;
+        Ok(INT_STATUS2::WOM_INT.get(status) != 0)
+    }
+
+    pub fn tap_event(&mut self) -> Result<Option<TapEvent>, Error> {
+        let status = self.register_read(&Bank0::IntStatus3)?;
+        if INT_STATUS3::TAP_DET_INT.get(status) == 0 {
+            return Ok(None);
+        }
+        TapEvent::from_apex_data4(self.register_read(&Bank0::ApexData4)?)
+    }
+}
+
+#[cfg(feature = "fifo")]
+impl Driver<'_> {
+    /// Reads the packets currently stored in the FIFO and passes them to `on_packet`.
+    pub fn drain_fifo(
+        &mut self,
+        on_packet: &mut dyn FnMut(&FifoPacket),
+    ) -> Result<DrainStats, Error> {
+        self.drain_fifo_budgeted(None, on_packet)
+    }
+
+    /// Same as [`Self::drain_fifo`], stopping before a packet that would exceed `budget`.
+    pub fn drain_fifo_budgeted(
+        &mut self,
+        budget: Option<DrainBudget>,
+        on_packet: &mut dyn FnMut(&FifoPacket),
+    ) -> Result<DrainStats, Error> {
+        self.ensure_not_busy()?;
+        let sources = self.prepare_fifo_read()?;
+        let status = self.fifo_status()?;
+        let mut stats = DrainStats {
+            lost_packets: status.lost_packets,
+            ..Default::default()
+        };
+        let Some(config) = sources.packet_config() else {
+            return Ok(stats);
+        };
+        let size = config.size();
+        let mut remaining = status.count as usize;
+        let mut buf = [0u8; FifoPacket::MAX_SIZE];
+        while remaining > 0 {
+            let spent = match budget {
+                None => false,
+                Some(DrainBudget::Packets(max)) => stats.packets >= max,
+                Some(DrainBudget::BusBytes(max)) => stats.bus_bytes + size + 1 > max,
+            };
+            if spent {
+                stats.remaining_bytes = remaining;
+                break;
+            }
+            if size > remaining {
+                // Truncated packet, discard it to stay aligned on packet boundaries.
+                self.discard_fifo_bytes(remaining)?;
+                stats.bytes += remaining;
+                stats.bus_bytes += remaining + remaining.div_ceil(FifoPacket::MAX_SIZE);
+                stats.discarded_bytes += remaining;
+                break;
+            }
+            let result = self.read_fifo_packet(sources, remaining, &mut buf[..size]);
+            if let Err(Error::FifoDesync) = result {
+                // The packet and the rest of the FIFO content were dropped.
+                let rest = remaining - size;
+                stats.bytes += remaining;
+                stats.bus_bytes += size + 1 + rest + rest.div_ceil(FifoPacket::MAX_SIZE);
+                stats.discarded_bytes += remaining;
+                stats.invalid_headers += 1;
+                break;
+            }
+            let valid = result?;
+            remaining -= size;
+            stats.bytes += size;
+            // The register address byte precedes the data of each burst.
+            stats.bus_bytes += size + 1;
+            if !valid {
+                stats.empty_markers += 1;
+                stats.discarded_bytes += size;
+                continue;
+            }
+
+            let packet = FifoPacket::parse(
+                &buf[..size],
+                self.state.sensor_data_endian,
+                self.state.fifo_invalid_samples,
+            );
+            if let Some(timestamp) = packet.odr_timestamp() {
+                self.extend_device_time(timestamp);
+            }
+            on_packet(&packet);
+            stats.packets += 1;
+        }
+        Ok(stats)
+    }
+
+    /// Reads as many whole packets as fit in `buf` in one FIFO_DATA burst, and returns them
+    /// parsed with the packet structure of the enabled sources.
+    pub fn read_fifo<'b>(&mut self, buf: &'b mut [u8]) -> Result<FifoPackets<'b>, Error> {
+        let (len, count, sources) = self.fifo_burst(buf)?;
+        let endian = self.state.sensor_data_endian;
+        let Some(config) = sources.packet_config().filter(|_| len != 0) else {
+            return Ok(FifoPackets::new(&[], endian));
+        };
+
+        let packets = FifoPackets::with_config(&buf[..len], endian, config)
+            .invalid_samples(self.state.fifo_invalid_samples);
+        for packet in packets.clone() {
+            if !sources.accepts(packet.header) {
+                self.discard_fifo_bytes(count - len)?;
+                return Err(Error::FifoDesync);
+            }
+            if let Some(timestamp) = packet.odr_timestamp() {
+                self.extend_device_time(timestamp);
+            }
+        }
+        Ok(packets)
+    }
+
+    /// Reads as many whole packets as fit in `buf` in one FIFO_DATA burst, and returns the
+    /// filled part of `buf` unparsed.
+    pub fn read_fifo_burst<'b>(&mut self, buf: &'b mut [u8]) -> Result<&'b mut [u8], Error> {
+        let (len, _, _) = self.fifo_burst(buf)?;
+        Ok(&mut buf[..len])
+    }
+
+    /// Reads the whole packets fitting in `buf` into its start, returns their length in bytes
+    /// along with the FIFO count and the enabled sources.
+    fn fifo_burst(&mut self, buf: &mut [u8]) -> Result<(usize, usize, FifoSources), Error> {
+        self.ensure_not_busy()?;
+        let count = self.fifo_status()?.count as usize;
+        let sources = self.fifo_sources()?;
+        if sources.packet_config().is_none() {
+            return Ok((0, count, sources));
+        }
+        let packet_size = sources.packet_size();
+        let len = count.min(buf.len() / packet_size * packet_size);
+        if len == 0 {
+            return Ok((0, count, sources));
+        }
+        if len < count {
+            self.register_set_bits(
+                &Bank0::FifoConfig1,
+                &FIFO_CONFIG1::FIFO_RESUME_PARTIAL_RD,
+                1,
+            )?;
+        }
+        self.register_read_burst(&Bank0::FifoData, &mut buf[..len])?;
+        Ok((len, count, sources))
+    }
+
+    /// Pops packets from the FIFO until one holds both accel and gyro data.
+    fn read_fifo_sample(&mut self) -> Result<Option<Reading>, Error> {
+        self.ensure_not_busy()?;
+        let sources = self.prepare_fifo_read()?;
+        let Some(config) = sources.packet_config() else {
+            return Ok(None);
+        };
+        let size = config.size();
+        let mut buf = [0u8; FifoPacket::MAX_SIZE];
+        loop {
+            let count = self.fifo_count()? as usize;
+            if count == 0 {
+                return Ok(None);
+            }
+            if size > count {
+                self.discard_fifo_bytes(count)?;
+                return Ok(None);
+            }
+            if !self.read_fifo_packet(sources, count, &mut buf[..size])? {
+                continue;
+            }
+            let packet = FifoPacket::parse(
+                &buf[..size],
+                self.state.sensor_data_endian,
+                self.state.fifo_invalid_samples,
+            );
+            if let Some(timestamp) = packet.odr_timestamp() {
+                self.extend_device_time(timestamp);
+            }
+            let (Some(accel), Some(gyro)) = (packet.accelerometer(), packet.gyroscope()) else {
+                continue;
+            };
+            let (accel, gyro, accel_factor, gyro_factor, resolution) =
+                if size == FifoPacket::MAX_SIZE {
+                    let (accel, gyro) = packet.hires_data();
+                    (
+                        accel,
+                        gyro,
+                        HIRES_ACCEL_SENSITIVITY,
+                        HIRES_GYRO_SENSITIVITY,
+                        Resolution::Bits20,
+                    )
+                } else {
+                    (
+                        widen(accel),
+                        widen(gyro),
+                        self.accel_range()?.sensitivity_scale_factor(),
+                        self.gyro_range()?.sensitivity_scale_factor(),
+                        Resolution::Bits16,
+                    )
+                };
+            let accel = self.state.mounting.apply(accel);
+            let gyro = self.state.mounting.apply(gyro);
+            let (acceleration, angular_velocity) =
+                self.scale_sample((accel, accel_factor), (gyro, gyro_factor));
+            return Ok(Some(Reading {
+                acceleration,
+                angular_velocity,
+                resolution,
+            }));
+        }
+    }
+
+    /// Selects bank 0 for a FIFO_DATA burst performed outside the driver and returns the number
+    /// of bytes to read.
+    pub fn prepare_fifo_burst(&mut self) -> Result<u16, Error> {
+        let count = self.fifo_status()?.count;
+        self.select_user_bank(BankSelection::Bank0)?;
+        Ok(count)
+    }
+
+    /// Replaces the data ready interrupt of `pin` by a FIFO watermark interrupt firing about
+    /// `host_rate_hz` times per second, and returns the number of packets per interrupt.
+    pub fn moderate_interrupts(
+        &mut self,
+        pin: InterruptPin,
+        host_rate_hz: f32,
+    ) -> Result<u16, Error> {
+        let mut sources = self.fifo_sources()?;
+        if !sources.accel && !sources.gyro {
+            self.register_set_bits(&Bank0::FifoConfig1, &FIFO_CONFIG1::FIFO_ACCEL_EN, 1)?;
+            self.register_set_bits(&Bank0::FifoConfig1, &FIFO_CONFIG1::FIFO_GYRO_EN, 1)?;
+            sources.accel = true;
+            sources.gyro = true;
+        }
+        let packet_size = sources.packet_size();
+
+        // Packets are pushed at the rate of the fastest sensor stored in the FIFO.
+        let mut odr_hz: f32 = 0.0;
+        if sources.accel {
+            odr_hz = odr_hz.max(self.accel_odr()?.hz());
+        }
+        if sources.gyro {
+            odr_hz = odr_hz.max(self.gyro_odr()?.hz());
+        }
+        // Half of the FIFO is left as margin for the interrupt latency.
+        let max_packets = crate::limits::FIFO_SIZE / 2 / packet_size;
+        let packets = ((odr_hz / host_rate_hz + 0.5) as usize).clamp(1, max_packets);
+        // Converted to records by `set_fifo_watermark` if FIFO_COUNT_REC is set.
+        let watermark = (packets * packet_size) as u16;
+
+        if self.fifo_mode()? == FifoMode::Bypass {
+            self.set_fifo_mode(FifoMode::Stream)?;
+        }
+        self.set_fifo_watermark(watermark)?;
+
+        let source = match pin {
+            InterruptPin::Int1 => Bank0::IntSource0,
+            InterruptPin::Int2 => Bank0::IntSource3,
+        };
+        self.register_set_bits(&source, &INT_SOURCE0::UI_DRDY_INT1_EN, 0)?;
+        self.register_set_bits(&source, &INT_SOURCE0::FIFO_THS_INT1_EN, 1)?;
+        Ok(packets as u16)
+    }
+
+    pub fn set_fifo_mode(&mut self, mode: FifoMode) -> Result<(), Error> {
+        self.register_set_bits(&Bank0::FifoConfig, &FIFO_CONFIG::FIFO_MODE, mode as u8)
+    }
+
+    pub fn fifo_mode(&mut self) -> Result<FifoMode, Error> {
+        let config = self.register_read(&Bank0::FifoConfig)?;
+        Ok(FifoMode::from(FIFO_CONFIG::FIFO_MODE.get(config)))
+    }
+
+    /// Selects the data pushed to the FIFO, then flushes it so no packet of the previous layout
+    /// is left.
+    pub fn set_fifo_enables(&mut self, enables: FifoEnables) -> Result<(), Error> {
+        let fields = [
+            (FIFO_CONFIG1::FIFO_ACCEL_EN, enables.accel),
+            (FIFO_CONFIG1::FIFO_GYRO_EN, enables.gyro),
+            (FIFO_CONFIG1::FIFO_TEMP_EN, enables.temperature),
+            (FIFO_CONFIG1::FIFO_TMST_FSYNC_EN, enables.timestamp),
+            (FIFO_CONFIG1::FIFO_HIRES_EN, enables.hires),
+        ];
+        let mut config = self.register_read(&Bank0::FifoConfig1)?;
+        for (field, enabled) in fields {
+            config = (config & !field.mask()) | field.put(enabled as u8);
+        }
+        self.register_write(&Bank0::FifoConfig1, config)?;
+        self.reset_fifo()
+    }
+
+    /// Selects the structure of the packets pushed to the FIFO, keeping the temperature
+    /// and FSYNC timestamp settings, then flushes it.
+    pub fn set_fifo_packet_config(&mut self, config: FifoPacketConfig) -> Result<(), Error> {
+        let sources = config.sources();
+        let enables = FifoEnables {
+            accel: sources.accel,
+            gyro: sources.gyro,
+            hires: sources.hires,
+            ..self.fifo_enables()?
+        };
+        self.set_fifo_enables(enables)
+    }
+
+    /// Structure of the packets pushed to the FIFO, `None` when no sensor data is enabled.
+    pub fn fifo_packet_config(&mut self) -> Result<Option<FifoPacketConfig>, Error> {
+        Ok(self.fifo_sources()?.packet_config())
+    }
+
+    pub fn fifo_enables(&mut self) -> Result<FifoEnables, Error> {
+        let config = self.register_read(&Bank0::FifoConfig1)?;
+        Ok(FifoEnables {
+            accel: FIFO_CONFIG1::FIFO_ACCEL_EN.get(config) != 0,
+            gyro: FIFO_CONFIG1::FIFO_GYRO_EN.get(config) != 0,
+            temperature: FIFO_CONFIG1::FIFO_TEMP_EN.get(config) != 0,
+            timestamp: FIFO_CONFIG1::FIFO_TMST_FSYNC_EN.get(config) != 0,
+            hires: FIFO_CONFIG1::FIFO_HIRES_EN.get(config) != 0,
+        })
+    }
+
+    /// Sets the FIFO count raising the FIFO threshold interrupt, unit: byte
+    ///
+    /// FIFO_WM is compared with FIFO_COUNTH/L, so it is written in packets when FIFO_COUNT_REC
+    /// selects records, rounded down.
+    pub fn set_fifo_watermark(&mut self, watermark: u16) -> Result<(), Error> {
+        let watermark = match self.state.fifo_count_unit {
+            FifoCountUnit::Bytes => watermark,
+            FifoCountUnit::Records => watermark / self.fifo_sources()?.packet_size() as u16,
+        };
+        self.write_fifo_watermark(watermark)
+    }
+
+    /// Reads the FIFO watermark, in bytes regardless of FIFO_COUNT_REC.
+    pub fn fifo_watermark(&mut self) -> Result<u16, Error> {
+        let watermark = self.read_fifo_watermark()?;
+        match self.state.fifo_count_unit {
+            FifoCountUnit::Bytes => Ok(watermark),
+            FifoCountUnit::Records => Ok(watermark * self.fifo_sources()?.packet_size() as u16),
+        }
+    }
+
+    /// Writes FIFO_WM in the unit of FIFO_COUNT_REC.
+    fn write_fifo_watermark(&mut self, watermark: u16) -> Result<(), Error> {
+        self.register_write(&Bank0::FifoConfig2, watermark as u8)?;
+        self.register_set_bits(
+            &Bank0::FifoConfig3,
+            &FIFO_CONFIG3::FIFO_WM,
+            (watermark >> 8) as u8,
+        )
+    }
+
+    /// Reads FIFO_WM in the unit of FIFO_COUNT_REC.
+    fn read_fifo_watermark(&mut self) -> Result<u16, Error> {
+        let low = self.register_read(&Bank0::FifoConfig2)?;
+        let high = FIFO_CONFIG3::FIFO_WM.get(self.register_read(&Bank0::FifoConfig3)?);
+        Ok(u16::from_le_bytes([low, high]))
+    }
+
+    /// Size of the packets pushed with the enabled FIFO sources, unit: byte
+    pub fn fifo_packet_size(&mut self) -> Result<usize, Error> {
+        Ok(self.fifo_sources()?.packet_size())
+    }
+
+    /// Reads the FIFO sources enabled in FIFO_CONFIG1.
+    fn fifo_sources(&mut self) -> Result<FifoSources, Error> {
+        let config = self.register_read(&Bank0::FifoConfig1)?;
+        Ok(FifoSources::from_config1(config))
+    }
+
+    /// Reads the enabled FIFO sources, and sets FIFO_RESUME_PARTIAL_RD if it is not yet.
+    ///
+    /// Without it, a FIFO_DATA read ending before the last stored byte makes the next read
+    /// restart from the first byte already read. The bit is left set.
+    fn prepare_fifo_read(&mut self) -> Result<FifoSources, Error> {
+        let config = self.register_read(&Bank0::FifoConfig1)?;
+        if FIFO_CONFIG1::FIFO_RESUME_PARTIAL_RD.get(config) == 0 {
+            let partial = FIFO_CONFIG1::FIFO_RESUME_PARTIAL_RD.put(1);
+            self.register_write(&Bank0::FifoConfig1, config | partial)?;
+        }
+        Ok(FifoSources::from_config1(config))
+    }
+
+    /// Reads the next packet of `sources` in one FIFO_DATA burst, `buf` being sized to it and
+    /// `remaining` bytes being stored.
+    ///
+    /// Returns `false` for a FIFO empty marker. A header of other sources means the reads are
+    /// misaligned: the rest of the FIFO content is dropped and [`Error::FifoDesync`] returned.
+    fn read_fifo_packet(
+        &mut self,
+        sources: FifoSources,
+        remaining: usize,
+        buf: &mut [u8],
+    ) -> Result<bool, Error> {
+        self.register_read_burst(&Bank0::FifoData, buf)?;
+        let header = FifoHeader::from_bits(buf[0]);
+        if header.is_empty() {
+            return Ok(false);
+        }
+        if !sources.accepts(header) {
+            self.discard_fifo_bytes(remaining - buf.len())?;
+            return Err(Error::FifoDesync);
+        }
+        Ok(true)
+    }
+
+    /// Reads and drops `count` bytes from the FIFO.
+    ///
+    /// FIFO_COUNT only covers complete packets, so dropping all counted bytes realigns the reads
+    /// on a packet boundary.
+    fn discard_fifo_bytes(&mut self, count: usize) -> Result<(), Error> {
+        let mut buf = [0u8; FifoPacket::MAX_SIZE];
+        let mut left = count;
+        while left > 0 {
+            let len = left.min(buf.len());
+            self.register_read_burst(&Bank0::FifoData, &mut buf[..len])?;
+            left -= len;
+        }
+        Ok(())
+    }
+
+    /// Accumulates a FIFO timestamp into the 64-bit device time.
+    fn extend_device_time(&mut self, timestamp: u16) {
+        let ticks = match self.state.device_time {
+            None => timestamp as u64,
+            Some((ticks, _)) if self.state.timestamp_delta => ticks + timestamp as u64,
+            Some((ticks, prev)) => ticks + timestamp.wrapping_sub(prev) as u64,
+        };
+        self.state.device_time = Some((ticks, timestamp));
+        let resolution = self.state.timestamp_resolution;
+        self.state
+            .odr_estimator
+            .update(resolution.ticks_us(ticks, self.state.rtc_clock));
+    }
+
+    /// Reads the number of bytes pending in the FIFO.
+    pub fn fifo_count(&mut self) -> Result<u16, Error> {
+        let mut buf = [0u8; 2];
+        self.register_read_burst(&Bank0::FifoCountH, &mut buf)?;
+        self.fifo_count_bytes(buf)
+    }
+
+    /// Decodes FIFO_COUNTH/L, in bytes regardless of FIFO_COUNT_REC.
+    fn fifo_count_bytes(&mut self, buf: [u8; 2]) -> Result<u16, Error> {
+        let count = self.state.fifo_count_endian.decode(buf);
+        match self.state.fifo_count_unit {
+            FifoCountUnit::Bytes => Ok(count),
+            FifoCountUnit::Records => Ok(count * self.fifo_sources()?.packet_size() as u16),
+        }
+    }
+
+    /// Selects the unit of FIFO_COUNTH/L, the driver converts both to bytes.
+    ///
+    /// The watermark is converted to the new unit, so it keeps its size in bytes.
+    pub fn set_fifo_count_unit(&mut self, unit: FifoCountUnit) -> Result<(), Error> {
+        let watermark = self.fifo_watermark()?;
+        self.register_set_bits(
+            &Bank0::IntfConfig0,
+            &INTF_CONFIG0::FIFO_COUNT_REC,
+            unit as u8,
+        )?;
+        self.state.fifo_count_unit = unit;
+        self.set_fifo_watermark(watermark)
+    }
+
+    pub fn fifo_count_unit(&mut self) -> Result<FifoCountUnit, Error> {
+        let config = self.register_read(&Bank0::IntfConfig0)?;
+        let unit = INTF_CONFIG0::FIFO_COUNT_REC.get(config).into();
+        self.state.fifo_count_unit = unit;
+        Ok(unit)
+    }
+
+    /// Selects the content of the FIFO packets for invalid samples.
+    pub fn set_fifo_invalid_samples(&mut self, mode: InvalidSampleMode) -> Result<(), Error> {
+        self.register_set_bits(
+            &Bank0::IntfConfig0,
+            &INTF_CONFIG0::FIFO_HOLD_LAST_DATA_EN,
+            mode as u8,
+        )?;
+        self.state.fifo_invalid_samples = mode;
+        Ok(())
+    }
+
+    pub fn fifo_invalid_samples(&mut self) -> Result<InvalidSampleMode, Error> {
+        let config = self.register_read(&Bank0::IntfConfig0)?;
+        let mode = INTF_CONFIG0::FIFO_HOLD_LAST_DATA_EN.get(config).into();
+        self.state.fifo_invalid_samples = mode;
+        Ok(mode)
+    }
+
+    /// Selects the byte order of FIFO_COUNTH/L.
+    pub fn set_fifo_count_endian(&mut self, endian: Endian) -> Result<(), Error> {
+        self.register_set_bits(
+            &Bank0::IntfConfig0,
+            &INTF_CONFIG0::FIFO_COUNT_ENDIAN,
+            endian as u8,
+        )?;
+        self.state.fifo_count_endian = endian;
+        Ok(())
+    }
+
+    pub fn fifo_count_endian(&mut self) -> Result<Endian, Error> {
+        let config = self.register_read(&Bank0::IntfConfig0)?;
+        let endian = INTF_CONFIG0::FIFO_COUNT_ENDIAN.get(config).into();
+        self.state.fifo_count_endian = endian;
+        Ok(endian)
+    }
+
+    pub fn fifo_status(&mut self) -> Result<FifoStatus, Error> {
+        // INT_STATUS and FIFO_COUNTH/L are consecutive.
+        let mut buf = [0u8; 3];
+        self.register_read_burst(&Bank0::IntStatus, &mut buf)?;
+        let flags = INT_STATUS::FIFO_THS_INT.mask() | INT_STATUS::FIFO_FULL_INT.mask();
+        buf[0] = self.take_int_status(buf[0], flags);
+        let count = self.fifo_count_bytes([buf[1], buf[2]])?;
+
+        let mut lost = [0u8; 2];
+        self.register_read_burst(&Bank0::FifoLostPkt0, &mut lost)?;
+        // FIFO_LOST_PKT0 holds the low byte, FIFO_COUNT_ENDIAN does not apply.
+        let lost_packets = u16::from_le_bytes(lost);
+        // The counter only goes back to zero when the FIFO is flushed.
+        let overflow = lost_packets != 0 && lost_packets != self.state.fifo_lost_packets;
+        self.state.fifo_lost_packets = lost_packets;
+        if overflow {
+            // Lost packets would count as longer intervals.
+            self.state.odr_estimator.restart();
+        }
+
+        Ok(FifoStatus {
+            watermark: INT_STATUS::FIFO_THS_INT.get(buf[0]) != 0,
+            full: INT_STATUS::FIFO_FULL_INT.get(buf[0]) != 0,
+            empty: count == 0,
+            count,
+            lost_packets,
+            overflow,
+        })
+    }
+
+    /// Drops the FIFO content, the FIFO count and the lost packet counter go back to zero.
+    pub fn reset_fifo(&mut self) -> Result<(), Error> {
+        self.register_write(&Bank0::SignalPathReset, SIGNAL_PATH_RESET::FIFO_FLUSH)?;
+        self.state.odr_estimator.restart();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Register file of the four user banks, REG_BANK_SEL selects the bank accessed.
+    pub(crate) struct RegisterFile {
+        pub banks: [[u8; 256]; 5],
+        pub bank: usize,
+    }
+
+    impl RegisterFile {
+        pub fn new() -> Self {
+            Self {
+                banks: [[0; 256]; 5],
+                bank: 0,
+            }
+        }
+    }
+
+    impl Interface for RegisterFile {
+        fn read_register(&mut self, address: RegAddr) -> Result<u8, Error> {
+            Ok(self.banks[self.bank][address.0 as usize])
+        }
+
+        fn write_register(&mut self, address: RegAddr, data: u8) -> Result<(), Error> {
+            if address == Bank0::RegBankSel.address() {
+                self.bank = data as usize;
+            } else {
+                self.banks[self.bank][address.0 as usize] = data;
+            }
+            Ok(())
+        }
+    }
+
+    /// Runs `f` on a driver of `interface` with a default state.
+    fn with_driver<R>(interface: &mut dyn Interface, f: impl FnOnce(&mut Driver) -> R) -> R {
+        let mut state = State::new(Units::default());
+        f(&mut Driver {
+            interface,
+            state: &mut state,
+        })
+    }
+
+    #[cfg(feature = "fixed")]
+    #[test]
+    fn to_fixed_scales_with_the_ratio() {
+        let (x, y, z) = to_fixed((2048, -1024, 0), AccelRange::G16.sensitivity_ratio());
+        assert_eq!((x, y, z), (I16F16::ONE, -I16F16::ONE / 2, I16F16::ZERO));
+        let (x, _, _) = to_fixed((164, 0, 0), GyroRange::Dps2000.sensitivity_ratio());
+        assert_eq!(x, I16F16::from_num(10));
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn fifo_count_follows_fifo_count_endian() {
+        let mut interface = RegisterFile::new();
+        interface.banks[0][Bank0::FifoCountH.address().0 as usize..][..2]
+            .copy_from_slice(&[0x01, 0x20]);
+        with_driver(&mut interface, |driver| {
+            driver.set_fifo_count_endian(Endian::Big).unwrap();
+            assert_eq!(driver.fifo_count().unwrap(), 0x0120);
+            driver.set_fifo_count_endian(Endian::Little).unwrap();
+            assert_eq!(driver.fifo_count().unwrap(), 0x2001);
+        });
+    }
+
+    /// Register file popping FIFO_DATA reads from `fifo`.
+    #[cfg(feature = "fifo")]
+    struct FifoFile<'a> {
+        registers: RegisterFile,
+        fifo: &'a [u8],
+    }
+
+    #[cfg(feature = "fifo")]
+    impl Interface for FifoFile<'_> {
+        fn read_register(&mut self, address: RegAddr) -> Result<u8, Error> {
+            self.registers.read_register(address)
+        }
+
+        fn read_registers(&mut self, address: RegAddr, buf: &mut [u8]) -> Result<(), Error> {
+            if self.registers.bank != 0 || address != Bank0::FifoData.address() {
+                return self.registers.read_registers(address, buf);
+            }
+            let (data, rest) = self.fifo.split_at(buf.len());
+            buf.copy_from_slice(data);
+            self.fifo = rest;
+            Ok(())
+        }
+
+        fn write_register(&mut self, address: RegAddr, data: u8) -> Result<(), Error> {
+            self.registers.write_register(address, data)
+        }
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn drain_stats_count_empty_markers_and_desync() {
+        let mut fifo = [0u8; 64];
+        // Accel and gyro packet, empty marker, accel only header, then one more packet.
+        fifo[0] = 0x68;
+        fifo[16] = 0x80;
+        fifo[32] = 0x40;
+        fifo[48] = 0x68;
+        let mut interface = FifoFile {
+            registers: RegisterFile::new(),
+            fifo: &fifo,
+        };
+        interface.registers.banks[0][Bank0::FifoConfig1.address().0 as usize] =
+            FIFO_CONFIG1::FIFO_ACCEL_EN.put(1) | FIFO_CONFIG1::FIFO_GYRO_EN.put(1);
+        interface.registers.banks[0][Bank0::FifoCountL.address().0 as usize] = 64;
+        with_driver(&mut interface, |driver| {
+            let mut packets = 0;
+            let stats = driver.drain_fifo(&mut |_| packets += 1).unwrap();
+            assert_eq!(packets, 1);
+            assert_eq!(stats.packets, 1);
+            assert_eq!(stats.empty_markers, 1);
+            assert_eq!(stats.invalid_headers, 1);
+            assert_eq!(stats.bytes, 64);
+            assert_eq!(stats.discarded_bytes, 48);
+        });
+        assert!(interface.fifo.is_empty());
+    }
+
+    /// Register file with a stationary sensor turning at 10°/s around X, at ±16g and ±2000°/s.
+    #[cfg(feature = "motion")]
+    pub(crate) fn turning_at_10_dps() -> RegisterFile {
+        let mut interface = RegisterFile::new();
+        interface.banks[0][Bank0::AccelConfig0.address().0 as usize] = 0x06;
+        interface.banks[0][Bank0::GyroConfig0.address().0 as usize] = 0x06;
+        interface.banks[0][Bank0::AccelDataZ1.address().0 as usize..][..2]
+            .copy_from_slice(&2048i16.to_be_bytes());
+        interface.banks[0][Bank0::GyroDataX1.address().0 as usize..][..2]
+            .copy_from_slice(&164i16.to_be_bytes());
+        interface
+    }
+
+    #[cfg(feature = "motion")]
+    #[test]
+    fn sample_tracks_gyro_bias() {
+        let mut interface = turning_at_10_dps();
+        with_driver(&mut interface, |driver| {
+            driver.state.gyro_bias.enabled = true;
+
+            for _ in 0..crate::motion::MOTION_WINDOW {
+                driver.sample().unwrap();
+            }
+            let bias = driver.state.gyro_bias.bias().unwrap();
+            assert!((bias.0 - 10.0).abs() < 1e-3, "{bias:?}");
+            assert!(driver.sample().unwrap().gyro.0.abs() < 1e-3);
+            assert!(
+                driver
+                    .read_sample_with_temp()
+                    .unwrap()
+                    .sample
+                    .angular_velocity
+                    .0
+                    .abs()
+                    < 1e-3
+            );
+        });
+    }
+
+    #[cfg(all(feature = "fifo", feature = "motion"))]
+    #[test]
+    fn fifo_samples_track_gyro_bias() {
+        const PACKETS: usize = crate::motion::MOTION_WINDOW + 1;
+        let mut fifo = [0u8; 16 * PACKETS];
+        for packet in fifo.chunks_mut(16) {
+            packet[0] = 0x68;
+            packet[5..7].copy_from_slice(&2048i16.to_be_bytes());
+            packet[7..9].copy_from_slice(&164i16.to_be_bytes());
+        }
+        let mut interface = FifoFile {
+            registers: turning_at_10_dps(),
+            fifo: &fifo,
+        };
+        interface.registers.banks[0][Bank0::FifoConfig1.address().0 as usize] =
+            FIFO_CONFIG1::FIFO_ACCEL_EN.put(1) | FIFO_CONFIG1::FIFO_GYRO_EN.put(1);
+        interface.registers.banks[0][Bank0::FifoCountL.address().0 as usize] = 16;
+        with_driver(&mut interface, |driver| {
+            driver.state.gyro_bias.enabled = true;
+            driver.state.data_source = DataSource::Fifo;
+
+            let mut last = None;
+            for _ in 0..PACKETS {
+                last = driver.read_sample().unwrap();
+            }
+            let bias = driver.state.gyro_bias.bias().unwrap();
+            assert!((bias.0 - 10.0).abs() < 1e-3, "{bias:?}");
+            assert!(last.unwrap().angular_velocity.0.abs() < 1e-3);
+        });
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn fifo_watermark_follows_fifo_count_unit() {
+        let mut interface = RegisterFile::new();
+        interface.banks[0][Bank0::FifoConfig1.address().0 as usize] =
+            FIFO_CONFIG1::FIFO_ACCEL_EN.put(1) | FIFO_CONFIG1::FIFO_GYRO_EN.put(1);
+        with_driver(&mut interface, |driver| {
+            driver.set_fifo_watermark(320).unwrap();
+            assert_eq!(driver.read_fifo_watermark().unwrap(), 320);
+            // 16 byte packets.
+            driver.set_fifo_count_unit(FifoCountUnit::Records).unwrap();
+            assert_eq!(driver.read_fifo_watermark().unwrap(), 20);
+            assert_eq!(driver.fifo_watermark().unwrap(), 320);
+            driver.set_fifo_watermark(100).unwrap();
+            assert_eq!(driver.read_fifo_watermark().unwrap(), 6);
+            assert_eq!(driver.fifo_watermark().unwrap(), 96);
+        });
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn moderate_interrupts_watermark_in_fifo_count_unit() {
+        for (unit, watermark) in [(FifoCountUnit::Bytes, 128), (FifoCountUnit::Records, 8)] {
+            let mut interface = RegisterFile::new();
+            // 8kHz ODRs, 16 byte packets.
+            interface.banks[0][Bank0::AccelConfig0.address().0 as usize] = 0x03;
+            interface.banks[0][Bank0::GyroConfig0.address().0 as usize] = 0x03;
+            with_driver(&mut interface, |driver| {
+                driver.set_fifo_count_unit(unit).unwrap();
+
+                let packets = driver.moderate_interrupts(InterruptPin::Int1, 1000.0);
+                assert_eq!(packets.unwrap(), 8);
+                assert_eq!(driver.read_fifo_watermark().unwrap(), watermark, "{unit:?}");
+                assert_eq!(driver.fifo_watermark().unwrap(), 128);
+                let source = driver.register_read(&Bank0::IntSource0).unwrap();
+                assert_eq!(INT_SOURCE0::FIFO_THS_INT1_EN.get(source), 1);
+            });
+        }
+    }
+
+    #[test]
+    fn latch_timestamp_is_little_endian_20_bit() {
+        let mut interface = RegisterFile::new();
+        interface.banks[1][Bank1::TmstVal0.address().0 as usize..][..3]
+            .copy_from_slice(&[0x56, 0x34, 0xF2]);
+        with_driver(&mut interface, |driver| {
+            // The sensor data endian does not apply to TMST_VALUE.
+            driver.state.sensor_data_endian = Endian::Big;
+            assert_eq!(driver.latch_timestamp().unwrap(), 0x2_3456);
+        });
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn device_time_without_rtc_counts_32_30_ticks() {
+        let mut interface = RegisterFile::new();
+        with_driver(&mut interface, |driver| {
+            for timestamp in [1000, 31000, 61000, 25464] {
+                driver.extend_device_time(timestamp);
+            }
+            // Starts at the first timestamp, the last one wrapped.
+            assert_eq!(driver.state.device_time_us(), Some(91000 * 32 / 30));
+            driver.state.rtc_clock = true;
+            assert_eq!(driver.state.device_time_us(), Some(91000));
+        });
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn device_time_with_rtc_counts_rtc_periods() {
+        let mut interface = RegisterFile::new();
+        with_driver(&mut interface, |driver| {
+            driver.state.timestamp_resolution = TimestampResolution::Us16;
+            driver.state.rtc_clock = true;
+
+            for timestamp in [0, 16384, 32768] {
+                driver.extend_device_time(timestamp);
+            }
+            // 32768 RTC clock periods.
+            assert_eq!(driver.state.device_time_us(), Some(1_000_000));
+            driver.state.rtc_clock = false;
+            assert_eq!(driver.state.device_time_us(), Some(32768 * 16 * 32 / 30));
+
+            // Ticks of another resolution do not add up.
+            driver
+                .set_timestamp_resolution(TimestampResolution::Us1)
+                .unwrap();
+            assert_eq!(driver.state.device_time_us(), None);
+        });
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn measured_odr_without_rtc_counts_32_30_ticks() {
+        let mut interface = RegisterFile::new();
+        with_driver(&mut interface, |driver| {
+            // 75 ticks of 32/30µs, a 12.5kHz ODR.
+            for i in 0..=crate::fifo::ODR_WINDOW {
+                driver.extend_device_time((i * 75) as u16);
+            }
+            assert_eq!(driver.state.odr_estimator.estimate(), Some(12500.0));
+
+            // 32 RTC clock periods at 16µs resolution with an RTC clock, a 1024Hz ODR.
+            driver.state.timestamp_resolution = TimestampResolution::Us16;
+            driver.state.rtc_clock = true;
+            driver.state.device_time = None;
+            driver.state.odr_estimator.restart();
+            for i in 0..=crate::fifo::ODR_WINDOW {
+                driver.extend_device_time((i * 32) as u16);
+            }
+            assert_eq!(driver.state.odr_estimator.estimate(), Some(1024.0));
+        });
+    }
+
+    /// Register file latching a timestamp counter at 10000 into TMST_VALUE on TMST_STROBE, when
+    /// TMST_TO_REGS_EN is set.
+    struct StrobedTimestamp(RegisterFile);
+
+    impl Interface for StrobedTimestamp {
+        fn read_register(&mut self, address: RegAddr) -> Result<u8, Error> {
+            self.0.read_register(address)
+        }
+
+        fn write_register(&mut self, address: RegAddr, data: u8) -> Result<(), Error> {
+            self.0.write_register(address, data)?;
+            let strobe = self.0.bank == 0
+                && address == Bank0::SignalPathReset.address()
+                && data & SIGNAL_PATH_RESET::TMST_STROBE != 0;
+            let config = self.0.banks[0][Bank0::TmstConfig.address().0 as usize];
+            if strobe && TMST_CONFIG::TMST_TO_REGS_EN.get(config) != 0 {
+                self.0.banks[1][Bank1::TmstVal0.address().0 as usize..][..3]
+                    .copy_from_slice(&10000u32.to_le_bytes()[..3]);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn init_enables_timestamp_latching() {
+        let mut interface = StrobedTimestamp(RegisterFile::new());
+        interface.0.banks[0][Bank0::WhoAmI.address().0 as usize] = ICM42688P_DEVICE_ID;
+        // Reset values.
+        interface.0.banks[0][Bank0::AccelConfig0.address().0 as usize] = 0x06;
+        interface.0.banks[0][Bank0::GyroConfig0.address().0 as usize] = 0x06;
+        interface.0.banks[0][Bank0::TmstConfig.address().0 as usize] = 0x23;
+        with_driver(&mut interface, |driver| {
+            driver.init().unwrap();
+            assert_eq!(driver.latch_timestamp().unwrap(), 10000);
+            // Without the RTC, one tick lasts 32/30µs.
+            assert_eq!(driver.sample().unwrap().timestamp, 10666);
+        });
+        assert_eq!(
+            interface.0.banks[0][Bank0::TmstConfig.address().0 as usize],
+            0x33
+        );
+    }
+}
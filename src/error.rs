@@ -1,14 +1,127 @@
+use bitflags::bitflags;
+
+use crate::interface::RegAddr;
+
 #[derive(Clone, Eq, PartialEq, Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Pin operation failed.
-    Pin,
+    Pin(PinFault),
 
     /// SPI communication failed.
-    Spi,
+    Spi(BusAccess),
+
+    /// I2C communication failed.
+    I2c(BusAccess),
 
     /// Unknown device id.
     BadDeviceId,
 
     /// The data returned from the sensor is invalid.
     DataCorrupted,
+
+    /// The setting is not supported in the current power mode.
+    InvalidForPowerMode,
+
+    /// The interrupt routing is inconsistent.
+    InterruptConflict(InterruptConflicts),
+
+    /// A FIFO packet header does not match the enabled FIFO sources, the remaining FIFO content
+    /// was dropped to realign on packet boundaries.
+    FifoDesync,
+
+    /// No interrupt timing satisfies both the host and the sensor constraints.
+    InterruptTiming,
+
+    /// The awaited event did not happen in time.
+    Timeout,
+
+    /// The sensor was disabled with [`crate::Icm42688p::restrict_capabilities`].
+    SensorDisabled,
+
+    /// The sensor data may be corrupted by a maintenance operation in progress or interrupted
+    /// by an error, see [`crate::Icm42688p::maintenance`].
+    Busy(Maintenance),
+}
+
+/// Operation during which the sensor data is not reliable.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Maintenance {
+    /// [`crate::Icm42688p::self_test_online`]
+    SelfTest,
+    /// [`crate::Icm42688p::init_dmp`]
+    ApexInit,
+    /// [`crate::Icm42688p::apply_config`]
+    Reconfiguration,
+}
+
+/// Register access during which a bus error occurred.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BusAccess {
+    pub operation: BusOperation,
+    /// Address of the first register accessed.
+    pub address: RegAddr,
+}
+
+impl BusAccess {
+    pub fn read(address: RegAddr) -> Self {
+        Self {
+            operation: BusOperation::Read,
+            address,
+        }
+    }
+
+    pub fn write(address: RegAddr) -> Self {
+        Self {
+            operation: BusOperation::Write,
+            address,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BusOperation {
+    Read,
+    Write,
+}
+
+/// Pin operation that failed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PinFault {
+    pub action: PinAction,
+    /// Register access the pin operation was part of, if any.
+    pub access: Option<BusAccess>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PinAction {
+    /// Driving the SPI chip select low.
+    ChipSelect,
+    /// Driving the SPI chip select high.
+    ChipDeselect,
+    /// Waiting for an interrupt pin.
+    InterruptWait,
+}
+
+bitflags! {
+    /// Interrupt routing mistakes found by [`crate::Icm42688p::check_interrupts`].
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct InterruptConflicts: u8 {
+        /// A UI, FIFO, SMD or WOM interrupt is routed to both pins.
+        const DuplicateRouting = 1 << 0;
+        /// An APEX interrupt is routed to both pins.
+        const DuplicateApexRouting = 1 << 1;
+        /// The tap interrupt is routed but tap detection is disabled.
+        const TapDisabled = 1 << 2;
+        /// A step interrupt is routed but the pedometer is disabled.
+        const PedometerDisabled = 1 << 3;
+        /// The tilt interrupt is routed but tilt detection is disabled.
+        const TiltDisabled = 1 << 4;
+        /// A wake or sleep interrupt is routed but raise to wake/sleep is disabled.
+        const RaiseToWakeDisabled = 1 << 5;
+        /// A WOM interrupt is routed but wake on motion is disabled.
+        const WomDisabled = 1 << 6;
+        /// The SMD interrupt is routed but significant motion detection is disabled.
+        const SmdDisabled = 1 << 7;
+    }
 }
@@ -0,0 +1,262 @@
+//! C ABI for firmware written in C.
+//!
+//! The bus is provided by the caller as a set of callbacks, see [`IcmBus`]. The driver is stored
+//! in caller-provided memory of [`icm42688p_size`] bytes aligned to [`icm42688p_align`], so no
+//! allocator is required. The final static library must provide a panic handler, e.g. from
+//! `panic-halt`.
+//!
+//! All functions return [`ICM_OK`] on success and one of the negative `ICM_ERR_*` codes on
+//! failure.
+//!
+//! The `ffi/` directory of the repository packages this module as a static and dynamic library,
+//! with the matching C header in `ffi/include/icm42688p.h`.
+
+use core::{
+    ffi::c_void,
+    mem::{align_of, size_of},
+};
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{
+    config::{AccelRange, GyroRange, Units},
+    error::{BusAccess, Error},
+    fifo::FifoPacket,
+    interface::{Interface, RegAddr},
+    Icm42688p,
+};
+
+pub const ICM_OK: i32 = 0;
+/// [`icm42688p_read_sample`] found no new sample.
+pub const ICM_NO_DATA: i32 = 1;
+pub const ICM_ERR_BUS: i32 = -1;
+pub const ICM_ERR_BAD_DEVICE_ID: i32 = -2;
+pub const ICM_ERR_DATA_CORRUPTED: i32 = -3;
+pub const ICM_ERR_INVALID_ARGUMENT: i32 = -4;
+pub const ICM_ERR_INVALID_FOR_POWER_MODE: i32 = -5;
+pub const ICM_ERR_FIFO_DESYNC: i32 = -6;
+pub const ICM_ERR_OTHER: i32 = -7;
+pub const ICM_ERR_BUSY: i32 = -8;
+
+/// Bus callbacks, `ctx` is passed back unchanged to every callback.
+///
+/// `read` and `write` return 0 on success.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct IcmBus {
+    pub ctx: *mut c_void,
+    /// Reads `len` consecutive registers starting at `address` into `buf`.
+    pub read: unsafe extern "C" fn(ctx: *mut c_void, address: u8, buf: *mut u8, len: usize) -> i32,
+    /// Writes `data` to the register at `address`.
+    pub write: unsafe extern "C" fn(ctx: *mut c_void, address: u8, data: u8) -> i32,
+    /// Blocks for at least `us` microseconds.
+    pub delay_us: unsafe extern "C" fn(ctx: *mut c_void, us: u32),
+}
+
+impl Interface for IcmBus {
+    fn read_register(&mut self, address: RegAddr) -> Result<u8, Error> {
+        let mut byte = 0;
+        self.read_registers(address, core::slice::from_mut(&mut byte))?;
+        Ok(byte)
+    }
+
+    fn read_registers(&mut self, address: RegAddr, buf: &mut [u8]) -> Result<(), Error> {
+        match unsafe { (self.read)(self.ctx, address.0, buf.as_mut_ptr(), buf.len()) } {
+            0 => Ok(()),
+            _ => Err(Error::Spi(BusAccess::read(address))),
+        }
+    }
+
+    fn write_register(&mut self, address: RegAddr, data: u8) -> Result<(), Error> {
+        match unsafe { (self.write)(self.ctx, address.0, data) } {
+            0 => Ok(()),
+            _ => Err(Error::Spi(BusAccess::write(address))),
+        }
+    }
+}
+
+impl DelayNs for IcmBus {
+    fn delay_ns(&mut self, ns: u32) {
+        unsafe { (self.delay_us)(self.ctx, ns.div_ceil(1000)) }
+    }
+}
+
+/// Sample in the default units, g and °/s.
+#[repr(C)]
+pub struct IcmSample {
+    pub accel: [f32; 3],
+    pub gyro: [f32; 3],
+    /// See [`crate::Sample::sequence`].
+    pub sequence: u32,
+}
+
+/// Raw FIFO packet.
+#[repr(C)]
+pub struct IcmFifoPacket {
+    pub header: u8,
+    /// The 16 most significant bits in 20 byte packets.
+    pub accel: [i16; 3],
+    /// The 16 most significant bits in 20 byte packets.
+    pub gyro: [i16; 3],
+    pub temperature: i16,
+    pub timestamp: u16,
+    /// 18-bit accel data of 20 byte packets, 0 in the other packets.
+    ///
+    /// Unit: 1 / [`crate::fifo::HIRES_ACCEL_SENSITIVITY`] g
+    pub hires_accel: [i32; 3],
+    /// 19-bit gyro data of 20 byte packets, 0 in the other packets.
+    ///
+    /// Unit: 1 / [`crate::fifo::HIRES_GYRO_SENSITIVITY`] °/s
+    pub hires_gyro: [i32; 3],
+}
+
+type Device = Icm42688p<IcmBus>;
+
+fn error_code(error: Error) -> i32 {
+    match error {
+        Error::Pin(_) | Error::Spi(_) | Error::I2c(_) => ICM_ERR_BUS,
+        Error::BadDeviceId => ICM_ERR_BAD_DEVICE_ID,
+        Error::DataCorrupted => ICM_ERR_DATA_CORRUPTED,
+        Error::InvalidForPowerMode => ICM_ERR_INVALID_FOR_POWER_MODE,
+        Error::FifoDesync => ICM_ERR_FIFO_DESYNC,
+        Error::Busy(_) => ICM_ERR_BUSY,
+        _ => ICM_ERR_OTHER,
+    }
+}
+
+fn status(result: Result<(), Error>) -> i32 {
+    match result {
+        Ok(()) => ICM_OK,
+        Err(error) => error_code(error),
+    }
+}
+
+/// Size of the memory to pass to [`icm42688p_init`], in bytes.
+#[no_mangle]
+pub extern "C" fn icm42688p_size() -> usize {
+    size_of::<Device>()
+}
+
+/// Alignment of the memory to pass to [`icm42688p_init`], in bytes.
+#[no_mangle]
+pub extern "C" fn icm42688p_align() -> usize {
+    align_of::<Device>()
+}
+
+/// Initializes a driver in `dev`, turning both sensors on in low noise mode.
+///
+/// # Safety
+///
+/// `dev` must point to writable memory of [`icm42688p_size`] bytes aligned to
+/// [`icm42688p_align`]. The callbacks of `bus` must stay valid as long as the driver is used.
+#[no_mangle]
+pub unsafe extern "C" fn icm42688p_init(dev: *mut c_void, bus: IcmBus) -> i32 {
+    let dev = dev as *mut Device;
+    if dev.is_null() || !dev.is_aligned() {
+        return ICM_ERR_INVALID_ARGUMENT;
+    }
+    let mut delay = bus;
+    match Device::with_interface(bus, Units::default(), &mut delay) {
+        Ok(device) => {
+            dev.write(device);
+            ICM_OK
+        }
+        Err(error) => error_code(error),
+    }
+}
+
+/// Reads a sample into `out`, returns [`ICM_NO_DATA`] if no new sample is available.
+///
+/// # Safety
+///
+/// `dev` must have been initialized by [`icm42688p_init`] and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn icm42688p_read_sample(dev: *mut c_void, out: *mut IcmSample) -> i32 {
+    let dev = &mut *(dev as *mut Device);
+    match dev.read_sample() {
+        Ok(Some(sample)) => {
+            let (ax, ay, az) = sample.acceleration;
+            let (gx, gy, gz) = sample.angular_velocity;
+            out.write(IcmSample {
+                accel: [ax, ay, az],
+                gyro: [gx, gy, gz],
+                sequence: sample.sequence,
+            });
+            ICM_OK
+        }
+        Ok(None) => ICM_NO_DATA,
+        Err(error) => error_code(error),
+    }
+}
+
+/// Sets the accel full scale range, `range` is the ACCEL_FS_SEL register value.
+///
+/// # Safety
+///
+/// `dev` must have been initialized by [`icm42688p_init`].
+#[no_mangle]
+pub unsafe extern "C" fn icm42688p_set_accel_range(dev: *mut c_void, range: u8) -> i32 {
+    let dev = &mut *(dev as *mut Device);
+    match AccelRange::try_from(range) {
+        Ok(range) => status(dev.set_accel_range(range)),
+        Err(_) => ICM_ERR_INVALID_ARGUMENT,
+    }
+}
+
+/// Sets the gyro full scale range, `range` is the GYRO_FS_SEL register value.
+///
+/// # Safety
+///
+/// `dev` must have been initialized by [`icm42688p_init`].
+#[no_mangle]
+pub unsafe extern "C" fn icm42688p_set_gyro_range(dev: *mut c_void, range: u8) -> i32 {
+    let dev = &mut *(dev as *mut Device);
+    match GyroRange::try_from(range) {
+        Ok(range) => status(dev.set_gyro_range(range)),
+        Err(_) => ICM_ERR_INVALID_ARGUMENT,
+    }
+}
+
+/// Drains the FIFO into `out`, storing the number of packets written in `count`.
+///
+/// Packets beyond `capacity` are read from the FIFO but dropped. Returns
+/// [`ICM_ERR_FIFO_DESYNC`] when a packet header did not match the enabled sources, `count` then
+/// holds the packets written before it.
+///
+/// # Safety
+///
+/// `dev` must have been initialized by [`icm42688p_init`], `out` must be valid for `capacity`
+/// writes and `count` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn icm42688p_fifo_drain(
+    dev: *mut c_void,
+    out: *mut IcmFifoPacket,
+    capacity: usize,
+    count: *mut usize,
+) -> i32 {
+    let dev = &mut *(dev as *mut Device);
+    let mut written = 0;
+    let result = dev.drain_fifo(|packet: &FifoPacket| {
+        if written < capacity {
+            let (hires_accel, hires_gyro) = match packet.hires_extension() {
+                Some(_) => packet.hires_data(),
+                None => Default::default(),
+            };
+            out.add(written).write(IcmFifoPacket {
+                header: packet.header.bits(),
+                accel: [packet.accel_x, packet.accel_y, packet.accel_z],
+                gyro: [packet.gyro_x, packet.gyro_y, packet.gyro_z],
+                temperature: packet.temperature,
+                timestamp: packet.timestamp,
+                hires_accel: hires_accel.into(),
+                hires_gyro: hires_gyro.into(),
+            });
+            written += 1;
+        }
+    });
+    count.write(written);
+    match result {
+        Ok(stats) if stats.invalid_headers != 0 => ICM_ERR_FIFO_DESYNC,
+        result => status(result.map(|_| ())),
+    }
+}
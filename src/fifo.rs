@@ -1,54 +1,1073 @@
-use bitflags::bitflags;
-
-bitflags! {
-    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-    pub struct HeaderFlags: u8 {
-        ///  FIFO is empty
-        const FifoEmpty = 1 << 7;
-        /// Packet is sized so that accel data have location in the packet, FIFO_ACCEL_EN must be 1
-        const ContainsAccelData = 1 << 6;
-        /// Packet is sized so that gyro data have location in the packet, FIFO_GYRO_EN must be 1
-        const ContainsGyroData = 1 << 5;
-        /// Packet contains ODR Timestamp
-        const ContainsODRTimestamp = 0b10 << 2;
-        /// Packet contains FSYNC time, and this packet is flagged as first ODR after FSYNC (only if FIFO_TMST_FSYNC_EN is 1)
-        const ContainsFsyncTime = 0b11 << 2;
-        ///  The ODR for accel is different for this accel data packet compared to the previous accel packet
-        const AccelODRDiff = 1 << 1;
-        /// The ODR for gyro is different for this gyro data packet compared to the previous gyro packet
-        const GyroODRDiff = 1 << 0;
-    }
-}
-
-struct FifoPacket {
-    header: u8,
-    accel_x: u16,
-    accel_y: u16,
-    accel_z: u16,
-    gyro_x: u16,
-    gyro_y: u16,
-    gyro_z: u16,
-    temperature: u16,
-    timestamp: u16,
-    ext_accel_x_gyro_x: u8,
-    ext_accel_y_gyro_y: u8,
-    ext_accel_z_gyro_z: u8,
+//! FIFO packets and configuration.
+//!
+//! The packet parser does no IO, FIFO bytes fetched by DMA or received over a link can be
+//! decoded anywhere with [`parse_packets`].
+//!
+//! The FIFO stores every sample at the accel and gyro ODRs. Unlike later TDK parts, the
+//! ICM-42688-P has no FIFO decimation (FDR_CONFIG) register, so a low-rate logger fed alongside
+//! a high-rate control loop has to keep every Nth packet itself, e.g. in the
+//! [`Icm42688p::drain_fifo`] callback.
+
+use crate::{
+    config::{AccelRange, Endian, GyroRange, TimestampResolution},
+    error::Error,
+    limits,
+    register::{Bank0, Register, FIFO_CONFIG1},
+    Icm42688p, Interface, MillisDurationU32, RegAddr,
+};
+
+/// Header byte of a FIFO packet
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FifoHeader(u8);
+
+/// Content of the timestamp field of a FIFO packet, see [`FifoHeader::timestamp_kind`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimestampKind {
+    /// No timestamp, or the reserved encoding.
+    None,
+    /// ODR timestamp.
+    Odr,
+    /// Time from the FSYNC event, the packet is the first one after it (FIFO_TMST_FSYNC_EN set).
+    Fsync,
 }
 
-impl FifoPacket {
-    pub fn accelerometer(&self) -> Option<(u16, u16, u16)> {
-        if self.header & HeaderFlags::ContainsAccelData.bits() != 0 {
-            Some((self.accel_x, self.accel_y, self.accel_z))
+impl FifoHeader {
+    const EMPTY: u8 = 1 << 7;
+    const ACCEL: u8 = 1 << 6;
+    const GYRO: u8 = 1 << 5;
+    const HIRES: u8 = 1 << 4;
+    const TIMESTAMP_FSYNC_OFFSET: u8 = 2;
+    const ODR_ACCEL: u8 = 1 << 1;
+    const ODR_GYRO: u8 = 1 << 0;
+
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Whether the byte is the FIFO empty marker rather than a packet header.
+    pub const fn is_empty(self) -> bool {
+        self.0 & Self::EMPTY != 0
+    }
+
+    /// Whether the packet is sized to hold accel data, FIFO_ACCEL_EN must be set.
+    pub const fn has_accel(self) -> bool {
+        self.0 & Self::ACCEL != 0
+    }
+
+    /// Whether the packet is sized to hold gyro data, FIFO_GYRO_EN must be set.
+    pub const fn has_gyro(self) -> bool {
+        self.0 & Self::GYRO != 0
+    }
+
+    /// Whether the packet holds a new and valid sample of 20-bit accel and/or gyro data.
+    pub const fn has_hires(self) -> bool {
+        self.0 & Self::HIRES != 0
+    }
+
+    /// What the timestamp field of the packet holds.
+    pub const fn timestamp_kind(self) -> TimestampKind {
+        match (self.0 >> Self::TIMESTAMP_FSYNC_OFFSET) & 0b11 {
+            0b10 => TimestampKind::Odr,
+            0b11 => TimestampKind::Fsync,
+            _ => TimestampKind::None,
+        }
+    }
+
+    /// Whether the accel ODR differs from the one of the previous packet with accel data.
+    pub const fn accel_odr_changed(self) -> bool {
+        self.0 & Self::ODR_ACCEL != 0
+    }
+
+    /// Whether the gyro ODR differs from the one of the previous packet with gyro data.
+    pub const fn gyro_odr_changed(self) -> bool {
+        self.0 & Self::ODR_GYRO != 0
+    }
+
+    /// Size of the packet starting with this header, in bytes.
+    ///
+    /// Returns `None` for the FIFO empty marker and for headers flagging neither accel nor gyro
+    /// data.
+    pub const fn packet_size(self) -> Option<usize> {
+        if self.is_empty() {
+            None
+        } else if self.has_hires() {
+            Some(limits::FIFO_PACKET_SIZE_HIRES)
+        } else if self.has_accel() && self.has_gyro() {
+            Some(limits::FIFO_PACKET_SIZE_COMBINED)
+        } else if self.has_accel() || self.has_gyro() {
+            Some(limits::FIFO_PACKET_SIZE_SINGLE)
         } else {
             None
         }
     }
+}
+
+/// Sensitivity of the 20-bit FIFO accel data, at the forced ±16g range, unit: LSB/g
+pub const HIRES_ACCEL_SENSITIVITY: f32 = 8192.0;
+/// Sensitivity of the 20-bit FIFO gyro data, at the forced ±2000°/s range, unit: LSB/(°/s)
+pub const HIRES_GYRO_SENSITIVITY: f32 = 131.0;
+
+/// Packet read from the FIFO.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FifoPacket {
+    pub(crate) header: FifoHeader,
+    pub(crate) accel_x: i16,
+    pub(crate) accel_y: i16,
+    pub(crate) accel_z: i16,
+    pub(crate) gyro_x: i16,
+    pub(crate) gyro_y: i16,
+    pub(crate) gyro_z: i16,
+    pub(crate) temperature: i16,
+    pub(crate) timestamp: u16,
+    pub(crate) ext_accel_x_gyro_x: u8,
+    pub(crate) ext_accel_y_gyro_y: u8,
+    pub(crate) ext_accel_z_gyro_z: u8,
+    /// Whether invalid samples carry the invalid code.
+    pub(crate) invalid_code: bool,
+}
+
+impl FifoPacket {
+    /// Size of the largest packet (packet 4), in bytes.
+    pub const MAX_SIZE: usize = limits::FIFO_PACKET_SIZE_HIRES;
+
+    /// Size of the packet starting with `header`, in bytes, see [`FifoHeader::packet_size`].
+    pub(crate) fn size(header: u8) -> Option<usize> {
+        FifoHeader(header).packet_size()
+    }
+
+    /// Decodes a packet, `buf` must be exactly [`Self::size`] bytes long.
+    pub(crate) fn parse(buf: &[u8], endian: Endian, invalid_samples: InvalidSampleMode) -> Self {
+        let word = |i: usize| endian.decode([buf[i], buf[i + 1]]);
+        let data = |i: usize| endian.decode_i16([buf[i], buf[i + 1]]);
+        let mut packet = Self {
+            header: FifoHeader(buf[0]),
+            accel_x: 0,
+            accel_y: 0,
+            accel_z: 0,
+            gyro_x: 0,
+            gyro_y: 0,
+            gyro_z: 0,
+            temperature: 0,
+            timestamp: 0,
+            ext_accel_x_gyro_x: 0,
+            ext_accel_y_gyro_y: 0,
+            ext_accel_z_gyro_z: 0,
+            invalid_code: invalid_samples == InvalidSampleMode::InvalidCode,
+        };
+        match buf.len() {
+            8 => {
+                let (x, y, z) = (data(1), data(3), data(5));
+                if packet.header.has_accel() {
+                    (packet.accel_x, packet.accel_y, packet.accel_z) = (x, y, z);
+                } else {
+                    (packet.gyro_x, packet.gyro_y, packet.gyro_z) = (x, y, z);
+                }
+                packet.temperature = buf[7] as i8 as i16;
+            }
+            16 => {
+                (packet.accel_x, packet.accel_y, packet.accel_z) = (data(1), data(3), data(5));
+                (packet.gyro_x, packet.gyro_y, packet.gyro_z) = (data(7), data(9), data(11));
+                packet.temperature = buf[13] as i8 as i16;
+                packet.timestamp = word(14);
+            }
+            20 => {
+                (packet.accel_x, packet.accel_y, packet.accel_z) = (data(1), data(3), data(5));
+                (packet.gyro_x, packet.gyro_y, packet.gyro_z) = (data(7), data(9), data(11));
+                packet.temperature = data(13);
+                packet.timestamp = word(15);
+                packet.ext_accel_x_gyro_x = buf[17];
+                packet.ext_accel_y_gyro_y = buf[18];
+                packet.ext_accel_z_gyro_z = buf[19];
+            }
+            _ => unreachable!(),
+        }
+        packet
+    }
 
-    pub fn gyroscope(&self) -> Option<(u16, u16, u16)> {
-        if self.header & HeaderFlags::ContainsGyroData.bits() != 0 {
-            Some((self.gyro_x, self.gyro_y, self.gyro_z))
+    pub fn header(&self) -> FifoHeader {
+        self.header
+    }
+
+    /// Layout of the packet.
+    pub fn kind(&self) -> FifoPacketKind {
+        let header = self.header;
+        if header.has_hires() {
+            FifoPacketKind::Hires
+        } else if !header.has_gyro() {
+            FifoPacketKind::Accel
+        } else if !header.has_accel() {
+            FifoPacketKind::Gyro
+        } else {
+            FifoPacketKind::Combined
+        }
+    }
+
+    /// ODR timestamp, `None` for packets without one or holding an FSYNC time instead.
+    pub fn odr_timestamp(&self) -> Option<u16> {
+        self.timestamp_field(TimestampKind::Odr)
+    }
+
+    /// Time from the FSYNC event to this sample, `None` unless the packet is the first one after
+    /// an FSYNC event.
+    pub fn fsync_time(&self) -> Option<u16> {
+        self.timestamp_field(TimestampKind::Fsync)
+    }
+
+    /// 16-bit timestamp field, holding either the ODR timestamp or the FSYNC time, `None` for 8
+    /// byte packets.
+    pub fn timestamp(&self) -> Option<u16> {
+        let has_field = self.header.packet_size()? >= limits::FIFO_PACKET_SIZE_COMBINED;
+        has_field.then_some(self.timestamp)
+    }
+
+    /// Whether the packet is the first one after an FSYNC event, its timestamp field then holds
+    /// the FSYNC time.
+    pub fn is_fsync_tagged(&self) -> bool {
+        self.fsync_time().is_some()
+    }
+
+    /// [`Self::odr_timestamp`] at `resolution`, unit: µs
+    ///
+    /// See [`TimestampResolution::ticks_us`] for `rtc_clock`.
+    pub fn odr_timestamp_us(
+        &self,
+        resolution: TimestampResolution,
+        rtc_clock: bool,
+    ) -> Option<u32> {
+        Some(resolution.ticks_us(self.odr_timestamp()?.into(), rtc_clock) as u32)
+    }
+
+    fn timestamp_field(&self, kind: TimestampKind) -> Option<u16> {
+        let has_field = self.header.packet_size()? >= limits::FIFO_PACKET_SIZE_COMBINED;
+        if has_field && self.header.timestamp_kind() == kind {
+            Some(self.timestamp)
         } else {
             None
         }
     }
+
+    /// Temperature data, 8-bit in 8 and 16 byte packets, 16-bit in 20 byte packets.
+    pub fn raw_temperature(&self) -> i16 {
+        self.temperature
+    }
+
+    /// [`Self::raw_temperature`], `None` for the invalid code the sensor inserts with
+    /// [`InvalidSampleMode::InvalidCode`].
+    pub fn temperature(&self) -> Option<i16> {
+        let invalid = match self.kind() {
+            FifoPacketKind::Hires => i16::MIN,
+            _ => i8::MIN as i16,
+        };
+        (!self.invalid_code || self.temperature != invalid).then_some(self.temperature)
+    }
+
+    /// Unit: °C
+    pub fn temperature_celsius(&self) -> f32 {
+        match self.kind() {
+            FifoPacketKind::Hires => self.temperature as f32 / 132.48 + 25.0,
+            _ => self.temperature as f32 / 2.07 + 25.0,
+        }
+    }
+
+    /// 18-bit accel data of a 20 byte packet, in [`HIRES_ACCEL_SENSITIVITY`] units.
+    ///
+    /// `None` for an invalid sample, see [`Self::accelerometer`].
+    pub fn hires_accelerometer(&self) -> Option<(i32, i32, i32)> {
+        self.accelerometer()?;
+        (self.kind() == FifoPacketKind::Hires).then(|| self.hires_data().0)
+    }
+
+    /// 19-bit gyro data of a 20 byte packet, in [`HIRES_GYRO_SENSITIVITY`] units.
+    ///
+    /// `None` for an invalid sample, see [`Self::gyroscope`].
+    pub fn hires_gyroscope(&self) -> Option<(i32, i32, i32)> {
+        self.gyroscope()?;
+        (self.kind() == FifoPacketKind::Hires).then(|| self.hires_data().1)
+    }
+
+    /// Extension bytes of a 20 byte packet, accel bits in the high nibble and gyro bits in the
+    /// low nibble of each byte, for X, Y and Z.
+    pub fn hires_extension(&self) -> Option<[u8; 3]> {
+        (self.kind() == FifoPacketKind::Hires).then_some([
+            self.ext_accel_x_gyro_x,
+            self.ext_accel_y_gyro_y,
+            self.ext_accel_z_gyro_z,
+        ])
+    }
+
+    /// Acceleration, unit: g
+    ///
+    /// `range` is the accel range the packet was recorded at, 20 byte packets are always at
+    /// ±16g.
+    pub fn acceleration_g(&self, range: AccelRange) -> Option<(f32, f32, f32)> {
+        let (x, y, z) = match self.hires_accelerometer() {
+            Some(raw) => return Some(scale(raw, HIRES_ACCEL_SENSITIVITY)),
+            None => self.accelerometer()?,
+        };
+        Some(scale(
+            (x as i32, y as i32, z as i32),
+            range.sensitivity_scale_factor(),
+        ))
+    }
+
+    /// Angular velocity, unit: °/s
+    ///
+    /// `range` is the gyro range the packet was recorded at, 20 byte packets are always at
+    /// ±2000°/s.
+    pub fn angular_velocity_dps(&self, range: GyroRange) -> Option<(f32, f32, f32)> {
+        let (x, y, z) = match self.hires_gyroscope() {
+            Some(raw) => return Some(scale(raw, HIRES_GYRO_SENSITIVITY)),
+            None => self.gyroscope()?,
+        };
+        Some(scale(
+            (x as i32, y as i32, z as i32),
+            range.sensitivity_scale_factor(),
+        ))
+    }
+
+    /// Accel and gyro data of a 20-bit packet, in [`HIRES_ACCEL_SENSITIVITY`] and
+    /// [`HIRES_GYRO_SENSITIVITY`] units.
+    ///
+    /// The 20-bit format holds 18 bits of accel data and 19 bits of gyro data, the always zero
+    /// low order bits are dropped.
+    pub(crate) fn hires_data(&self) -> ((i32, i32, i32), (i32, i32, i32)) {
+        let extend = |data: i16, ext: u8, shift: u8| (((data as i32) << 4) | ext as i32) >> shift;
+        let ext = [
+            self.ext_accel_x_gyro_x,
+            self.ext_accel_y_gyro_y,
+            self.ext_accel_z_gyro_z,
+        ];
+        let accel = (
+            extend(self.accel_x, ext[0] >> 4, 2),
+            extend(self.accel_y, ext[1] >> 4, 2),
+            extend(self.accel_z, ext[2] >> 4, 2),
+        );
+        let gyro = (
+            extend(self.gyro_x, ext[0] & 0x0F, 1),
+            extend(self.gyro_y, ext[1] & 0x0F, 1),
+            extend(self.gyro_z, ext[2] & 0x0F, 1),
+        );
+        (accel, gyro)
+    }
+
+    /// Raw accel data, the 16 most significant bits in 20 byte packets, `None` for gyro only
+    /// packets and for invalid samples.
+    ///
+    /// With [`InvalidSampleMode::InvalidCode`], valid data never reaches -32768, an axis at
+    /// -32768 marks a sample the accelerometer did not produce.
+    pub fn accelerometer(&self) -> Option<(i16, i16, i16)> {
+        let data = (self.accel_x, self.accel_y, self.accel_z);
+        (self.header.has_accel() && self.is_valid(data)).then_some(data)
+    }
+
+    /// Raw gyro data, the 16 most significant bits in 20 byte packets, `None` for accel only
+    /// packets and for invalid samples, see [`Self::accelerometer`].
+    pub fn gyroscope(&self) -> Option<(i16, i16, i16)> {
+        let data = (self.gyro_x, self.gyro_y, self.gyro_z);
+        (self.header.has_gyro() && self.is_valid(data)).then_some(data)
+    }
+
+    fn is_valid(&self, (x, y, z): (i16, i16, i16)) -> bool {
+        !self.invalid_code || (x != i16::MIN && y != i16::MIN && z != i16::MIN)
+    }
+}
+
+/// Packets parsed from FIFO data, returned by [`Icm42688p::read_fifo`].
+///
+/// Bytes that do not start a valid packet are skipped, a truncated packet at the end is
+/// dropped.
+#[derive(Clone)]
+pub struct FifoPackets<'a> {
+    data: &'a [u8],
+    endian: Endian,
+    config: Option<FifoPacketConfig>,
+    invalid_samples: InvalidSampleMode,
+}
+
+impl<'a> FifoPackets<'a> {
+    /// Parses `data` read from FIFO_DATA, e.g. through [`Icm42688p::begin_dma_fifo_read`],
+    /// with the sensor data byte order `endian`.
+    ///
+    /// The size of each packet is taken from its header.
+    pub fn new(data: &'a [u8], endian: Endian) -> Self {
+        Self {
+            data,
+            endian,
+            config: None,
+            invalid_samples: InvalidSampleMode::default(),
+        }
+    }
+
+    /// Same as [`Self::new`], for a FIFO pushing `config` packets only.
+    ///
+    /// Every packet is expected to be [`FifoPacketConfig::size`] bytes long, bytes starting with
+    /// a header of another packet structure are skipped like invalid ones.
+    pub fn with_config(data: &'a [u8], endian: Endian, config: FifoPacketConfig) -> Self {
+        Self {
+            data,
+            endian,
+            config: Some(config),
+            invalid_samples: InvalidSampleMode::default(),
+        }
+    }
+
+    /// Sets how the sensor encoded invalid samples, [`InvalidSampleMode::InvalidCode`] by
+    /// default.
+    pub fn invalid_samples(self, invalid_samples: InvalidSampleMode) -> Self {
+        Self {
+            invalid_samples,
+            ..self
+        }
+    }
+}
+
+impl Iterator for FifoPackets<'_> {
+    type Item = FifoPacket;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (&header, rest) = self.data.split_first()?;
+            let size = match self.config {
+                Some(config) if config.accepts(FifoHeader(header)) => Some(config.size()),
+                Some(_) => None,
+                None => FifoPacket::size(header),
+            };
+            let Some(size) = size else {
+                self.data = rest;
+                continue;
+            };
+            if size > self.data.len() {
+                self.data = &[];
+                return None;
+            }
+            let (packet, rest) = self.data.split_at(size);
+            self.data = rest;
+            return Some(FifoPacket::parse(packet, self.endian, self.invalid_samples));
+        }
+    }
+}
+
+/// Parses FIFO_DATA bytes with the default big endian sensor data, without an [`Interface`].
+///
+/// Never panics, whatever the content of `data`: bytes that do not start a valid packet are
+/// skipped and a truncated packet at the end is dropped. Use [`FifoPackets::new`] for little
+/// endian data.
+pub fn parse_packets(data: &[u8]) -> FifoPackets<'_> {
+    FifoPackets::new(data, Endian::default())
+}
+
+fn scale((x, y, z): (i32, i32, i32), factor: f32) -> (f32, f32, f32) {
+    (x as f32 / factor, y as f32 / factor, z as f32 / factor)
+}
+
+/// Layout of a [`FifoPacket`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FifoPacketKind {
+    /// 8 bytes, accel data only (packet 1).
+    Accel,
+    /// 8 bytes, gyro data only (packet 2).
+    Gyro,
+    /// 16 bytes, accel and gyro data (packet 3).
+    Combined,
+    /// 20 bytes, 20-bit accel and gyro data (packet 4).
+    Hires,
+}
+
+/// Structure of the packets pushed to the FIFO
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FifoPacketConfig {
+    /// Packet 1: header, 16-bit accel data and 8-bit temperature, 8 bytes.
+    AccelOnly,
+    /// Packet 2: header, 16-bit gyro data and 8-bit temperature, 8 bytes.
+    GyroOnly,
+    /// Packet 3: header, 16-bit accel and gyro data, 8-bit temperature and timestamp, 16 bytes.
+    Combined,
+    /// Packet 4: header, 20-bit accel and gyro data, 16-bit temperature and timestamp, 20
+    /// bytes. The full scale ranges are forced, see [`FifoEnables::hires`].
+    Extended,
+}
+
+impl FifoPacketConfig {
+    /// Size of each packet, in bytes.
+    pub const fn size(self) -> usize {
+        self.sources().packet_size()
+    }
+
+    /// Layout of the packets parsed from the FIFO.
+    pub const fn kind(self) -> FifoPacketKind {
+        match self {
+            FifoPacketConfig::AccelOnly => FifoPacketKind::Accel,
+            FifoPacketConfig::GyroOnly => FifoPacketKind::Gyro,
+            FifoPacketConfig::Combined => FifoPacketKind::Combined,
+            FifoPacketConfig::Extended => FifoPacketKind::Hires,
+        }
+    }
+
+    /// Whether a packet starting with `header` has this structure.
+    pub fn accepts(self, header: FifoHeader) -> bool {
+        self.sources().accepts(header)
+    }
+
+    pub(crate) const fn sources(self) -> FifoSources {
+        let (accel, gyro, hires) = match self {
+            FifoPacketConfig::AccelOnly => (true, false, false),
+            FifoPacketConfig::GyroOnly => (false, true, false),
+            FifoPacketConfig::Combined => (true, true, false),
+            FifoPacketConfig::Extended => (true, true, true),
+        };
+        FifoSources { accel, gyro, hires }
+    }
+}
+
+/// Data sources enabled in the FIFO.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct FifoSources {
+    pub accel: bool,
+    pub gyro: bool,
+    pub hires: bool,
+}
+
+impl FifoSources {
+    /// Sources enabled in a FIFO_CONFIG1 value.
+    pub fn from_config1(config: u8) -> Self {
+        Self {
+            accel: FIFO_CONFIG1::FIFO_ACCEL_EN.get(config) != 0,
+            gyro: FIFO_CONFIG1::FIFO_GYRO_EN.get(config) != 0,
+            hires: FIFO_CONFIG1::FIFO_HIRES_EN.get(config) != 0,
+        }
+    }
+
+    /// Whether a packet starting with `header` can come from these sources.
+    ///
+    /// Anything else means the reads are no longer aligned on packet boundaries.
+    pub fn accepts(&self, header: FifoHeader) -> bool {
+        if header.has_hires() {
+            return self.hires;
+        }
+        !self.hires && header.has_accel() == self.accel && header.has_gyro() == self.gyro
+    }
+
+    /// Structure of the packets these sources push, `None` when the FIFO is not written.
+    pub fn packet_config(&self) -> Option<FifoPacketConfig> {
+        match (self.hires, self.accel, self.gyro) {
+            (true, _, _) => Some(FifoPacketConfig::Extended),
+            (false, true, true) => Some(FifoPacketConfig::Combined),
+            (false, true, false) => Some(FifoPacketConfig::AccelOnly),
+            (false, false, true) => Some(FifoPacketConfig::GyroOnly),
+            (false, false, false) => None,
+        }
+    }
+
+    /// Size of the packets these sources push, in bytes.
+    pub const fn packet_size(&self) -> usize {
+        if self.hires {
+            limits::FIFO_PACKET_SIZE_HIRES
+        } else if self.accel && self.gyro {
+            limits::FIFO_PACKET_SIZE_COMBINED
+        } else {
+            limits::FIFO_PACKET_SIZE_SINGLE
+        }
+    }
+}
+
+/// FIFO operating mode
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum FifoMode {
+    /// The FIFO is not filled.
+    #[default]
+    Bypass = 0b00,
+    /// New packets are pushed when the FIFO is full, the oldest ones are dropped.
+    Stream = 0b01,
+    /// New packets are dropped when the FIFO is full.
+    StopOnFull = 0b10,
+}
+
+impl From<u8> for FifoMode {
+    fn from(value: u8) -> Self {
+        match value & 0b11 {
+            0b00 => FifoMode::Bypass,
+            0b01 => FifoMode::Stream,
+            _ => FifoMode::StopOnFull,
+        }
+    }
+}
+
+/// Unit of FIFO_COUNTH/L
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum FifoCountUnit {
+    #[default]
+    Bytes = 0,
+    /// Number of packets.
+    Records = 1,
+}
+
+impl From<u8> for FifoCountUnit {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => FifoCountUnit::Bytes,
+            _ => FifoCountUnit::Records,
+        }
+    }
+}
+
+/// Content of the FIFO packets for samples the sensor did not produce, e.g. the first ones
+/// after enabling a sensor or while a sensor runs at a lower ODR than the other one
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum InvalidSampleMode {
+    /// Data set to the invalid code, -32768 for 16-bit data and -524288 for 20-bit data, valid
+    /// data is clamped to -32766.
+    #[default]
+    InvalidCode = 0,
+    /// Data set to the last valid sample, valid data spans the full range.
+    HoldLast = 1,
+}
+
+impl From<u8> for InvalidSampleMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => InvalidSampleMode::InvalidCode,
+            _ => InvalidSampleMode::HoldLast,
+        }
+    }
+}
+
+/// Data pushed to the FIFO
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct FifoEnables {
+    pub accel: bool,
+    pub gyro: bool,
+    pub temperature: bool,
+    /// FSYNC timestamps, must be enabled whenever FSYNC is used.
+    pub timestamp: bool,
+    /// 20-bit accel and gyro data and 16-bit temperature, in 20 byte packets, see
+    /// [`FifoPacket::hires_accelerometer`]. The full scale ranges of these packets are forced to
+    /// ±16g and ±2000°/s, the sensitivities being [`HIRES_ACCEL_SENSITIVITY`] and
+    /// [`HIRES_GYRO_SENSITIVITY`], while the data registers keep the configured ranges.
+    pub hires: bool,
+}
+
+/// Range [`WatermarkTuner`] keeps the watermark in, unit: packet
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WatermarkBounds {
+    pub min_packets: u16,
+    pub max_packets: u16,
+}
+
+/// Number of consecutive drains the host must keep up for before the watermark is lowered.
+const CALM_DRAINS: u8 = 16;
+
+/// Adjusts the FIFO watermark from the size of the drains run on each FIFO threshold interrupt.
+///
+/// [`Self::update`] is meant to be called with the [`DrainStats`] of each drain:
+/// - on a FIFO overflow, or a drain finding the FIFO over three quarters full, the watermark is
+///   halved so the interrupt leaves more headroom;
+/// - when the host drains more than one extra watermark of data, interrupts are served late
+///   and the watermark is raised by one packet, batching more data per interrupt;
+/// - after 16 consecutive drains finding at most one packet beyond the watermark, the
+///   watermark is lowered by one packet to reduce the latency.
+///
+/// The watermark always stays within the [`WatermarkBounds`], which are clamped to half the
+/// FIFO.
+pub struct WatermarkTuner {
+    bounds: WatermarkBounds,
+    packet_size: usize,
+    packets: u16,
+    calm_drains: u8,
+    lost_packets: u16,
+}
+
+impl WatermarkTuner {
+    /// Starts from the current watermark of `imu`, clamped to `bounds`.
+    ///
+    /// The tuner must be created again after changing the FIFO sources.
+    pub fn new<I>(imu: &mut Icm42688p<I>, bounds: WatermarkBounds) -> Result<Self, Error>
+    where
+        I: Interface,
+    {
+        let packet_size = imu.driver().fifo_packet_size()?;
+        let max_packets = (limits::FIFO_SIZE / 2 / packet_size) as u16;
+        let min_packets = bounds.min_packets.clamp(1, max_packets);
+        let bounds = WatermarkBounds {
+            min_packets,
+            max_packets: bounds.max_packets.clamp(min_packets, max_packets),
+        };
+        let current = imu.driver().fifo_watermark()? / packet_size as u16;
+        let mut tuner = Self {
+            bounds,
+            packet_size,
+            packets: 0,
+            calm_drains: 0,
+            lost_packets: 0,
+        };
+        tuner.apply(imu, current)?;
+        Ok(tuner)
+    }
+
+    /// Adapts the watermark to a drain, and returns it, unit: packet
+    pub fn update<I>(&mut self, imu: &mut Icm42688p<I>, stats: &DrainStats) -> Result<u16, Error>
+    where
+        I: Interface,
+    {
+        let overflow = stats.lost_packets != 0 && stats.lost_packets != self.lost_packets;
+        self.lost_packets = stats.lost_packets;
+        let drained = (stats.bytes / self.packet_size) as u16;
+
+        let packets = if overflow || stats.bytes > limits::FIFO_SIZE * 3 / 4 {
+            self.calm_drains = 0;
+            self.packets / 2
+        } else if drained > self.packets * 2 {
+            self.calm_drains = 0;
+            self.packets + 1
+        } else if drained <= self.packets + 1 {
+            self.calm_drains += 1;
+            if self.calm_drains < CALM_DRAINS {
+                return Ok(self.packets);
+            }
+            self.calm_drains = 0;
+            self.packets - 1
+        } else {
+            self.calm_drains = 0;
+            return Ok(self.packets);
+        };
+        self.apply(imu, packets)?;
+        Ok(self.packets)
+    }
+
+    /// Current watermark, unit: packet
+    pub fn watermark_packets(&self) -> u16 {
+        self.packets
+    }
+
+    fn apply<I>(&mut self, imu: &mut Icm42688p<I>, packets: u16) -> Result<(), Error>
+    where
+        I: Interface,
+    {
+        let packets = packets.clamp(self.bounds.min_packets, self.bounds.max_packets);
+        if packets != self.packets {
+            // In bytes, converted to the unit of FIFO_COUNT_REC by the driver.
+            imu.driver()
+                .set_fifo_watermark(packets * self.packet_size as u16)?;
+            self.packets = packets;
+        }
+        Ok(())
+    }
+}
+
+/// Settings of [`crate::Icm42688p::capture_burst`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CaptureConfig {
+    /// Time the FIFO keeps recording after the trigger.
+    pub post_trigger: MillisDurationU32,
+    /// Interval between trigger polls.
+    pub poll_interval: MillisDurationU32,
+    /// Time to wait for the trigger before giving up.
+    pub timeout: MillisDurationU32,
+}
+
+/// Cap on the work of [`crate::Icm42688p::drain_fifo_budgeted`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DrainBudget {
+    /// Maximum number of packets to decode.
+    Packets(usize),
+    /// Maximum number of bytes of the FIFO_DATA bursts, counting the register address byte sent
+    /// before each packet, only whole packets are read.
+    BusBytes(usize),
+}
+
+/// FIFO fill level and flags.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FifoStatus {
+    /// The FIFO threshold interrupt fired since the last status read.
+    pub watermark: bool,
+    /// The FIFO full interrupt fired since the last status read.
+    pub full: bool,
+    pub empty: bool,
+    /// Fill level, unit: byte
+    pub count: u16,
+    /// Number of packets the FIFO dropped because it was full, since it was last flushed.
+    pub lost_packets: u16,
+    /// Packets were dropped since the last status read.
+    pub overflow: bool,
+}
+
+/// Statistics of a FIFO drain.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct DrainStats {
+    /// Number of packets decoded.
+    pub packets: usize,
+    /// Number of bytes read from the FIFO.
+    pub bytes: usize,
+    /// Number of bytes of the FIFO_DATA bursts, [`Self::bytes`] plus the register address byte of
+    /// each burst, see [`DrainBudget::BusBytes`].
+    pub bus_bytes: usize,
+    /// Number of bytes read but not part of a decoded packet.
+    pub discarded_bytes: usize,
+    /// Number of FIFO empty markers read in place of a packet.
+    pub empty_markers: usize,
+    /// Number of packet headers not matching the enabled FIFO sources, at most 1.
+    ///
+    /// Such a header means the reads are misaligned, so the drain stops after dropping the rest
+    /// of the FIFO content, counted in [`Self::discarded_bytes`].
+    pub invalid_headers: usize,
+    /// Number of packets the FIFO dropped because it was full, since it was last flushed.
+    pub lost_packets: u16,
+    /// Number of bytes left in the FIFO because the [`DrainBudget`] was spent, unit: byte
+    pub remaining_bytes: usize,
+}
+
+impl DrainStats {
+    /// Whether the FIFO has overflowed.
+    pub fn overflow(&self) -> bool {
+        self.lost_packets != 0
+    }
+}
+
+/// Exclusive access to the bus for a FIFO read performed by the application, e.g. with DMA.
+///
+/// The guard mutably borrows the driver, so no other driver call can interleave transactions
+/// with the transfer. The register bank is left on bank 0 and must not be changed.
+pub struct FifoDmaGuard<'a, I> {
+    imu: &'a mut Icm42688p<I>,
+    count: u16,
+}
+
+impl<'a, I> FifoDmaGuard<'a, I>
+where
+    I: Interface,
+{
+    pub(crate) fn new(imu: &'a mut Icm42688p<I>, count: u16) -> Self {
+        Self { imu, count }
+    }
+
+    /// Number of bytes to read from FIFO_DATA, unit: byte
+    pub fn count(&self) -> u16 {
+        self.count
+    }
+
+    /// Address of FIFO_DATA, to be read in a single burst of [`Self::count`] bytes.
+    pub fn address(&self) -> RegAddr {
+        Bank0::FifoData.address()
+    }
+
+    /// The bus interface to run the transfer on.
+    pub fn interface(&mut self) -> &mut I {
+        &mut self.imu.interface
+    }
+}
+
+/// Size of the buffer of a [`FifoReader`], four high resolution packets, unit: byte
+const FIFO_READER_LEN: usize = 4 * limits::FIFO_PACKET_SIZE_HIRES;
+
+/// Iterator over the packets stored in the FIFO, see [`Icm42688p::fifo_packets`].
+///
+/// Packets are read into an internal buffer of a few packets with [`Icm42688p::read_fifo`],
+/// which is refilled from the sensor once consumed. The iteration ends when the FIFO is empty,
+/// or after yielding an error.
+pub struct FifoReader<'a, I> {
+    imu: &'a mut Icm42688p<I>,
+    buf: [u8; FIFO_READER_LEN],
+    /// Bytes of `buf` filled by the last refill.
+    len: usize,
+    /// Bytes of `buf` already parsed.
+    pos: usize,
+    endian: Endian,
+    config: Option<FifoPacketConfig>,
+    invalid_samples: InvalidSampleMode,
+    done: bool,
+}
+
+impl<'a, I> FifoReader<'a, I>
+where
+    I: Interface,
+{
+    pub(crate) fn new(imu: &'a mut Icm42688p<I>) -> Result<Self, Error> {
+        let mut reader = Self {
+            imu,
+            buf: [0; FIFO_READER_LEN],
+            len: 0,
+            pos: 0,
+            endian: Endian::default(),
+            config: None,
+            invalid_samples: InvalidSampleMode::default(),
+            done: false,
+        };
+        reader.refill()?;
+        Ok(reader)
+    }
+
+    fn refill(&mut self) -> Result<(), Error> {
+        let packets = self.imu.read_fifo(&mut self.buf)?;
+        self.len = packets.data.len();
+        self.pos = 0;
+        self.endian = packets.endian;
+        self.config = packets.config;
+        self.invalid_samples = packets.invalid_samples;
+        self.done = self.len == 0;
+        Ok(())
+    }
+}
+
+impl<I> Iterator for FifoReader<'_, I>
+where
+    I: Interface,
+{
+    type Item = Result<FifoPacket, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let mut packets = FifoPackets {
+                data: &self.buf[self.pos..self.len],
+                endian: self.endian,
+                config: self.config,
+                invalid_samples: self.invalid_samples,
+            };
+            if let Some(packet) = packets.next() {
+                self.pos = self.len - packets.data.len();
+                return Some(Ok(packet));
+            }
+            if let Err(error) = self.refill() {
+                self.done = true;
+                return Some(Err(error));
+            }
+        }
+        None
+    }
+}
+
+/// Number of timestamp intervals of an ODR estimation window.
+pub const ODR_WINDOW: u32 = 256;
+
+/// Estimates the output data rate from the device time of consecutive FIFO packets.
+pub(crate) struct OdrEstimator {
+    /// Device time of the first packet of the window and the number of intervals since.
+    window: Option<(u64, u32)>,
+    /// Estimate of the last complete window, unit: Hz
+    estimate: Option<f32>,
+}
+
+impl OdrEstimator {
+    pub fn new() -> Self {
+        Self {
+            window: None,
+            estimate: None,
+        }
+    }
+
+    /// Adds the device time of a timestamped packet, unit: µs
+    pub fn update(&mut self, time_us: u64) {
+        let Some((start, intervals)) = self.window else {
+            self.window = Some((time_us, 0));
+            return;
+        };
+        let intervals = intervals + 1;
+        if intervals < ODR_WINDOW {
+            self.window = Some((start, intervals));
+            return;
+        }
+        if time_us > start {
+            self.estimate = Some(intervals as f32 * 1e6 / (time_us - start) as f32);
+        }
+        self.window = Some((time_us, 0));
+    }
+
+    /// Drops the current window, e.g. after packets were lost.
+    pub fn restart(&mut self) {
+        self.window = None;
+    }
+
+    /// Estimate of the last complete window, unit: Hz
+    pub fn estimate(&self) -> Option<f32> {
+        self.estimate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes `words` after `header` in `endian`, in address order.
+    fn encode(header: u8, words: &[i16], endian: Endian, out: &mut [u8]) -> usize {
+        out[0] = header;
+        for (i, word) in words.iter().enumerate() {
+            let bytes = match endian {
+                Endian::Big => word.to_be_bytes(),
+                Endian::Little => word.to_le_bytes(),
+            };
+            out[1 + 2 * i..][..2].copy_from_slice(&bytes);
+        }
+        1 + 2 * words.len()
+    }
+
+    fn parse(buf: &[u8], endian: Endian) -> FifoPacket {
+        FifoPacket::parse(buf, endian, InvalidSampleMode::HoldLast)
+    }
+
+    #[test]
+    fn parse_packet_1_and_2() {
+        for endian in [Endian::Big, Endian::Little] {
+            let mut buf = [0u8; 8];
+            encode(0x40, &[1, -2, 0x1234], endian, &mut buf);
+            buf[7] = -3i8 as u8;
+            let packet = parse(&buf, endian);
+            assert_eq!(packet.kind(), FifoPacketKind::Accel);
+            assert_eq!(packet.accelerometer(), Some((1, -2, 0x1234)));
+            assert_eq!(packet.gyroscope(), None);
+            assert_eq!(packet.raw_temperature(), -3);
+            assert_eq!(packet.timestamp(), None);
+
+            encode(0x20, &[-1, 2, -0x1234], endian, &mut buf);
+            let packet = parse(&buf, endian);
+            assert_eq!(packet.kind(), FifoPacketKind::Gyro);
+            assert_eq!(packet.gyroscope(), Some((-1, 2, -0x1234)));
+            assert_eq!(packet.accelerometer(), None);
+        }
+    }
+
+    #[test]
+    fn parse_packet_3() {
+        for endian in [Endian::Big, Endian::Little] {
+            let mut buf = [0u8; 16];
+            // Accel, gyro, then the temperature byte and the timestamp.
+            encode(0x68, &[1, 2, 3, -4, -5, -6], endian, &mut buf);
+            buf[13] = 10;
+            let timestamp = match endian {
+                Endian::Big => 0xBEEFu16.to_be_bytes(),
+                Endian::Little => 0xBEEFu16.to_le_bytes(),
+            };
+            buf[14..].copy_from_slice(&timestamp);
+            let packet = parse(&buf, endian);
+            assert_eq!(packet.kind(), FifoPacketKind::Combined);
+            assert_eq!(packet.accelerometer(), Some((1, 2, 3)));
+            assert_eq!(packet.gyroscope(), Some((-4, -5, -6)));
+            assert_eq!(packet.raw_temperature(), 10);
+            assert_eq!(packet.odr_timestamp(), Some(0xBEEF));
+            assert_eq!(packet.fsync_time(), None);
+        }
+    }
+
+    #[test]
+    fn parse_packet_4() {
+        for endian in [Endian::Big, Endian::Little] {
+            let mut buf = [0u8; 20];
+            let len = encode(
+                0x7C,
+                &[1, 2, 3, -4, -5, -6, 1000, 0x1234u16 as i16],
+                endian,
+                &mut buf,
+            );
+            buf[len..].copy_from_slice(&[0x12, 0x34, 0x56]);
+            let packet = parse(&buf, endian);
+            assert_eq!(packet.kind(), FifoPacketKind::Hires);
+            assert_eq!(packet.accelerometer(), Some((1, 2, 3)));
+            assert_eq!(packet.gyroscope(), Some((-4, -5, -6)));
+            assert_eq!(packet.raw_temperature(), 1000);
+            assert_eq!(packet.fsync_time(), Some(0x1234));
+            assert_eq!(packet.odr_timestamp(), None);
+            assert_eq!(packet.hires_extension(), Some([0x12, 0x34, 0x56]));
+        }
+    }
 }
@@ -0,0 +1,190 @@
+//! Hardware-in-the-loop test sequence.
+//!
+//! [`run`] drives a real sensor through the register sequences of the driver and returns a
+//! [`HilReport`]. A test binary only has to create the bus of its target (e.g. with
+//! `linux-embedded-hal` or under `probe-run`) and print the report, whose [`Display`] output is
+//! one `hil <step> <result>` line per step followed by a `hil result <result>` line.
+
+use core::fmt::{self, Display, Formatter};
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{
+    AccelRange, Error, FifoEnables, FifoMode, GyroRange, Icm42688p, Interface, PowerMode,
+    SelfTestConfig,
+};
+
+/// Time the FIFO fills before [`HilStep::FifoDrain`] drains it, about 10 packets at the default
+/// 1kHz ODR, unit: ms
+const FIFO_FILL_MS: u32 = 10;
+
+/// Step of the hardware-in-the-loop sequence.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HilStep {
+    /// Runs the initialization sequence again.
+    Init,
+    /// Writes and reads back every accel range.
+    AccelRangeSweep,
+    /// Writes and reads back every gyro range.
+    GyroRangeSweep,
+    /// Reads accel, gyro and temperature data.
+    SensorData,
+    /// Streams accel, gyro and temperature data to the FIFO, then drains it.
+    FifoDrain,
+    /// Runs the abbreviated self-test, see [`Icm42688p::self_test_online`].
+    SelfTest,
+}
+
+impl HilStep {
+    /// Steps in execution order.
+    pub const ALL: [HilStep; 6] = [
+        HilStep::Init,
+        HilStep::AccelRangeSweep,
+        HilStep::GyroRangeSweep,
+        HilStep::SensorData,
+        HilStep::FifoDrain,
+        HilStep::SelfTest,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            HilStep::Init => "init",
+            HilStep::AccelRangeSweep => "accel_range_sweep",
+            HilStep::GyroRangeSweep => "gyro_range_sweep",
+            HilStep::SensorData => "sensor_data",
+            HilStep::FifoDrain => "fifo_drain",
+            HilStep::SelfTest => "self_test",
+        }
+    }
+}
+
+/// Reason of a failed step.
+#[derive(Clone, PartialEq, Debug)]
+pub enum HilFailure {
+    /// The driver returned an error.
+    Driver(Error),
+    /// The sensor did not behave as expected.
+    Mismatch,
+}
+
+impl From<Error> for HilFailure {
+    fn from(error: Error) -> Self {
+        HilFailure::Driver(error)
+    }
+}
+
+/// Results of the hardware-in-the-loop sequence, indexed like [`HilStep::ALL`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct HilReport {
+    pub results: [Result<(), HilFailure>; HilStep::ALL.len()],
+}
+
+impl HilReport {
+    /// Whether all steps passed.
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+}
+
+impl Display for HilReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (step, result) in HilStep::ALL.iter().zip(&self.results) {
+            match result {
+                Ok(()) => writeln!(f, "hil {} pass", step.name())?,
+                Err(HilFailure::Driver(error)) => {
+                    writeln!(f, "hil {} fail {:?}", step.name(), error)?
+                }
+                Err(HilFailure::Mismatch) => writeln!(f, "hil {} fail Mismatch", step.name())?,
+            }
+        }
+        let result = if self.passed() { "pass" } else { "fail" };
+        writeln!(f, "hil result {}", result)
+    }
+}
+
+/// Runs every [`HilStep`] against `imu`, the sensor is left in its default configuration.
+pub fn run<I>(imu: &mut Icm42688p<I>, delay: &mut impl DelayNs) -> HilReport
+where
+    I: Interface,
+{
+    HilReport {
+        results: HilStep::ALL.map(|step| run_step(imu, step, delay)),
+    }
+}
+
+fn run_step<I>(
+    imu: &mut Icm42688p<I>,
+    step: HilStep,
+    delay: &mut impl DelayNs,
+) -> Result<(), HilFailure>
+where
+    I: Interface,
+{
+    match step {
+        HilStep::Init => {
+            imu.driver().init()?;
+            imu.set_power_mode(PowerMode::SixAxisLowNoise, delay)?;
+        }
+        HilStep::AccelRangeSweep => {
+            use AccelRange as E;
+            for range in [E::G2, E::G4, E::G8, E::G16] {
+                imu.set_accel_range(range)?;
+                if imu.accel_range()? != range {
+                    return Err(HilFailure::Mismatch);
+                }
+            }
+        }
+        HilStep::GyroRangeSweep => {
+            use GyroRange as E;
+            for range in [
+                E::Dps15_625,
+                E::Dps31_25,
+                E::Dps62_5,
+                E::Dps125,
+                E::Dps250,
+                E::Dps500,
+                E::Dps1000,
+                E::Dps2000,
+            ] {
+                imu.set_gyro_range(range)?;
+                if imu.gyro_range()? != range {
+                    return Err(HilFailure::Mismatch);
+                }
+            }
+        }
+        HilStep::SensorData => {
+            imu.acceleration()?;
+            imu.angular_velocity()?;
+            let temperature = imu.temperature_celsius()?;
+            if !(-40.0..=85.0).contains(&temperature) {
+                return Err(HilFailure::Mismatch);
+            }
+        }
+        HilStep::FifoDrain => {
+            imu.set_fifo_enables(FifoEnables {
+                accel: true,
+                gyro: true,
+                temperature: true,
+                ..Default::default()
+            })?;
+            imu.set_fifo_mode(FifoMode::Stream)?;
+            delay.delay_ms(FIFO_FILL_MS);
+            let stats = imu.drain_fifo(|_| {});
+            imu.set_fifo_mode(FifoMode::Bypass)?;
+            imu.set_fifo_enables(FifoEnables::default())?;
+            let stats = stats?;
+            if stats.packets == 0 || stats.invalid_headers != 0 {
+                return Err(HilFailure::Mismatch);
+            }
+        }
+        HilStep::SelfTest => {
+            if !imu
+                .self_test_online(SelfTestConfig::default(), delay)?
+                .passed()
+            {
+                return Err(HilFailure::Mismatch);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use embedded_hal::{digital::OutputPin, i2c::I2c, spi::SpiBus};
+
+use crate::{
+    config::DisabledInterface,
+    error::{BusAccess, Error, PinAction, PinFault},
+    register::{Bank0, Register},
+};
+
+const SPI_WRITE_OPERATION: u8 = 0b00000000;
+const SPI_READ_OPERATION: u8 = 0b10000000;
+
+/// Register address within the selected user bank
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct RegAddr(pub u8);
+
+impl RegAddr {
+    /// Address of the register `offset` places further.
+    pub const fn offset(self, offset: u8) -> Self {
+        Self(self.0.wrapping_add(offset))
+    }
+}
+
+/// User register bank, selected through REG_BANK_SEL
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Bank(pub u8);
+
+/// Register access to the sensor over a host bus.
+///
+/// The driver logic only talks to the sensor through this trait object, so it is compiled once
+/// regardless of how many bus types a firmware instantiates the driver with.
+pub trait Interface {
+    /// Reads the register at `address` of the currently selected bank.
+    fn read_register(&mut self, address: RegAddr) -> Result<u8, Error>;
+
+    /// Reads `buf.len()` consecutive registers starting at `address` of the currently selected
+    /// bank.
+    ///
+    /// The default implementation reads the registers one by one, buses supporting burst reads
+    /// should override it so the data comes from a single transaction.
+    fn read_registers(&mut self, address: RegAddr, buf: &mut [u8]) -> Result<(), Error> {
+        for (offset, byte) in buf.iter_mut().enumerate() {
+            *byte = self.read_register(address.offset(offset as u8))?;
+        }
+        Ok(())
+    }
+
+    /// Writes `data` to the register at `address` of the currently selected bank.
+    fn write_register(&mut self, address: RegAddr, data: u8) -> Result<(), Error>;
+
+    /// Brings the serial interface to a known state before the driver identifies the sensor.
+    ///
+    /// Returns the host interface the driver should disable in INTF_CONFIG0, if any. The default
+    /// implementation does nothing.
+    fn prepare(&mut self) -> Result<Option<DisabledInterface>, Error> {
+        Ok(None)
+    }
+}
+
+/// Hook called after each register write of the driver, for bring-up tools.
+///
+/// Arguments are the bank, the register address, the value before the write (`None` for write
+/// only registers) and the value written. Bank selection writes are not reported.
+pub type RegisterWriteObserver = fn(bank: Bank, address: RegAddr, old: Option<u8>, new: u8);
+
+/// Steps run by [`SpiInterface`] before the driver identifies the sensor.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpiStartup {
+    /// Issues a WHO_AM_I read whose result is ignored, for boards where the first transaction
+    /// after power up is lost, e.g. because the chip select line was floating.
+    pub dummy_read: bool,
+    /// Disables the I2C interface, so SPI traffic to other devices is never decoded as I2C.
+    pub disable_i2c: bool,
+}
+
+impl Default for SpiStartup {
+    fn default() -> Self {
+        Self {
+            dummy_read: true,
+            disable_i2c: true,
+        }
+    }
+}
+
+/// SPI interface with a chip select pin driven by the driver.
+///
+/// SPI max freq: [`crate::limits::SPI_MAX_FREQ_HZ`].
+pub struct SpiInterface<SPI, CS> {
+    spi: SPI,
+    cs: CS,
+    startup: SpiStartup,
+}
+
+impl<SPI, CS> SpiInterface<SPI, CS>
+where
+    SPI: SpiBus<u8>,
+    CS: OutputPin,
+{
+    pub fn new(spi: SPI, cs: CS) -> Self {
+        Self::with_startup(spi, cs, SpiStartup::default())
+    }
+
+    pub fn with_startup(spi: SPI, cs: CS, startup: SpiStartup) -> Self {
+        Self { spi, cs, startup }
+    }
+
+    /// Releases the SPI bus and the chip select pin.
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+
+    /// Runs a full duplex transfer, a bus error takes precedence over a chip deselect error.
+    fn transfer(&mut self, access: BusAccess, buf: &mut [u8]) -> Result<(), Error> {
+        self.select_chip(access)?;
+        let result = self
+            .spi
+            .transfer_in_place(buf)
+            .and_then(|_| self.spi.flush())
+            .map_err(|_| Error::Spi(access));
+        let unselected = self.unselect_chip(access);
+        result.and(unselected)
+    }
+
+    fn read(&mut self, address: RegAddr, buf: &mut [u8]) -> Result<(), Error> {
+        let access = BusAccess::read(address);
+        self.select_chip(access)?;
+        let result = self
+            .spi
+            .write(&[address.0 | SPI_READ_OPERATION])
+            .and_then(|_| self.spi.read(buf))
+            .and_then(|_| self.spi.flush())
+            .map_err(|_| Error::Spi(access));
+        let unselected = self.unselect_chip(access);
+        result.and(unselected)
+    }
+
+    fn select_chip(&mut self, access: BusAccess) -> Result<(), Error> {
+        self.cs.set_low().map_err(|_| {
+            Error::Pin(PinFault {
+                action: PinAction::ChipSelect,
+                access: Some(access),
+            })
+        })
+    }
+
+    fn unselect_chip(&mut self, access: BusAccess) -> Result<(), Error> {
+        self.cs.set_high().map_err(|_| {
+            Error::Pin(PinFault {
+                action: PinAction::ChipDeselect,
+                access: Some(access),
+            })
+        })
+    }
+}
+
+impl<SPI, CS> Interface for SpiInterface<SPI, CS>
+where
+    SPI: SpiBus<u8>,
+    CS: OutputPin,
+{
+    fn read_register(&mut self, address: RegAddr) -> Result<u8, Error> {
+        let mut buf: [u8; 2] = [address.0 | SPI_READ_OPERATION, 0];
+        self.transfer(BusAccess::read(address), &mut buf)?;
+        Ok(buf[1])
+    }
+
+    fn read_registers(&mut self, address: RegAddr, buf: &mut [u8]) -> Result<(), Error> {
+        self.read(address, buf)
+    }
+
+    fn write_register(&mut self, address: RegAddr, data: u8) -> Result<(), Error> {
+        let mut buf: [u8; 2] = [address.0 | SPI_WRITE_OPERATION, data];
+        self.transfer(BusAccess::write(address), &mut buf)
+    }
+
+    fn prepare(&mut self) -> Result<Option<DisabledInterface>, Error> {
+        if self.startup.dummy_read {
+            // The bank is unknown, a wrong read is fine as long as the chip select toggled.
+            self.read_register(Bank0::WhoAmI.address())?;
+        }
+        Ok(self.startup.disable_i2c.then_some(DisabledInterface::I2c))
+    }
+}
+
+/// I2C address, selected by the AP_AD0 pin level.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum I2cAddress {
+    #[default]
+    Ad0Low = 0x68,
+    Ad0High = 0x69,
+}
+
+/// I2C interface.
+///
+/// Each register access is a single bus transaction, so the bus can be shared with other
+/// devices through a bus manager such as `embedded-hal-bus`'s `RefCellDevice` or
+/// `AtomicDevice`, which lock it for one transaction at a time.
+///
+/// I2C max freq: [`crate::limits::I2C_MAX_FREQ_HZ`].
+pub struct I2cInterface<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2cInterface<I2C>
+where
+    I2C: I2c,
+{
+    pub fn new(i2c: I2C, address: I2cAddress) -> Self {
+        Self {
+            i2c,
+            address: address as u8,
+        }
+    }
+
+    /// Releases the I2C bus.
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C> Interface for I2cInterface<I2C>
+where
+    I2C: I2c,
+{
+    fn read_register(&mut self, address: RegAddr) -> Result<u8, Error> {
+        let mut buf = [0];
+        self.read_registers(address, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_registers(&mut self, address: RegAddr, buf: &mut [u8]) -> Result<(), Error> {
+        self.i2c
+            .write_read(self.address, &[address.0], buf)
+            .map_err(|_| Error::I2c(BusAccess::read(address)))
+    }
+
+    fn write_register(&mut self, address: RegAddr, data: u8) -> Result<(), Error> {
+        self.i2c
+            .write(self.address, &[address.0, data])
+            .map_err(|_| Error::I2c(BusAccess::write(address)))
+    }
+}
@@ -0,0 +1,129 @@
+/// Estimated group delay at DC of the accel and gyro signal paths, unit: µs
+///
+/// `None` when the configuration is not covered by the datasheet tables, e.g. the low power
+/// only accel ODRs or a reserved filter setting.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GroupDelay {
+    pub accel_us: Option<u32>,
+    pub gyro_us: Option<u32>,
+}
+
+/// UI filter group delay at ODRs of 2kHz and above, which do not depend on the filter settings,
+/// for ODR codes 1 to 5, unit: 0.1ms
+const HIGH_ODR_DELAY: [u16; 5] = [1, 1, 2, 4, 8];
+
+/// UI filter group delay per filter order, ODR and UI_FILT_BW column, unit: 0.1ms
+///
+/// Rows are the 1kHz, 500Hz, 200Hz, 100Hz, 50Hz, 25Hz and 12.5Hz ODRs, columns the UI_FILT_BW
+/// values 0 to 7, 14 and 15.
+const UI_FILTER_DELAY: [[[u16; 10]; 7]; 3] = [
+    [
+        [6, 18, 20, 28, 31, 41, 47, 81, 15, 2],
+        [11, 36, 40, 55, 61, 81, 93, 162, 30, 4],
+        [27, 44, 50, 68, 76, 102, 117, 203, 38, 10],
+        [53, 44, 50, 68, 76, 102, 117, 203, 38, 19],
+        [105, 44, 50, 68, 76, 102, 117, 203, 38, 38],
+        [210, 44, 50, 68, 76, 102, 117, 203, 38, 75],
+        [210, 44, 50, 68, 76, 102, 117, 203, 38, 75],
+    ],
+    [
+        [7, 21, 24, 32, 37, 52, 61, 120, 15, 2],
+        [13, 41, 47, 64, 73, 104, 122, 240, 30, 4],
+        [33, 51, 58, 80, 91, 129, 153, 300, 38, 10],
+        [65, 51, 58, 80, 91, 129, 153, 300, 38, 19],
+        [129, 51, 58, 80, 91, 129, 153, 300, 38, 38],
+        [257, 51, 58, 80, 91, 129, 153, 300, 38, 75],
+        [257, 51, 58, 80, 91, 129, 153, 300, 38, 75],
+    ],
+    [
+        [8, 23, 27, 40, 46, 66, 82, 141, 15, 2],
+        [16, 46, 54, 79, 92, 132, 163, 281, 30, 4],
+        [40, 58, 68, 98, 114, 165, 204, 352, 38, 10],
+        [80, 58, 68, 98, 114, 165, 204, 352, 38, 19],
+        [159, 58, 68, 98, 114, 165, 204, 352, 38, 38],
+        [318, 58, 68, 98, 114, 165, 204, 352, 38, 75],
+        [318, 58, 68, 98, 114, 165, 204, 352, 38, 75],
+    ],
+];
+
+/// Anti-alias filter 3dB bandwidth for AAF_DELT codes 1 to 63, unit: Hz
+const AAF_BANDWIDTH: [u16; 63] = [
+    42, 84, 126, 170, 213, 258, 303, 348, 394, 441, 488, 536, 585, 634, 684, 734, 785, 837, 890,
+    943, 997, 1051, 1107, 1163, 1220, 1277, 1336, 1395, 1454, 1515, 1577, 1639, 1702, 1766, 1830,
+    1896, 1962, 2029, 2097, 2166, 2235, 2306, 2377, 2449, 2522, 2596, 2671, 2746, 2823, 2900, 2978,
+    3057, 3137, 3217, 3299, 3381, 3464, 3548, 3633, 3718, 3805, 3892, 3979,
+];
+
+/// Group delay of the UI filter from the datasheet tables, unit: µs
+///
+/// `odr`, `order` and `bandwidth` are the ODR, UI_FILT_ORD and UI_FILT_BW register values.
+pub(crate) fn ui_filter_delay_us(odr: u8, order: u8, bandwidth: u8) -> Option<u32> {
+    if let 1..=5 = odr {
+        return Some(HIGH_ODR_DELAY[odr as usize - 1] as u32 * 100);
+    }
+    let row = match odr {
+        6 => 0,
+        15 => 1,
+        7..=11 => odr as usize - 5,
+        _ => return None,
+    };
+    let column = match bandwidth {
+        0..=7 => bandwidth as usize,
+        14 => 8,
+        15 => 9,
+        _ => return None,
+    };
+    let delay = UI_FILTER_DELAY.get(order as usize)?[row][column];
+    Some(delay as u32 * 100)
+}
+
+/// Estimated group delay at DC of the anti-alias filter, unit: µs
+///
+/// The datasheet only gives the 3dB bandwidth of this 2nd order filter, the delay is the one of
+/// a Butterworth filter with the same bandwidth, √2 / (2π f).
+pub(crate) fn aaf_delay_us(delt: u8) -> Option<u32> {
+    let bandwidth = *AAF_BANDWIDTH.get((delt as usize).checked_sub(1)?)? as u32;
+    Some(225_079 / bandwidth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ui_filter_delay_follows_the_datasheet_tables() {
+        // 1kHz, BW 0, 1st order.
+        assert_eq!(ui_filter_delay_us(6, 0, 0), Some(600));
+        // 500Hz and 200Hz, the first rows after 1kHz.
+        assert_eq!(ui_filter_delay_us(15, 0, 0), Some(1100));
+        assert_eq!(ui_filter_delay_us(7, 0, 0), Some(2700));
+        // 12.5Hz, 2nd and 3rd order.
+        assert_eq!(ui_filter_delay_us(11, 1, 0), Some(25700));
+        assert_eq!(ui_filter_delay_us(11, 2, 7), Some(35200));
+        // BW 14 and 15.
+        assert_eq!(ui_filter_delay_us(6, 0, 14), Some(1500));
+        assert_eq!(ui_filter_delay_us(15, 2, 15), Some(400));
+        // 32kHz and 2kHz, whatever the filter settings.
+        assert_eq!(ui_filter_delay_us(1, 2, 15), Some(100));
+        assert_eq!(ui_filter_delay_us(5, 0, 0), Some(800));
+    }
+
+    #[test]
+    fn ui_filter_delay_outside_the_tables() {
+        // Reserved ODR, and the low power only 6.25Hz.
+        assert_eq!(ui_filter_delay_us(0, 0, 0), None);
+        assert_eq!(ui_filter_delay_us(12, 0, 0), None);
+        // Reserved bandwidth and filter order.
+        assert_eq!(ui_filter_delay_us(6, 0, 8), None);
+        assert_eq!(ui_filter_delay_us(6, 3, 0), None);
+    }
+
+    #[test]
+    fn aaf_delay_from_bandwidth() {
+        assert!(AAF_BANDWIDTH.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(aaf_delay_us(1), Some(225_079 / 42));
+        assert_eq!(aaf_delay_us(63), Some(225_079 / 3979));
+        assert_eq!(aaf_delay_us(0), None);
+        assert_eq!(aaf_delay_us(64), None);
+    }
+}
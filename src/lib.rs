@@ -1,231 +1,1399 @@
 #![no_std]
 #![allow(dead_code)]
 
-use core::fmt::Debug;
-use embedded_hal::{digital::OutputPin, spi};
+use embedded_hal::{delay::DelayNs, digital::OutputPin, i2c::I2c, spi::SpiBus};
 
+#[cfg(feature = "apex")]
+mod apex;
+#[cfg(feature = "async")]
+mod asynch;
+#[cfg(feature = "audit")]
+mod audit;
+#[cfg(feature = "benchmark")]
+pub mod benchmark;
+#[cfg(feature = "boards")]
+mod board;
 mod config;
+#[cfg(feature = "drift")]
+mod drift;
+mod driver;
 mod error;
-mod fifo;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fifo")]
+pub mod fifo;
+#[cfg(feature = "hil-tests")]
+pub mod hil;
+mod interface;
+#[cfg(feature = "latency")]
+mod latency;
+pub mod limits;
+#[cfg(feature = "math")]
+mod math;
+#[cfg(feature = "motion")]
+mod motion;
+#[cfg(feature = "apex")]
+mod power;
+pub mod prelude;
+mod reader;
+#[cfg(feature = "redundant")]
+mod redundant;
 mod register;
+#[cfg(feature = "replay")]
+mod replay;
+#[cfg(feature = "scheduler")]
+mod scheduler;
+#[cfg(feature = "selftest")]
+mod selftest;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+pub mod time;
+mod vector;
 
+#[cfg(feature = "apex")]
+pub use apex::*;
+#[cfg(feature = "async")]
+pub use asynch::*;
+#[cfg(feature = "audit")]
+pub use audit::*;
+#[cfg(feature = "boards")]
+pub use board::*;
 pub use config::*;
-use error::*;
-use register::*;
+#[cfg(feature = "drift")]
+pub use drift::*;
+use driver::*;
+pub use error::*;
+#[cfg(feature = "fifo")]
+pub use fifo::*;
+pub use interface::*;
+#[cfg(feature = "latency")]
+pub use latency::*;
+#[cfg(feature = "math")]
+pub use math::*;
+#[cfg(feature = "motion")]
+pub use motion::*;
+#[cfg(feature = "apex")]
+pub use power::*;
+pub use reader::*;
+#[cfg(feature = "redundant")]
+pub use redundant::*;
+#[cfg(feature = "replay")]
+pub use replay::*;
+#[cfg(feature = "scheduler")]
+pub use scheduler::*;
+#[cfg(feature = "selftest")]
+pub use selftest::*;
+#[cfg(feature = "snapshot")]
+pub use snapshot::*;
+pub use time::*;
+pub use vector::*;
 
-const ICM42688P_DEVICE_ID: u8 = 0x47;
-
-const SPI_WRITE_OPERATION: u8 = 0b00000000;
-const SPI_READ_OPERATION: u8 = 0b10000000;
+#[cfg(feature = "fixed")]
+pub use fixed;
+#[cfg(feature = "fixed")]
+use fixed::types::I16F16;
+#[cfg(feature = "micromath")]
+pub use micromath;
+#[cfg(feature = "nalgebra")]
+pub use nalgebra;
+#[cfg(feature = "nb")]
+pub use nb;
 
 /// ICM-42688-P driver.
 ///
-/// SPI max freq: 24Mhz.
-///
 /// Orientation of axes:
 /// +Z
 /// ^   +Y head
 /// | 7
 /// |/
 /// +-------> +X
-pub struct Icm42688p<SPI, CS> {
-    spi: SPI,
-    cs: CS,
+pub struct Icm42688p<I> {
+    interface: I,
+    state: State,
 }
 
-impl<EA, EB, SPI, CS> Icm42688p<SPI, CS>
+impl<SPI, CS> Icm42688p<SpiInterface<SPI, CS>>
 where
-    EA: Debug,
-    EB: Debug,
-    SPI: spi::SpiBus<u8, Error = EA>,
-    CS: OutputPin<Error = EB>,
+    SPI: SpiBus<u8>,
+    CS: OutputPin,
 {
-    pub fn new(spi: SPI, cs: CS) -> Result<Self, Error> {
-        let mut instance = Self { spi, cs };
-        if instance.device_id()? != ICM42688P_DEVICE_ID {
-            return Err(Error::BadDeviceId);
-        }
+    pub fn new(spi: SPI, cs: CS, delay: &mut impl DelayNs) -> Result<Self, Error> {
+        Self::new_with_units(spi, cs, Units::default(), delay)
+    }
 
-        instance.set_accel_range(AccelRange::default())?;
-        instance.set_gyro_range(GyroRange::default())?;
-        instance.set_power_mode(PowerMode::SixAxisLowNoise)?;
+    /// Creates a driver whose scaled outputs are reported in `units`.
+    pub fn new_with_units(
+        spi: SPI,
+        cs: CS,
+        units: Units,
+        delay: &mut impl DelayNs,
+    ) -> Result<Self, Error> {
+        Self::with_interface(SpiInterface::new(spi, cs), units, delay)
+    }
+}
 
-        debug_assert!(instance.power_mode()? != PowerMode::SixAxisLowNoise);
+impl<I2C> Icm42688p<I2cInterface<I2C>>
+where
+    I2C: I2c,
+{
+    /// Creates a driver on an I2C bus, which may be shared with other devices.
+    ///
+    /// `i2c` can be a bus manager device like `embedded-hal-bus`'s `RefCellDevice`.
+    pub fn new_i2c(
+        i2c: I2C,
+        address: I2cAddress,
+        units: Units,
+        delay: &mut impl DelayNs,
+    ) -> Result<Self, Error> {
+        Self::with_interface(I2cInterface::new(i2c, address), units, delay)
+    }
 
+    /// Creates a driver for a breakout `board`, with the default units.
+    ///
+    /// The I2C bus must be configured at most at [`Board::max_i2c_freq_hz`].
+    #[cfg(feature = "boards")]
+    pub fn new_board(i2c: I2C, board: Board, delay: &mut impl DelayNs) -> Result<Self, Error> {
+        Self::new_i2c(i2c, board.i2c_address(), Units::default(), delay)
+    }
+}
+
+impl<I> Icm42688p<I>
+where
+    I: Interface,
+{
+    /// Creates a driver on top of an arbitrary [`Interface`].
+    ///
+    /// Both sensors are turned on in low noise mode.
+    pub fn with_interface(
+        interface: I,
+        units: Units,
+        delay: &mut impl DelayNs,
+    ) -> Result<Self, Error> {
+        let mut instance = Self {
+            interface,
+            state: State::new(units),
+        };
+        instance.driver().init()?;
+        instance.set_power_mode(PowerMode::SixAxisLowNoise, delay)?;
         Ok(instance)
     }
 
+    /// Releases the underlying interface.
+    pub fn release(self) -> I {
+        self.interface
+    }
+
+    /// Output units of the scaled readings.
+    pub fn units(&self) -> Units {
+        self.state.units
+    }
+
+    /// Sets how the register bank is recovered after a failed bank switch.
+    pub fn set_bank_recovery(&mut self, recovery: BankRecovery) {
+        self.state.bank_recovery = recovery;
+    }
+
+    /// Installs a hook called after each register write, `None` removes it.
+    ///
+    /// While installed, each write to a readable register is preceded by a read of its previous
+    /// value.
+    pub fn set_register_write_observer(&mut self, observer: Option<RegisterWriteObserver>) {
+        self.state.write_observer = observer;
+    }
+
+    /// Maintenance operation left unfinished by an error, e.g. a self-test whose settings could
+    /// not be restored.
+    ///
+    /// While set, the data reads (registers, [`Self::read_sample`] and the FIFO reads) fail with
+    /// [`Error::Busy`] so samples taken with a test or intermediate configuration cannot reach a
+    /// control loop. Rerunning the operation successfully clears it, or
+    /// [`Self::clear_maintenance`] once the configuration was restored by other means.
+    pub fn maintenance(&self) -> Option<Maintenance> {
+        self.state.maintenance
+    }
+
+    pub fn clear_maintenance(&mut self) {
+        self.state.maintenance = None;
+    }
+
+    /// Last register accesses, oldest first, to diagnose a failure after the fact.
+    ///
+    /// Covers the accesses of the driver core, including REG_BANK_SEL writes, up to
+    /// [`AUDIT_LOG_LEN`] entries.
+    #[cfg(feature = "audit")]
+    pub fn audit_log(&self) -> impl Iterator<Item = RegisterAccess> + '_ {
+        self.state.audit.iter()
+    }
+
+    #[cfg(feature = "audit")]
+    pub fn clear_audit_log(&mut self) {
+        self.state.audit.clear();
+    }
+
+    /// Sets the orientation of the sensor in the body frame.
+    ///
+    /// Applies to all accel and gyro outputs, raw or scaled, including [`Self::read_sample`] from
+    /// the FIFO. Raw FIFO packets from [`Self::drain_fifo`], the self-test, and the axes of the
+    /// tap and wake on motion detectors stay in the sensor frame.
+    pub fn set_mounting(&mut self, mounting: MountingMatrix) {
+        self.state.mounting = mounting;
+    }
+
+    /// Reports the outputs in `frame`, for a sensor aligned with the body.
+    ///
+    /// A rotated sensor combines both with `set_mounting(mounting.then(frame.into()))`.
+    pub fn set_frame(&mut self, frame: Frame) {
+        self.set_mounting(frame.into());
+    }
+
+    /// Sets where [`Self::read_sample`] reads the samples from.
+    ///
+    /// The FIFO itself is not configured, it must be set up to store accel and gyro data.
+    pub fn set_data_source(&mut self, source: DataSource) {
+        self.state.data_source = source;
+    }
+
+    pub fn data_source(&self) -> DataSource {
+        self.state.data_source
+    }
+
+    /// Reads a sample from the configured [`DataSource`].
+    ///
+    /// Returns `None` if the FIFO holds no complete packet with both accel and gyro data, packets
+    /// with only one of them are dropped, and for the samples discarded after a range change, see
+    /// [`Self::set_range_change_discard`]. Otherwise always returns a sample when reading from
+    /// registers.
+    pub fn read_sample(&mut self) -> Result<Option<Sample>, Error> {
+        self.driver().read_sample()
+    }
+
+    /// Reads an accel sample and classifies the motion over the last [`MOTION_WINDOW`] samples.
+    ///
+    /// Meant to be called at a steady rate, e.g. once per accel ODR period. The sensor is
+    /// reported as moving until the window is filled.
+    #[cfg(feature = "motion")]
+    pub fn motion_state(&mut self) -> Result<MotionState, Error> {
+        self.driver().motion_state()
+    }
+
+    /// Sets the thresholds of [`Self::motion_state`].
+    #[cfg(feature = "motion")]
+    pub fn set_motion_config(&mut self, config: MotionConfig) {
+        self.state.motion.config = config;
+    }
+
+    /// Enables the gyro bias tracking of [`Self::angular_velocity`].
+    ///
+    /// While [`Self::motion_state`] reports the sensor as stationary, each angular velocity
+    /// reading refines the bias estimate, which is subtracted from all readings. While enabled,
+    /// angular velocity readings read the accel data in the same burst and feed it to the motion
+    /// detector, so [`Self::motion_state`] does not have to be polled alongside. This covers the
+    /// samples of [`Self::read_sample`] from either data source, [`Self::sample`] and
+    /// [`Self::read_sample_with_temp`]; the fixed point readings and the FIFO packets are left
+    /// untouched.
+    #[cfg(feature = "motion")]
+    pub fn set_gyro_bias_tracking(&mut self, enabled: bool) {
+        self.state.gyro_bias.enabled = enabled;
+    }
+
+    /// Current gyro bias estimate, in the configured gyro unit.
+    ///
+    /// `None` until the sensor has been stationary with tracking enabled.
+    #[cfg(feature = "motion")]
+    pub fn gyro_bias(&self) -> Option<(f32, f32, f32)> {
+        let unit = self.state.units.gyro;
+        let (x, y, z) = self.state.gyro_bias.bias()?;
+        Some((unit.from_dps(x), unit.from_dps(y), unit.from_dps(z)))
+    }
+
+    /// Number of angular velocity readings since the gyro bias estimate was last updated.
+    #[cfg(feature = "motion")]
+    pub fn gyro_bias_age(&self) -> Option<u32> {
+        self.state.gyro_bias.age()
+    }
+
+    /// Reads the configuration registers of all banks, e.g. to compare two units or firmware
+    /// versions with [`ConfigSnapshot::diff`].
+    #[cfg(feature = "snapshot")]
+    pub fn config_snapshot(&mut self) -> Result<ConfigSnapshot, Error> {
+        self.driver().config_snapshot()
+    }
+
+    /// Whether a new sample is available in the data registers since the last call.
+    ///
+    /// INT_STATUS clears on read, the FIFO flags read along are kept for the next FIFO status
+    /// query so no event is lost.
+    pub fn data_ready(&mut self) -> Result<bool, Error> {
+        self.driver().data_ready()
+    }
+
+    /// Polls [`Self::data_ready`] every 100µs until it is set, or returns [`Error::Timeout`]
+    /// after `timeout`.
+    pub fn wait_for_data_ready(
+        &mut self,
+        delay: &mut impl DelayNs,
+        timeout: MicrosDurationU32,
+    ) -> Result<(), Error> {
+        const POLL_INTERVAL_US: u32 = 100;
+        let timeout_us = timeout.to_micros();
+        let mut waited_us = 0;
+        while !self.data_ready()? {
+            if waited_us >= timeout_us {
+                return Err(Error::Timeout);
+            }
+            let step = POLL_INTERVAL_US.min(timeout_us - waited_us);
+            delay.delay_us(step);
+            waited_us += step;
+        }
+        Ok(())
+    }
+
+    /// Reads a new sample from the data registers, or returns `nb::Error::WouldBlock` if the
+    /// data ready flag is not set, for polling from a superloop.
+    ///
+    /// Each sample is returned once, see [`Self::data_ready`].
+    #[cfg(feature = "nb")]
+    pub fn try_sample(&mut self) -> nb::Result<Sample, Error> {
+        if !self.driver().data_ready()? {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(self.driver().read_sample_with_temp()?.sample)
+    }
+
+    /// Reads a sample and the temperature from the data registers in one transaction.
+    ///
+    /// Unlike separate calls, the temperature is guaranteed to belong to the same sampling
+    /// instant as the accel and gyro data. Always reads the registers, regardless of the
+    /// [`DataSource`].
+    pub fn read_sample_with_temp(&mut self) -> Result<SampleWithTemp, Error> {
+        self.driver().read_sample_with_temp()
+    }
+
+    /// Reads accel, gyro and temperature in one bus transaction, then the device time.
+    ///
+    /// The data come from the same sampling instant, the timestamp is latched right after they
+    /// were read. Always reads the registers, regardless of the [`DataSource`].
+    pub fn sample(&mut self) -> Result<SensorData, Error> {
+        self.driver().sample()
+    }
+
+    /// Fills `out` with consecutive new samples, see [`Self::sample`], and returns how many were
+    /// captured.
+    ///
+    /// Each sample is read once [`Self::data_ready`] is set. The capture stops early if no new
+    /// sample arrives within two periods of the slower ODR. The ranges are read once, they must
+    /// not change during the capture.
+    pub fn sample_into(
+        &mut self,
+        out: &mut [SensorData],
+        delay: &mut impl DelayNs,
+    ) -> Result<usize, Error> {
+        let timeout = self.data_ready_timeout()?;
+        let accel_factor = self.driver().accel_sensitivity()?;
+        let gyro_factor = self.driver().gyro_sensitivity()?;
+        for (count, slot) in out.iter_mut().enumerate() {
+            match self.wait_for_data_ready(delay, timeout) {
+                Ok(()) => {}
+                Err(Error::Timeout) => return Ok(count),
+                Err(error) => return Err(error),
+            }
+            *slot = self.driver().sample_scaled(accel_factor, gyro_factor)?;
+        }
+        Ok(out.len())
+    }
+
+    /// Iterator over new samples, see [`Self::sample`], each read once [`Self::data_ready`] is
+    /// set.
+    ///
+    /// The ranges are read once, they must not change while iterating.
+    ///
+    /// ```rust,ignore
+    /// for sample in imu.samples(&mut delay)?.take(100) {
+    ///     let sample = sample?;
+    ///     // ...
+    /// }
+    /// ```
+    pub fn samples<'a, D: DelayNs>(
+        &'a mut self,
+        delay: &'a mut D,
+    ) -> Result<Samples<'a, I, D>, Error> {
+        Samples::new(self, delay)
+    }
+
+    /// Two periods of the slower ODR.
+    fn data_ready_timeout(&mut self) -> Result<MicrosDurationU32, Error> {
+        let odr_hz = self.accel_odr()?.hz().min(self.gyro_odr()?.hz());
+        Ok(MicrosDurationU32::micros((2_000_000.0 / odr_hz) as u32))
+    }
+
+    /// Reads the raw temperature, accel and gyro data registers in one bus transaction.
+    ///
+    /// Always reads the registers, regardless of the [`DataSource`].
+    pub fn read_sensor_data(&mut self) -> Result<RawSensorData, Error> {
+        self.driver().read_sensor_data()
+    }
+
+    /// Acceleration, in the configured accel unit.
     pub fn acceleration(&mut self) -> Result<(f32, f32, f32), Error> {
-        let (x, y, z) = self.raw_acceleration()?;
-        let factor = self.accel_range()?.sensitivity_scale_factor();
-        let x = x as f32 / factor;
-        let y = y as f32 / factor;
-        let z = z as f32 / factor;
-        Ok((x, y, z))
+        self.driver().acceleration()
+    }
+
+    /// Same as [`Self::acceleration`], as any vector type built from an array, e.g. nalgebra's
+    /// `Vector3<f32>`, glam's `Vec3` or mint's `Vector3<f32>`, or as `micromath::vector::F32x3`
+    /// with the `micromath` feature.
+    pub fn acceleration_vector<V: FromAxes<M>, M>(&mut self) -> Result<V, Error> {
+        let (x, y, z) = self.acceleration()?;
+        Ok(V::from_axes(x, y, z))
+    }
+
+    /// Same as [`Self::acceleration`], as a `nalgebra` vector.
+    #[cfg(feature = "nalgebra")]
+    pub fn acceleration_vector3(&mut self) -> Result<nalgebra::Vector3<f32>, Error> {
+        self.acceleration_vector()
+    }
+
+    /// Acceleration in Q16.16 fixed point, unit: g
+    ///
+    /// Computed with integer arithmetic only from the sensitivity ratios of the ranges, see
+    /// [`AccelRange::sensitivity_ratio`], for targets without an FPU.
+    #[cfg(feature = "fixed")]
+    pub fn acceleration_fixed(&mut self) -> Result<(I16F16, I16F16, I16F16), Error> {
+        self.driver().acceleration_fixed()
+    }
+
+    /// Acceleration, unit: g
+    pub fn acceleration_g(&mut self) -> Result<(f32, f32, f32), Error> {
+        self.driver().acceleration_g()
+    }
+
+    /// Acceleration, unit: m/s²
+    pub fn acceleration_ms2(&mut self) -> Result<(f32, f32, f32), Error> {
+        let unit = AccelUnit::MetersPerSecondSquared;
+        let (x, y, z) = self.acceleration_g()?;
+        Ok((unit.from_g(x), unit.from_g(y), unit.from_g(z)))
     }
 
+    /// Angular velocity, in the configured gyro unit.
     pub fn angular_velocity(&mut self) -> Result<(f32, f32, f32), Error> {
-        let (x, y, z) = self.raw_angular_velocity()?;
-        // let factor =
-        //     (core::f32::consts::PI / 180.0) / self.gyro_range()?.sensitivity_scale_factor();
-        let factor = self.gyro_range()?.sensitivity_scale_factor();
-        let x = x as f32 / factor;
-        let y = y as f32 / factor;
-        let z = z as f32 / factor;
-        Ok((x, y, z))
+        self.driver().angular_velocity()
+    }
+
+    /// Same as [`Self::angular_velocity`], as any vector type built from an array, see
+    /// [`Self::acceleration_vector`].
+    pub fn angular_velocity_vector<V: FromAxes<M>, M>(&mut self) -> Result<V, Error> {
+        let (x, y, z) = self.angular_velocity()?;
+        Ok(V::from_axes(x, y, z))
+    }
+
+    /// Same as [`Self::angular_velocity`], as a `nalgebra` vector.
+    #[cfg(feature = "nalgebra")]
+    pub fn angular_velocity_vector3(&mut self) -> Result<nalgebra::Vector3<f32>, Error> {
+        self.angular_velocity_vector()
+    }
+
+    /// Angular velocity in Q16.16 fixed point, unit: °/s
+    ///
+    /// See [`Self::acceleration_fixed`]. The tracked gyro bias is not removed.
+    #[cfg(feature = "fixed")]
+    pub fn angular_velocity_fixed(&mut self) -> Result<(I16F16, I16F16, I16F16), Error> {
+        self.driver().angular_velocity_fixed()
+    }
+
+    /// Angular velocity, unit: °/s
+    pub fn angular_velocity_dps(&mut self) -> Result<(f32, f32, f32), Error> {
+        self.driver().angular_velocity_dps()
+    }
+
+    /// Angular velocity, unit: rad/s
+    pub fn angular_velocity_rad(&mut self) -> Result<(f32, f32, f32), Error> {
+        let unit = GyroUnit::RadiansPerSecond;
+        let (x, y, z) = self.angular_velocity_dps()?;
+        Ok((unit.from_dps(x), unit.from_dps(y), unit.from_dps(z)))
+    }
+
+    /// Temperature, in the configured temperature unit.
+    pub fn temperature(&mut self) -> Result<f32, Error> {
+        self.driver().temperature()
+    }
+
+    /// Reads the temperature data registers, see [`RawTemperature::celsius`].
+    pub fn raw_temperature(&mut self) -> Result<RawTemperature, Error> {
+        self.driver().raw_temperature()
     }
 
     pub fn temperature_celsius(&mut self) -> Result<f32, Error> {
-        let temp = self.raw_temperature()? as f32;
-        Ok((temp / 132.48) + 25.0)
+        self.driver().temperature_celsius()
     }
 
     pub fn temperature_fahrenheit(&mut self) -> Result<f32, Error> {
-        Ok(self.temperature_celsius()? * 1.8 + 32.0)
+        Ok(TempUnit::Fahrenheit.from_celsius(self.temperature_celsius()?))
+    }
+
+    /// Sets the number of samples [`Self::read_sample`] discards after a full-scale range change.
+    ///
+    /// The FIFO is always flushed on a range change, so a batch never mixes scales.
+    pub fn set_range_change_discard(&mut self, samples: u8) {
+        self.state.range_change_discard = samples;
     }
 
     pub fn set_accel_range(&mut self, range: AccelRange) -> Result<(), Error> {
-        let fs_sel = range as u8;
-        self.register_set_bits(&Bank0::AccelConfig0, &ACCEL_CONFIG0::FS_SEL, fs_sel)
+        self.driver().set_accel_range(range)
     }
 
     pub fn accel_range(&mut self) -> Result<AccelRange, Error> {
-        let range = self.register_read(&Bank0::AccelConfig0)? >> 5;
-        range.try_into()
+        self.driver().accel_range()
+    }
+
+    /// Sets the accel user offset the sensor adds to the accel data, unit: g
+    ///
+    /// Each axis is clamped to ±[`limits::ACCEL_OFFSET_MAX_G`] and rounded to
+    /// [`limits::ACCEL_OFFSET_RESOLUTION_G`]. The offset is kept across accel range changes,
+    /// see [`Self::set_accel_offset_on_range_change`].
+    pub fn set_accel_offset_g(&mut self, offset: (f32, f32, f32)) -> Result<(), Error> {
+        self.driver().set_accel_offset_g(offset)
+    }
+
+    pub fn accel_offset_g(&mut self) -> Result<(f32, f32, f32), Error> {
+        self.driver().accel_offset_g()
+    }
+
+    /// Effect of the accel user offset on the raw accel data at the current range, unit: LSB
+    pub fn accel_offset_lsb(&mut self) -> Result<(f32, f32, f32), Error> {
+        self.driver().accel_offset_lsb()
+    }
+
+    /// Selects what [`Self::set_accel_range`] and [`Self::apply_config`] do with the accel user
+    /// offset.
+    pub fn set_accel_offset_on_range_change(&mut self, handling: AccelOffsetOnRangeChange) {
+        self.driver().set_accel_offset_on_range_change(handling)
     }
 
     pub fn accel_odr(&mut self) -> Result<AccelODR, Error> {
-        let odr = self.register_read(&Bank0::AccelConfig0)? & 0x0F;
-        odr.try_into()
+        self.driver().accel_odr()
+    }
+
+    /// Sets the accelerometer ODR.
+    ///
+    /// Returns [`Error::InvalidForPowerMode`] if the current power mode does not support `odr`,
+    /// see [`AccelODR::supported_in`].
+    pub fn set_accel_odr(&mut self, odr: AccelODR) -> Result<(), Error> {
+        self.driver().set_accel_odr(odr)
     }
 
     pub fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), Error> {
-        let fs_sel = range as u8;
-        self.register_set_bits(&Bank0::GyroConfig0, &GYRO_CONFIG0::FS_SEL, fs_sel)
+        self.driver().set_gyro_range(range)
     }
 
     pub fn gyro_range(&mut self) -> Result<GyroRange, Error> {
-        let range = self.register_read(&Bank0::GyroConfig0)? >> 5;
-        range.try_into()
+        self.driver().gyro_range()
     }
 
     pub fn gyro_odr(&mut self) -> Result<GyroODR, Error> {
-        let odr = self.register_read(&Bank0::AccelConfig0)? & 0x0F;
-        odr.try_into()
+        self.driver().gyro_odr()
     }
 
-    pub fn set_power_mode(&mut self, mode: PowerMode) -> Result<(), Error> {
-        let mut accel_gyro_mode = self.register_read(&Bank0::PwrMgmt0)? & 0xF0;
-        accel_gyro_mode |= mode as u8;
-        self.register_write(&Bank0::PwrMgmt0, accel_gyro_mode)
+    pub fn set_gyro_odr(&mut self, odr: GyroODR) -> Result<(), Error> {
+        self.driver().set_gyro_odr(odr)
     }
 
-    pub fn power_mode(&mut self) -> Result<PowerMode, Error> {
-        let mode = self.register_read(&Bank0::PwrMgmt0)? & 0x0F;
-        mode.try_into()
-    }
-
-    fn raw_acceleration(&mut self) -> Result<(u16, u16, u16), Error> {
-        let x = u16::from_be_bytes([
-            self.register_read(&Bank0::AccelDataX0)?,
-            self.register_read(&Bank0::AccelDataX1)?,
-        ]);
-        let y = u16::from_be_bytes([
-            self.register_read(&Bank0::AccelDataY0)?,
-            self.register_read(&Bank0::AccelDataY1)?,
-        ]);
-        let z = u16::from_be_bytes([
-            self.register_read(&Bank0::AccelDataZ0)?,
-            self.register_read(&Bank0::AccelDataZ1)?,
-        ]);
-        Ok((x, y, z))
-    }
-
-    fn raw_angular_velocity(&mut self) -> Result<(u16, u16, u16), Error> {
-        let x = u16::from_be_bytes([
-            self.register_read(&Bank0::GyroDataX0)?,
-            self.register_read(&Bank0::GyroDataX1)?,
-        ]);
-        let y = u16::from_be_bytes([
-            self.register_read(&Bank0::GyroDataY0)?,
-            self.register_read(&Bank0::GyroDataY1)?,
-        ]);
-        let z = u16::from_be_bytes([
-            self.register_read(&Bank0::GyroDataZ0)?,
-            self.register_read(&Bank0::GyroDataZ1)?,
-        ]);
-        Ok((x, y, z))
-    }
-
-    fn raw_temperature(&mut self) -> Result<u16, Error> {
-        Ok(u16::from_be_bytes([
-            self.register_read(&Bank0::TempData0)?,
-            self.register_read(&Bank0::TempData1)?,
-        ]))
-    }
-
-    fn fifo_count(&mut self) -> Result<u16, Error> {
-        Ok(u16::from_be_bytes([
-            self.register_read(&Bank0::FifoCountL)?,
-            self.register_read(&Bank0::FifoCountH)?,
-        ]))
-    }
-
-    fn reset_fifo(&mut self) {
-        self.register_set_bits(
-            &Bank0::SignalPathReset,
-            &BitRange {
-                offset: SIGNAL_PATH_RESET::FIFO_FLUSH,
-                length: 1,
-            },
-            1,
-        )
-        .unwrap();
-    }
-
-    fn register_set_bits(
-        &mut self,
-        reg: &dyn Register,
-        range: &BitRange,
-        data: u8,
+    /// Sets the power mode.
+    ///
+    /// Returns [`Error::InvalidForPowerMode`] if `mode` does not support the current accelerometer
+    /// ODR, see [`AccelODR::supported_in`]. When a sensor is turned on from off, waits
+    /// [`limits::PWR_MGMT_WRITE_DELAY_US`] before returning as the sensor must not receive
+    /// register writes in the meantime.
+    pub fn set_power_mode(
+        &mut self,
+        mode: PowerMode,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error> {
+        if self.driver().set_power_mode(mode)? {
+            delay.delay_us(limits::PWR_MGMT_WRITE_DELAY_US);
+        }
+        Ok(())
+    }
+
+    /// Degrades to the sensors allowed by `capabilities`, e.g. accel only after a gyro failure.
+    ///
+    /// The disabled sensors are turned off, in low noise mode for the remaining one. Afterwards
+    /// power modes turning a disabled sensor on and its readings return
+    /// [`Error::SensorDisabled`]. See [`SelfTestReport::capabilities`].
+    pub fn restrict_capabilities(
+        &mut self,
+        capabilities: Capabilities,
+        delay: &mut impl DelayNs,
     ) -> Result<(), Error> {
-        let mut buf = self.register_read(reg)?;
-        buf &= !range.mask();
-        buf |= data << range.offset;
-        self.register_write(reg, buf)
-    }
-
-    fn register_read(&mut self, reg: &dyn Register) -> Result<u8, Error> {
-        debug_assert!(reg.readable());
-        let mut buf: [u8; 2] = [reg.address() | SPI_READ_OPERATION, 0];
-        self.spi
-            .transfer_in_place(&mut buf)
-            .map_err(|_| Error::Spi)?;
-        Ok(buf[1])
-    }
-
-    fn register_write(&mut self, reg: &dyn Register, data: u8) -> Result<(), Error> {
-        debug_assert!(reg.writable());
-        let mut buf: [u8; 2] = [reg.address() | SPI_WRITE_OPERATION, data];
-        self.spi
-            .transfer_in_place(&mut buf)
-            .map_err(|_| Error::Spi)?;
+        if self.driver().restrict_capabilities(capabilities)? {
+            delay.delay_us(limits::PWR_MGMT_WRITE_DELAY_US);
+        }
         Ok(())
     }
 
-    fn select_user_bank(&mut self, _reg: &dyn Register, bank: BankSelection) -> Result<(), Error> {
-        self.register_write(&Bank0::RegBankSel, bank as u8)
+    /// Sensors the driver is allowed to use, see [`Self::restrict_capabilities`].
+    pub fn capabilities(&self) -> Capabilities {
+        self.state.capabilities
+    }
+
+    pub fn power_mode(&mut self) -> Result<PowerMode, Error> {
+        self.driver().power_mode()
+    }
+
+    /// Applies all settings of `config`, and reports the ones changed to fit the sensor.
+    ///
+    /// The sensors are briefly turned off while the configuration registers are written, with
+    /// the IDLE bit set so the RC oscillator keeps running across the off/on transition. An
+    /// accel ODR not supported in the power mode is replaced by the closest supported one, and
+    /// sensors disabled by [`Self::restrict_capabilities`] are left off, see
+    /// [`ConfigAdjustments`].
+    pub fn apply_config(
+        &mut self,
+        config: &Config,
+        delay: &mut impl DelayNs,
+    ) -> Result<ConfigReport, Error> {
+        let report = config.adjusted(self.state.capabilities);
+        self.state.maintenance = Some(Maintenance::Reconfiguration);
+        if self.driver().begin_config(&report.applied)? {
+            delay.delay_us(limits::PWR_MGMT_WRITE_DELAY_US);
+        }
+        self.driver().end_config()?;
+        self.state.maintenance = None;
+        Ok(report)
+    }
+
+    /// Takes a single accelerometer measurement in low power mode.
+    ///
+    /// Meant for periodic checks with the sensors otherwise asleep: the accelerometer is turned
+    /// on in low power mode at the configured ODR, sampled once it delivers valid data and the
+    /// previous power mode is restored. The gyroscope is off during the measurement. Returns
+    /// [`Error::InvalidForPowerMode`] if the accel ODR is not supported in low power mode.
+    pub fn trigger_one_shot_accel(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<(f32, f32, f32), Error> {
+        let previous = self.power_mode()?;
+        let odr = self.accel_odr()?;
+        self.set_power_mode(PowerMode::AccelLowPower, delay)?;
+        delay.delay_ms(limits::ACCEL_STARTUP_TIME_MS);
+        delay.delay_us((1_000_000.0 / odr.hz()) as u32);
+        let acceleration = self.acceleration();
+        self.set_power_mode(previous, delay)?;
+        acceleration
+    }
+
+    /// Reads the accel data registers, in LSB of the current full scale range, in the body frame.
+    pub fn raw_acceleration(&mut self) -> Result<(i16, i16, i16), Error> {
+        self.driver().raw_acceleration()
+    }
+
+    /// Reads the gyro data registers, in LSB of the current full scale range, in the body frame.
+    pub fn raw_angular_velocity(&mut self) -> Result<(i16, i16, i16), Error> {
+        self.driver().raw_angular_velocity()
+    }
+
+    /// Reads the accel data registers of a single axis in one transaction, e.g. for a tilt
+    /// switch only needing Z, in LSB of the current full scale range, in the body frame.
+    pub fn raw_acceleration_axis(&mut self, axis: Axis) -> Result<i16, Error> {
+        self.driver().raw_acceleration_axis(axis)
+    }
+
+    /// Reads the gyro data registers of a single axis in one transaction, in LSB of the current
+    /// full scale range, in the body frame.
+    pub fn raw_angular_velocity_axis(&mut self, axis: Axis) -> Result<i16, Error> {
+        self.driver().raw_angular_velocity_axis(axis)
+    }
+
+    /// Runs an abbreviated self-test, meant for idle windows of a running application.
+    ///
+    /// Both sensors run at 1kHz, ±4g and ±250°/s in low noise mode for the duration of the test,
+    /// which lasts about `2 * samples` ms plus twice `settle` plus the gyro startup time. The ranges,
+    /// ODRs and power mode are restored afterwards, including when the test fails with an error:
+    /// the sensors are put to sleep, so any ODR is accepted, then the ranges, ODRs and power mode
+    /// are written back. A failed step does not stop the following ones. If they cannot all be
+    /// restored, the first error is returned and the data reads fail until then, see
+    /// [`Self::maintenance`].
+    #[cfg(feature = "selftest")]
+    pub fn self_test_online(
+        &mut self,
+        config: SelfTestConfig,
+        delay: &mut impl DelayNs,
+    ) -> Result<SelfTestReport, Error> {
+        let mode = self.power_mode()?;
+        let accel_range = self.accel_range()?;
+        let accel_odr = self.accel_odr()?;
+        let gyro_range = self.gyro_range()?;
+        let gyro_odr = self.gyro_odr()?;
+
+        self.state.maintenance = Some(Maintenance::SelfTest);
+        let report = self.run_self_test(config, delay);
+
+        let restored: Result<(), Error> = [
+            self.driver().set_self_test(false),
+            self.set_power_mode(PowerMode::Sleep, delay),
+            self.set_accel_range(accel_range),
+            self.set_accel_odr(accel_odr),
+            self.set_gyro_range(gyro_range),
+            self.set_gyro_odr(gyro_odr),
+            self.set_power_mode(mode, delay),
+        ]
+        .into_iter()
+        .collect();
+        restored?;
+        self.state.maintenance = None;
+        report
+    }
+
+    #[cfg(feature = "selftest")]
+    fn run_self_test(
+        &mut self,
+        config: SelfTestConfig,
+        delay: &mut impl DelayNs,
+    ) -> Result<SelfTestReport, Error> {
+        self.set_power_mode(PowerMode::Sleep, delay)?;
+        self.set_accel_range(AccelRange::G4)?;
+        self.set_accel_odr(AccelODR::Hz1k)?;
+        self.set_gyro_range(GyroRange::Dps250)?;
+        self.set_gyro_odr(GyroODR::Hz1k)?;
+        self.set_power_mode(PowerMode::SixAxisLowNoise, delay)?;
+        delay.delay_ms(limits::GYRO_STARTUP_TIME_MS);
+
+        let (accel_off, gyro_off) = self.average_raw(config.samples, delay)?;
+        self.driver().set_self_test(true)?;
+        delay.delay_ms(config.settle.to_millis());
+        let (accel_on, gyro_on) = self.average_raw(config.samples, delay)?;
+        self.driver().set_self_test(false)?;
+        delay.delay_ms(config.settle.to_millis());
+
+        let (accel_codes, gyro_codes) = self.driver().factory_self_test_codes()?;
+        let response = |on: [f32; 3], off: [f32; 3]| core::array::from_fn(|i| on[i] - off[i]);
+        Ok(selftest::evaluate(
+            response(accel_on, accel_off),
+            response(gyro_on, gyro_off),
+            accel_codes,
+            gyro_codes,
+        ))
+    }
+
+    /// Averages raw accel and gyro samples taken 1ms apart.
+    #[cfg(feature = "selftest")]
+    fn average_raw(
+        &mut self,
+        samples: u16,
+        delay: &mut impl DelayNs,
+    ) -> Result<([f32; 3], [f32; 3]), Error> {
+        let mut accel = [0.0; 3];
+        let mut gyro = [0.0; 3];
+        for _ in 0..samples {
+            delay.delay_ms(1);
+            let (ax, ay, az) = self.driver().sensor_acceleration()?;
+            let (gx, gy, gz) = self.driver().sensor_angular_velocity()?;
+            for (sum, raw) in accel.iter_mut().zip([ax, ay, az]) {
+                *sum += raw as f32;
+            }
+            for (sum, raw) in gyro.iter_mut().zip([gx, gy, gz]) {
+                *sum += raw as f32;
+            }
+        }
+        let samples = samples.max(1) as f32;
+        Ok((
+            accel.map(|sum| sum / samples),
+            gyro.map(|sum| sum / samples),
+        ))
     }
 
     pub fn device_id(&mut self) -> Result<u8, Error> {
-        self.register_read(&Bank0::WhoAmI)
+        self.driver().device_id()
+    }
+
+    /// Sets the resolution of the timestamps.
+    pub fn set_timestamp_resolution(
+        &mut self,
+        resolution: TimestampResolution,
+    ) -> Result<(), Error> {
+        self.driver().set_timestamp_resolution(resolution)
+    }
+
+    /// Reads the active resolution of the timestamps.
+    pub fn timestamp_resolution(&mut self) -> Result<TimestampResolution, Error> {
+        self.driver().timestamp_resolution()
+    }
+
+    /// Reads which host interfaces the sensor honors.
+    ///
+    /// Helps debugging boards with both buses wired: after reset SPI and I2C are both enabled
+    /// until one is disabled, see [`SpiStartup::disable_i2c`].
+    pub fn active_interface(&mut self) -> Result<ActiveInterface, Error> {
+        self.driver().active_interface()
+    }
+
+    /// Restarts the ODR counter and the signal path, e.g. to align the sampling instants on an
+    /// external event. The sample in progress is lost.
+    pub fn abort_and_reset(&mut self) -> Result<(), Error> {
+        self.driver().abort_and_reset()
+    }
+
+    /// Latches the 20-bit timestamp counter and reads it, unit: µs
+    ///
+    /// The counter wraps, after about 1s at 1µs resolution.
+    pub fn latch_timestamp_us(&mut self) -> Result<u32, Error> {
+        self.driver().latch_timestamp_us()
+    }
+
+    /// Resets the DMP memory, which holds the APEX state, and waits the required 1ms.
+    ///
+    /// The APEX features must be configured again, then started with [`Self::init_dmp`].
+    #[cfg(feature = "apex")]
+    pub fn reset_dmp_memory(&mut self, delay: &mut impl DelayNs) -> Result<(), Error> {
+        self.driver().reset_dmp_memory()?;
+        delay.delay_ms(1);
+        Ok(())
+    }
+
+    /// Starts the DMP with the current APEX configuration and waits the required 50ms.
+    #[cfg(feature = "apex")]
+    pub fn init_dmp(&mut self, delay: &mut impl DelayNs) -> Result<(), Error> {
+        self.state.maintenance = Some(Maintenance::ApexInit);
+        self.driver().init_dmp()?;
+        delay.delay_ms(50);
+        self.state.maintenance = None;
+        Ok(())
+    }
+
+    /// Reads the clock source and the RTC (CLKIN) configuration.
+    ///
+    /// Use this to verify an external clock setup actually took effect.
+    pub fn clock_status(&mut self) -> Result<ClockStatus, Error> {
+        self.driver().clock_status()
+    }
+
+    /// Makes the timestamps report the time since the last ODR instead of an absolute time.
+    pub fn set_timestamp_delta(&mut self, enabled: bool) -> Result<(), Error> {
+        self.driver().set_timestamp_delta(enabled)
+    }
+
+    pub fn timestamp_delta(&mut self) -> Result<bool, Error> {
+        self.driver().timestamp_delta()
+    }
+
+    /// Time elapsed between two 16-bit timestamps at the last known resolution, unit: µs
+    ///
+    /// Accounts for the tick duration of the clock in use, the 32/30 scaling without an RTC
+    /// clock input or the RTC clock period at 16µs resolution, as read at initialization or by
+    /// [`Self::clock_status`]. See [`TimestampResolution::delta_us`].
+    pub fn timestamp_delta_us(&self, prev: u16, cur: u16) -> u32 {
+        let rtc_clock = self.state.rtc_clock;
+        self.state
+            .timestamp_resolution
+            .delta_us(prev, cur, rtc_clock)
+    }
+
+    /// Minimizes the gyro filter delay.
+    ///
+    /// Disables the gyro anti-alias and notch filters and selects `filter` as gyro UI filter.
+    /// The gyro output becomes noisier and signals above ODR/2, e.g. motor vibrations, alias into
+    /// the measured band; only use this if the delay matters more than the noise.
+    pub fn set_low_latency_mode(&mut self, filter: LowLatencyFilter) -> Result<(), Error> {
+        self.driver().set_low_latency_mode(filter)
+    }
+
+    /// Estimated sensor latency for control loop design, from the current ODRs and filters.
+    ///
+    /// The UI filter delay comes from the datasheet tables, rounded to 0.1ms. The anti-alias
+    /// filter delay is estimated from its bandwidth and the notch filter is neglected.
+    #[cfg(feature = "latency")]
+    pub fn estimated_group_delay_us(&mut self) -> Result<GroupDelay, Error> {
+        self.driver().estimated_group_delay()
+    }
+
+    /// Restores the default gyro filters after [`Self::set_low_latency_mode`].
+    pub fn clear_low_latency_mode(&mut self) -> Result<(), Error> {
+        self.driver().clear_low_latency_mode()
+    }
+
+    /// Checks the interrupt routing for mistakes that would leave a pin silent.
+    ///
+    /// Returns [`Error::InterruptConflict`] listing every event routed to both pins and every
+    /// routed APEX, WOM or SMD interrupt whose feature is disabled.
+    pub fn check_interrupts(&mut self) -> Result<(), Error> {
+        self.driver().check_interrupts()
+    }
+
+    /// Selects the byte order of the sensor data registers and FIFO packets.
+    ///
+    /// The driver decodes both orders. Little endian lets hosts with a little endian CPU map
+    /// DMA buffers of sensor data straight onto `i16` words.
+    pub fn set_sensor_data_endian(&mut self, endian: Endian) -> Result<(), Error> {
+        self.driver().set_sensor_data_endian(endian)
+    }
+
+    /// Reads the byte order of the sensor data, which the driver tracks from then on.
+    ///
+    /// The byte orders are read at init, so a configuration left by another firmware is
+    /// honored.
+    pub fn sensor_data_endian(&mut self) -> Result<Endian, Error> {
+        self.driver().sensor_data_endian()
+    }
+
+    /// Selects how the FIFO fills, it stays empty in the default [`FifoMode::Bypass`].
+    #[cfg(feature = "fifo")]
+    pub fn set_fifo_mode(&mut self, mode: FifoMode) -> Result<(), Error> {
+        self.driver().set_fifo_mode(mode)
+    }
+
+    #[cfg(feature = "fifo")]
+    pub fn fifo_mode(&mut self) -> Result<FifoMode, Error> {
+        self.driver().fifo_mode()
+    }
+
+    /// Selects the data pushed to the FIFO.
+    ///
+    /// The FIFO is flushed, as packets already stored have the previous layout.
+    #[cfg(feature = "fifo")]
+    pub fn set_fifo_enables(&mut self, enables: FifoEnables) -> Result<(), Error> {
+        self.driver().set_fifo_enables(enables)
+    }
+
+    #[cfg(feature = "fifo")]
+    pub fn fifo_enables(&mut self) -> Result<FifoEnables, Error> {
+        self.driver().fifo_enables()
+    }
+
+    /// Selects the structure of the packets pushed to the FIFO, [`Self::read_fifo`] then parses
+    /// packets of that size only.
+    ///
+    /// The temperature and FSYNC timestamp settings of [`FifoEnables`] are kept. The FIFO is
+    /// flushed, as packets already stored have the previous structure.
+    #[cfg(feature = "fifo")]
+    pub fn set_fifo_packet_config(&mut self, config: FifoPacketConfig) -> Result<(), Error> {
+        self.driver().set_fifo_packet_config(config)
+    }
+
+    /// Structure of the packets pushed to the FIFO, `None` when neither accel nor gyro data is
+    /// enabled.
+    #[cfg(feature = "fifo")]
+    pub fn fifo_packet_config(&mut self) -> Result<Option<FifoPacketConfig>, Error> {
+        self.driver().fifo_packet_config()
+    }
+
+    /// Selects the byte order of the FIFO count, the driver decodes both orders.
+    #[cfg(feature = "fifo")]
+    pub fn set_fifo_count_endian(&mut self, endian: Endian) -> Result<(), Error> {
+        self.driver().set_fifo_count_endian(endian)
+    }
+
+    #[cfg(feature = "fifo")]
+    pub fn fifo_count_endian(&mut self) -> Result<Endian, Error> {
+        self.driver().fifo_count_endian()
+    }
+
+    /// Selects the unit of the FIFO count register, the driver converts both to bytes.
+    ///
+    /// The FIFO watermark is converted to the new unit, so it keeps its size in bytes.
+    #[cfg(feature = "fifo")]
+    pub fn set_fifo_count_unit(&mut self, unit: FifoCountUnit) -> Result<(), Error> {
+        self.driver().set_fifo_count_unit(unit)
+    }
+
+    /// Reads the unit of the FIFO count register, which the driver tracks from then on.
+    #[cfg(feature = "fifo")]
+    pub fn fifo_count_unit(&mut self) -> Result<FifoCountUnit, Error> {
+        self.driver().fifo_count_unit()
+    }
+
+    /// Selects the content of the FIFO packets for invalid samples, the driver decodes both.
+    ///
+    /// With the default [`InvalidSampleMode::InvalidCode`], the FIFO readers report invalid
+    /// samples as missing data, e.g. [`FifoPacket::accelerometer`] returns `None`.
+    #[cfg(feature = "fifo")]
+    pub fn set_fifo_invalid_samples(&mut self, mode: InvalidSampleMode) -> Result<(), Error> {
+        self.driver().set_fifo_invalid_samples(mode)
+    }
+
+    /// Reads the content of the FIFO packets for invalid samples, which the driver tracks from
+    /// then on.
+    #[cfg(feature = "fifo")]
+    pub fn fifo_invalid_samples(&mut self) -> Result<InvalidSampleMode, Error> {
+        self.driver().fifo_invalid_samples()
+    }
+
+    /// Number of bytes pending in the FIFO, read in one transaction.
+    ///
+    /// Unlike [`Self::fifo_status`], INT_STATUS is not read, so no interrupt is acknowledged.
+    #[cfg(feature = "fifo")]
+    pub fn fifo_count(&mut self) -> Result<u16, Error> {
+        self.driver().fifo_count()
+    }
+
+    /// Switches `pin` to pulsed mode with `timing`.
+    ///
+    /// The pulse settings are common to both pins. See [`InterruptTiming::for_host`] to pick a
+    /// timing the host can detect at the configured ODR.
+    pub fn set_interrupt_timing(
+        &mut self,
+        pin: InterruptPin,
+        timing: InterruptTiming,
+    ) -> Result<(), Error> {
+        self.driver().set_interrupt_timing(pin, timing)
+    }
+
+    /// Reads the FIFO fill level and flags.
+    ///
+    /// INT_STATUS clears on read, so this also acknowledges pending data ready and FIFO
+    /// interrupts.
+    #[cfg(feature = "fifo")]
+    pub fn fifo_status(&mut self) -> Result<FifoStatus, Error> {
+        self.driver().fifo_status()
+    }
+
+    /// Reads the packets currently stored in the FIFO and passes them to `on_packet`.
+    ///
+    /// Each packet is read in one FIFO_DATA burst sized to the enabled sources. FIFO empty
+    /// markers are skipped, the returned [`DrainStats`] reports them alongside the number of
+    /// decoded packets. The FIFO status is read first, see [`Self::fifo_status`].
+    ///
+    /// FIFO_RESUME_PARTIAL_RD in FIFO_CONFIG1 is set before the first read and left set, so any
+    /// later FIFO_DATA read, including one performed by the application, continues after the last
+    /// byte read rather than restarting from the first byte of the interrupted burst.
+    ///
+    /// When a packet header does not match the sources enabled in the FIFO, the rest of the FIFO
+    /// content is dropped and the drain stops, see [`DrainStats::invalid_headers`]. The packets
+    /// already passed to `on_packet` stay counted.
+    #[cfg(feature = "fifo")]
+    pub fn drain_fifo(
+        &mut self,
+        mut on_packet: impl FnMut(&FifoPacket),
+    ) -> Result<DrainStats, Error> {
+        self.driver().drain_fifo(&mut on_packet)
+    }
+
+    /// Same as [`Self::drain_fifo`], reading at most `budget` so a control loop bounds the bus
+    /// time spent per tick.
+    ///
+    /// The packets left over stay in the FIFO for the next call, [`DrainStats::remaining_bytes`]
+    /// reports how many bytes they take. FIFO_RESUME_PARTIAL_RD is left set, as with
+    /// [`Self::drain_fifo`].
+    #[cfg(feature = "fifo")]
+    pub fn drain_fifo_budgeted(
+        &mut self,
+        budget: DrainBudget,
+        mut on_packet: impl FnMut(&FifoPacket),
+    ) -> Result<DrainStats, Error> {
+        self.driver()
+            .drain_fifo_budgeted(Some(budget), &mut on_packet)
+    }
+
+    /// Replaces the data ready interrupt of `pin` by a FIFO watermark interrupt firing about
+    /// `host_rate_hz` times per second, e.g. 1kHz for an 8kHz ODR, and returns the number of
+    /// packets per interrupt.
+    ///
+    /// The watermark is computed from the current ODRs and FIFO sources, accel and gyro are
+    /// stored in the FIFO if neither is, and the FIFO is switched to stream mode if bypassed. It
+    /// is capped at half the FIFO, so low host rates may fire more often than requested. The
+    /// interrupt fires when the FIFO count crosses the watermark, so drain the FIFO on each one,
+    /// see [`Self::drain_fifo`]. Call it again after changing an ODR.
+    #[cfg(feature = "fifo")]
+    pub fn moderate_interrupts(
+        &mut self,
+        pin: InterruptPin,
+        host_rate_hz: f32,
+    ) -> Result<u16, Error> {
+        self.driver().moderate_interrupts(pin, host_rate_hz)
+    }
+
+    /// Reads as many whole packets as fit in `buf` in a single FIFO_DATA burst, and returns
+    /// them parsed.
+    ///
+    /// Packets that do not fit are left in the FIFO for the next call. The FIFO status is read
+    /// first, see [`Self::fifo_status`]. Returns [`Error::FifoDesync`] when a packet header does
+    /// not match the sources enabled in the FIFO, after dropping the rest of the FIFO content.
+    ///
+    /// The interface must override [`Interface::read_registers`] with a real burst read, the
+    /// default implementation would step through the registers following FIFO_DATA.
+    #[cfg(feature = "fifo")]
+    pub fn read_fifo<'a>(&mut self, buf: &'a mut [u8]) -> Result<FifoPackets<'a>, Error> {
+        self.driver().read_fifo(buf)
+    }
+
+    /// Iterates over the packets stored in the FIFO, until it is empty.
+    ///
+    /// The packets are read a few at a time with [`Self::read_fifo`], an error ends the
+    /// iteration after being yielded. Packets pushed while iterating are yielded too.
+    #[cfg(feature = "fifo")]
+    pub fn fifo_packets(&mut self) -> Result<FifoReader<'_, I>, Error> {
+        FifoReader::new(self)
+    }
+
+    /// Reads as many whole packets as fit in `buf` in a single FIFO_DATA read, and returns the
+    /// filled part of `buf` as raw bytes, e.g. for an SPI bus doing the transfer by DMA.
+    ///
+    /// The read length is a multiple of the packet size of the enabled sources, so no packet is
+    /// split between calls, the packets that do not fit stay in the FIFO. Unlike
+    /// [`Self::read_fifo`] the headers are not checked and the device time is not extended,
+    /// [`fifo::parse_packets`] parses the returned bytes. Same interface requirement as
+    /// [`Self::read_fifo`].
+    #[cfg(feature = "fifo")]
+    pub fn read_fifo_burst<'a>(&mut self, buf: &'a mut [u8]) -> Result<&'a mut [u8], Error> {
+        self.driver().read_fifo_burst(buf)
+    }
+
+    /// Captures the packets surrounding an event into the FIFO, e.g. for impact forensics.
+    ///
+    /// The FIFO is flushed and set to [`FifoMode::Stream`], so it holds the latest packets, then
+    /// `trigger` is polled every [`CaptureConfig::poll_interval`] until it returns `true`,
+    /// e.g. `|imu| imu.wake_on_motion_event()`. Once [`CaptureConfig::post_trigger`] has
+    /// elapsed the FIFO is set to [`FifoMode::StopOnFull`]: being full by then, it keeps the
+    /// packets up to that instant and drops the new ones. A FIFO not full yet keeps filling
+    /// until it is.
+    ///
+    /// Returns the status of the frozen FIFO, its content is then read as usual, e.g. with
+    /// [`Self::fifo_packets`], and [`Self::set_fifo_mode`] resumes the recording. The FIFO
+    /// sources must be enabled beforehand, see [`Self::set_fifo_enables`]. Returns
+    /// [`Error::Timeout`] if the trigger did not fire within [`CaptureConfig::timeout`],
+    /// with the previous FIFO mode restored, as on errors of `trigger`.
+    #[cfg(feature = "fifo")]
+    pub fn capture_burst(
+        &mut self,
+        config: CaptureConfig,
+        delay: &mut impl DelayNs,
+        mut trigger: impl FnMut(&mut Self) -> Result<bool, Error>,
+    ) -> Result<FifoStatus, Error> {
+        let previous = self.fifo_mode()?;
+        self.set_fifo_mode(FifoMode::Stream)?;
+        self.flush_fifo()?;
+
+        let mut waited_ms = 0;
+        loop {
+            let fired = match trigger(self) {
+                Ok(fired) => fired,
+                Err(error) => {
+                    self.set_fifo_mode(previous)?;
+                    return Err(error);
+                }
+            };
+            if fired {
+                break;
+            }
+            if waited_ms >= config.timeout.to_millis() {
+                self.set_fifo_mode(previous)?;
+                return Err(Error::Timeout);
+            }
+            let poll_interval_ms = config.poll_interval.to_millis();
+            delay.delay_ms(poll_interval_ms);
+            waited_ms = waited_ms.saturating_add(poll_interval_ms.max(1));
+        }
+
+        delay.delay_ms(config.post_trigger.to_millis());
+        self.set_fifo_mode(FifoMode::StopOnFull)?;
+        self.fifo_status()
+    }
+
+    /// Drops the FIFO content, the FIFO count and the lost packet counter go back to zero.
+    #[cfg(feature = "fifo")]
+    pub fn flush_fifo(&mut self) -> Result<(), Error> {
+        self.driver().reset_fifo()
+    }
+
+    /// Sets the FIFO count raising the FIFO threshold interrupt, unit: byte
+    ///
+    /// Like the FIFO count, the watermark is converted to packets when the FIFO count is
+    /// reported in records, see [`Self::set_fifo_count_unit`]. The interrupt is routed by
+    /// [`Self::moderate_interrupts`], which also picks a watermark, see [`WatermarkTuner`] to
+    /// adjust it at runtime.
+    #[cfg(feature = "fifo")]
+    pub fn set_fifo_watermark(&mut self, watermark: u16) -> Result<(), Error> {
+        self.driver().set_fifo_watermark(watermark)
+    }
+
+    /// Reads the FIFO watermark, unit: byte
+    #[cfg(feature = "fifo")]
+    pub fn fifo_watermark(&mut self) -> Result<u16, Error> {
+        self.driver().fifo_watermark()
+    }
+
+    /// Hands the bus over for a FIFO read performed by the application, e.g. with DMA.
+    ///
+    /// Reads the FIFO status, see [`Self::fifo_status`], and selects register bank 0. Driver
+    /// calls are locked out until the returned guard is dropped.
+    #[cfg(feature = "fifo")]
+    pub fn begin_dma_fifo_read(&mut self) -> Result<FifoDmaGuard<'_, I>, Error> {
+        let count = self.driver().prepare_fifo_burst()?;
+        Ok(FifoDmaGuard::new(self, count))
     }
 
-    fn select_chip(&mut self) -> Result<(), Error> {
-        self.cs.set_low().map_err(|_| Error::Pin)
+    /// Output data rate measured from the FIFO timestamps, unit: Hz
+    ///
+    /// Estimated over windows of [`ODR_WINDOW`] timestamped packets read by
+    /// [`Self::drain_fifo`] or [`Self::read_sample`], `None` until a first window is complete.
+    /// Use it instead of the nominal ODR for integration time steps. The timestamps are counted
+    /// in device time, which follows the internal oscillator unless an RTC clock is fed on CLKIN,
+    /// see [`Self::clock_status`] and [`Self::device_time_us`].
+    #[cfg(feature = "fifo")]
+    pub fn measured_odr(&self) -> Option<f32> {
+        self.state.odr_estimator.estimate()
     }
 
-    fn unselect_chip(&mut self) -> Result<(), Error> {
-        self.cs.set_high().map_err(|_| Error::Pin)
+    /// Device time of the latest timestamped packet drained from the FIFO, unit: µs
+    ///
+    /// The 16-bit FIFO timestamps are extended to 64 bits by accumulating their wraps, the count
+    /// starts at the timestamp of the first drained packet. Consecutive timestamped packets must
+    /// be less than one wrap period apart, 65.5ms at 1µs resolution, so FIFO overflows and ODRs
+    /// below 25Hz make the time drift. Returns `None` until a packet holding an ODR timestamp has
+    /// been read from the FIFO.
+    ///
+    /// The tick duration of the clock in use is accounted for, as read at initialization or by
+    /// [`Self::clock_status`]: 32/30 of the nominal resolution without an RTC clock input, one
+    /// RTC clock period at 16µs resolution with one, see [`TimestampResolution::ticks_us`]. The
+    /// count restarts when the resolution is changed with [`Self::set_timestamp_resolution`].
+    #[cfg(feature = "fifo")]
+    pub fn device_time_us(&self) -> Option<u64> {
+        self.state.device_time_us()
+    }
+
+    /// Enables APEX tap detection and routes its interrupt to `pin`.
+    ///
+    /// Tap detection runs on the accelerometer at 1kHz in low noise mode, the accel ODR, UI filter
+    /// and power mode are changed accordingly. `pin` is configured as push-pull, active high and
+    /// stays asserted until the tap is read with [`Self::tap_event`]. Conflicts with interrupts
+    /// already routed are reported as by [`Self::check_interrupts`].
+    #[cfg(feature = "apex")]
+    pub fn enable_tap_detection(
+        &mut self,
+        pin: InterruptPin,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error> {
+        self.driver().prepare_tap_detection()?;
+        delay.delay_ms(1);
+        self.driver().configure_tap_detection()?;
+        delay.delay_ms(1);
+        self.driver().route_tap_interrupt(pin)?;
+        delay.delay_ms(50);
+        self.driver().enable_tap_detection()?;
+        self.driver().check_interrupts()
+    }
+
+    /// Reads the pending tap, if any.
+    #[cfg(feature = "apex")]
+    pub fn tap_event(&mut self) -> Result<Option<TapEvent>, Error> {
+        self.driver().tap_event()
+    }
+
+    /// Enables wake on motion, reported by [`Self::wake_on_motion_event`].
+    ///
+    /// An event is raised when any axis changes by more than `threshold_mg` between two accel
+    /// samples, up to 1000mg. The accelerometer must be running, typically in low power mode.
+    #[cfg(feature = "apex")]
+    pub fn enable_wake_on_motion(
+        &mut self,
+        threshold_mg: f32,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error> {
+        self.enable_wake_on_motion_with(threshold_mg, WomConfig::default(), delay)
+    }
+
+    /// Same as [`Self::enable_wake_on_motion`], with the axes combination and the reference
+    /// sample of `config`.
+    #[cfg(feature = "apex")]
+    pub fn enable_wake_on_motion_with(
+        &mut self,
+        threshold_mg: f32,
+        config: WomConfig,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error> {
+        let threshold = (threshold_mg * 256.0 / 1000.0).clamp(0.0, 255.0) as u8;
+        self.driver().configure_wake_on_motion(threshold)?;
+        delay.delay_ms(50);
+        self.driver().enable_wake_on_motion(config)
+    }
+
+    /// Whether a wake on motion event occurred since the last call.
+    #[cfg(feature = "apex")]
+    pub fn wake_on_motion_event(&mut self) -> Result<bool, Error> {
+        self.driver().wake_on_motion_event()
+    }
+
+    fn driver(&mut self) -> Driver<'_> {
+        Driver {
+            interface: &mut self.interface,
+            state: &mut self.state,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        driver::tests::RegisterFile,
+        register::{Bank0, Register},
+    };
+
+    pub(crate) struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    pub(crate) fn imu() -> Icm42688p<RegisterFile> {
+        let mut interface = RegisterFile::new();
+        // Reset values: ±16g / ±2000dps at 1kHz.
+        interface.banks[0][Bank0::AccelConfig0.address().0 as usize] = 0x06;
+        interface.banks[0][Bank0::GyroConfig0.address().0 as usize] = 0x06;
+        Icm42688p {
+            interface,
+            state: State::new(Units::default()),
+        }
+    }
+
+    #[cfg(feature = "selftest")]
+    #[test]
+    fn self_test_online_restores_low_power_odr() {
+        let mut imu = imu();
+        let mut delay = NoDelay;
+        imu.set_accel_odr(AccelODR::Hz6_25).unwrap();
+        imu.set_power_mode(PowerMode::AccelLowPower, &mut delay)
+            .unwrap();
+
+        let _ = imu.self_test_online(SelfTestConfig::default(), &mut delay);
+        assert_eq!(imu.power_mode().unwrap(), PowerMode::AccelLowPower);
+        assert_eq!(imu.accel_odr().unwrap(), AccelODR::Hz6_25);
+        assert_eq!(imu.maintenance(), None);
+    }
+
+    #[cfg(feature = "micromath")]
+    #[test]
+    fn acceleration_as_micromath_vector() {
+        let mut imu = imu();
+        // 1g on Z at ±16g.
+        imu.interface.banks[0][Bank0::AccelDataZ1.address().0 as usize] = 0x08;
+
+        let accel: micromath::vector::F32x3 = imu.acceleration_vector().unwrap();
+        assert_eq!((accel.x, accel.y, accel.z), (0.0, 0.0, 1.0));
+        let accel: [f32; 3] = imu.acceleration_vector().unwrap();
+        assert_eq!(accel, [0.0, 0.0, 1.0]);
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn watermark_tuner_writes_records() {
+        let mut imu = imu();
+        imu.interface.banks[0][Bank0::FifoConfig1.address().0 as usize] = 0x03;
+        imu.set_fifo_count_unit(FifoCountUnit::Records).unwrap();
+        let bounds = WatermarkBounds {
+            min_packets: 4,
+            max_packets: 10,
+        };
+        let fifo_wm = |imu: &Icm42688p<RegisterFile>| {
+            imu.interface.banks[0][Bank0::FifoConfig2.address().0 as usize]
+        };
+
+        let mut tuner = WatermarkTuner::new(&mut imu, bounds).unwrap();
+        assert_eq!(fifo_wm(&imu), 4);
+        // Late drain of 9 packets of 16 bytes.
+        let stats = DrainStats {
+            bytes: 9 * 16,
+            ..Default::default()
+        };
+        assert_eq!(tuner.update(&mut imu, &stats).unwrap(), 5);
+        assert_eq!(fifo_wm(&imu), 5);
+        assert_eq!(imu.fifo_watermark().unwrap(), 5 * 16);
     }
 }
@@ -0,0 +1,75 @@
+//! Limits and timings from the datasheet (DS-000347 rev 1.8).
+
+/// Maximum SPI clock frequency, unit: Hz
+pub const SPI_MAX_FREQ_HZ: u32 = 24_000_000;
+
+/// Maximum I2C clock frequency, unit: Hz
+pub const I2C_MAX_FREQ_HZ: u32 = 1_000_000;
+
+/// Frequency of the RTC clock on CLKIN in RTC mode, unit: Hz
+pub const RTC_FREQ_HZ: u32 = 32_768;
+
+/// Lowest accelerometer ODR, unit: Hz
+pub const ACCEL_ODR_MIN_HZ: f32 = 1.5625;
+
+/// Highest accelerometer ODR, unit: Hz
+pub const ACCEL_ODR_MAX_HZ: f32 = 32000.0;
+
+/// Highest accelerometer ODR in low power mode, unit: Hz
+pub const ACCEL_LP_ODR_MAX_HZ: f32 = 500.0;
+
+/// Lowest accelerometer ODR in low noise mode, unit: Hz
+pub const ACCEL_LN_ODR_MIN_HZ: f32 = 12.5;
+
+/// Largest accel user offset, unit: g
+pub const ACCEL_OFFSET_MAX_G: f32 = 1.0;
+
+/// Resolution of the accel user offset, unit: g
+pub const ACCEL_OFFSET_RESOLUTION_G: f32 = 0.0005;
+
+/// Lowest gyroscope ODR, unit: Hz
+pub const GYRO_ODR_MIN_HZ: f32 = 12.5;
+
+/// Highest gyroscope ODR, unit: Hz
+pub const GYRO_ODR_MAX_HZ: f32 = 32000.0;
+
+/// Physical size of the FIFO, unit: byte
+pub const FIFO_SIZE: usize = 2048;
+
+/// Buffer size needed to hold the whole FIFO content in the worst case, unit: byte
+///
+/// Larger than [`FIFO_SIZE`] as the read cache can hold up to two extra packets.
+pub const FIFO_BUFFER_SIZE: usize = 2080;
+
+/// Size of an accel or gyro only FIFO packet (packet 1 and 2), unit: byte
+pub const FIFO_PACKET_SIZE_SINGLE: usize = 8;
+
+/// Size of an accel and gyro FIFO packet (packet 3), unit: byte
+pub const FIFO_PACKET_SIZE_COMBINED: usize = 16;
+
+/// Size of a high resolution FIFO packet (packet 4), unit: byte
+pub const FIFO_PACKET_SIZE_HIRES: usize = 20;
+
+/// Number of accel and gyro packets the FIFO can hold.
+pub const FIFO_CAPACITY_COMBINED: usize = FIFO_SIZE / FIFO_PACKET_SIZE_COMBINED;
+
+/// Number of high resolution packets the FIFO can hold.
+pub const FIFO_CAPACITY_HIRES: usize = FIFO_SIZE / FIFO_PACKET_SIZE_HIRES;
+
+/// Time from power-up until registers can be accessed, unit: ms
+pub const POWER_UP_TIME_MS: u32 = 1;
+
+/// Time for a soft reset to complete, unit: ms
+pub const SOFT_RESET_TIME_MS: u32 = 1;
+
+/// Time from gyroscope enable to valid data, unit: ms
+pub const GYRO_STARTUP_TIME_MS: u32 = 30;
+
+/// Time from accelerometer sleep to valid data, unit: ms
+pub const ACCEL_STARTUP_TIME_MS: u32 = 10;
+
+/// Minimum time the gyroscope must stay on once enabled, unit: ms
+pub const GYRO_MIN_ON_TIME_MS: u32 = 45;
+
+/// Time without register writes after turning a sensor on from off, unit: µs
+pub const PWR_MGMT_WRITE_DELAY_US: u32 = 200;
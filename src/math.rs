@@ -0,0 +1,91 @@
+/// Approximate 1/√x, relative error below 0.2%, for targets without an FPU square root.
+///
+/// Uses the bit level initial guess refined by one Newton-Raphson step. Returns a meaningless
+/// value for `x <= 0`.
+pub fn fast_inv_sqrt(x: f32) -> f32 {
+    let guess = f32::from_bits(0x5F37_5A86 - (x.to_bits() >> 1));
+    guess * (1.5 - 0.5 * x * guess * guess)
+}
+
+/// Scales `v` to unit length with [`fast_inv_sqrt`], `None` for the zero vector.
+///
+/// Works on the tuples returned by the driver, e.g. to get the gravity direction from
+/// [`crate::Icm42688p::acceleration`].
+pub fn normalize((x, y, z): (f32, f32, f32)) -> Option<(f32, f32, f32)> {
+    let norm_squared = x * x + y * y + z * z;
+    if norm_squared <= f32::MIN_POSITIVE {
+        return None;
+    }
+    let scale = fast_inv_sqrt(norm_squared);
+    Some((x * scale, y * scale, z * scale))
+}
+
+/// Same as [`normalize`], for a `micromath` vector.
+#[cfg(feature = "micromath")]
+pub fn normalize_vector(v: micromath::vector::F32x3) -> Option<micromath::vector::F32x3> {
+    let (x, y, z) = normalize((v.x, v.y, v.z))?;
+    Some(micromath::vector::F32x3 { x, y, z })
+}
+
+/// Smallest rotation from the measured gravity direction to the sensor Z axis, i.e. the tilt of
+/// the sensor at rest, from an acceleration in any unit. `None` for the zero vector.
+#[cfg(feature = "micromath")]
+pub fn tilt_quaternion(accel: (f32, f32, f32)) -> Option<micromath::Quaternion> {
+    let (x, y, z) = normalize(accel)?;
+    let up = micromath::vector::F32x3 {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    Some(micromath::Quaternion::from_two_vectors(
+        micromath::vector::F32x3 { x, y, z },
+        up,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_inv_sqrt_error_bound() {
+        let mut x = 1e-6;
+        while x < 1e6 {
+            let exact = 1.0 / (x as f64).sqrt();
+            let error = ((fast_inv_sqrt(x) as f64 - exact) / exact).abs();
+            assert!(error < 0.002, "x = {x}, relative error = {error}");
+            x *= 1.07;
+        }
+    }
+
+    #[test]
+    fn normalize_unit_length() {
+        let (x, y, z) = normalize((3.0, -4.0, 12.0)).unwrap();
+        let length = (x * x + y * y + z * z) as f64;
+        assert!((length.sqrt() - 1.0).abs() < 0.002);
+        assert!((x / y + 0.75).abs() < 1e-6);
+        assert!((z / x - 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn normalize_zero_vector() {
+        assert_eq!(normalize((0.0, 0.0, 0.0)), None);
+        assert_eq!(normalize((0.0, -0.0, 0.0)), None);
+    }
+
+    #[cfg(feature = "micromath")]
+    #[test]
+    fn tilt_quaternion_rotates_gravity_to_z() {
+        use micromath::vector::F32x3;
+
+        let accel = F32x3 {
+            x: 0.0,
+            y: 1.0,
+            z: 1.0,
+        };
+        let q = tilt_quaternion((accel.x, accel.y, accel.z)).unwrap();
+        let up = q.rotate(normalize_vector(accel).unwrap());
+        assert!(up.x.abs() < 0.01 && up.y.abs() < 0.01 && (up.z - 1.0).abs() < 0.01);
+        assert!(tilt_quaternion((0.0, 0.0, 0.0)).is_none());
+    }
+}
@@ -0,0 +1,146 @@
+/// Number of accel samples the motion classification is computed over.
+pub const MOTION_WINDOW: usize = 32;
+
+/// Motion classification
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum MotionState {
+    Stationary,
+    /// Also reported until [`MOTION_WINDOW`] samples have been collected.
+    #[default]
+    Moving,
+}
+
+/// Thresholds of the motion classification on the standard deviation of the acceleration
+/// vector, i.e. the square root of the summed per axis variances, unit: g
+///
+/// The gap between both thresholds is the hysteresis keeping the state from toggling on noise.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MotionConfig {
+    /// Below this the sensor becomes stationary.
+    pub stationary_below: f32,
+    /// Above this the sensor becomes moving.
+    pub moving_above: f32,
+}
+
+impl Default for MotionConfig {
+    fn default() -> Self {
+        Self {
+            stationary_below: 0.01,
+            moving_above: 0.02,
+        }
+    }
+}
+
+/// Sliding window of accel samples classified by their variance.
+pub(crate) struct MotionDetector {
+    pub config: MotionConfig,
+    window: [(f32, f32, f32); MOTION_WINDOW],
+    len: usize,
+    next: usize,
+    state: MotionState,
+}
+
+impl MotionDetector {
+    pub fn new() -> Self {
+        Self {
+            config: MotionConfig::default(),
+            window: [(0.0, 0.0, 0.0); MOTION_WINDOW],
+            len: 0,
+            next: 0,
+            state: MotionState::default(),
+        }
+    }
+
+    /// Adds an acceleration sample in g and classifies the window.
+    pub fn update(&mut self, sample: (f32, f32, f32)) -> MotionState {
+        self.window[self.next] = sample;
+        self.next = (self.next + 1) % MOTION_WINDOW;
+        self.len = (self.len + 1).min(MOTION_WINDOW);
+        if self.len < MOTION_WINDOW {
+            return self.state;
+        }
+
+        let variance = self.variance();
+        let square = |x: f32| x * x;
+        match self.state {
+            MotionState::Moving if variance < square(self.config.stationary_below) => {
+                self.state = MotionState::Stationary
+            }
+            MotionState::Stationary if variance > square(self.config.moving_above) => {
+                self.state = MotionState::Moving
+            }
+            _ => {}
+        }
+        self.state
+    }
+
+    pub fn state(&self) -> MotionState {
+        self.state
+    }
+
+    /// Sum of the per axis variances of the window, unit: g²
+    fn variance(&self) -> f32 {
+        let n = MOTION_WINDOW as f32;
+        let (mut sx, mut sy, mut sz) = (0.0, 0.0, 0.0);
+        for (x, y, z) in self.window {
+            (sx, sy, sz) = (sx + x, sy + y, sz + z);
+        }
+        let mean = (sx / n, sy / n, sz / n);
+        let mut sum = 0.0;
+        for (x, y, z) in self.window {
+            let d = (x - mean.0, y - mean.1, z - mean.2);
+            sum += d.0 * d.0 + d.1 * d.1 + d.2 * d.2;
+        }
+        sum / n
+    }
+}
+
+/// Weight of a new stationary gyro sample in the bias estimate.
+const GYRO_BIAS_GAIN: f32 = 0.02;
+
+/// Gyro bias estimated from the readings taken while stationary.
+pub(crate) struct GyroBiasTracker {
+    pub enabled: bool,
+    /// unit: °/s
+    bias: Option<(f32, f32, f32)>,
+    /// Number of readings since the bias was last updated.
+    age: u32,
+}
+
+impl GyroBiasTracker {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            bias: None,
+            age: 0,
+        }
+    }
+
+    /// Updates the estimate with a reading in °/s if `motion` is stationary and returns the
+    /// reading with the bias removed.
+    pub fn correct(&mut self, dps: (f32, f32, f32), motion: MotionState) -> (f32, f32, f32) {
+        if !self.enabled {
+            return dps;
+        }
+        if motion == MotionState::Stationary {
+            let blend = |bias: f32, x: f32| bias + GYRO_BIAS_GAIN * (x - bias);
+            self.bias = Some(match self.bias {
+                None => dps,
+                Some(b) => (blend(b.0, dps.0), blend(b.1, dps.1), blend(b.2, dps.2)),
+            });
+            self.age = 0;
+        } else {
+            self.age = self.age.saturating_add(1);
+        }
+        let b = self.bias.unwrap_or_default();
+        (dps.0 - b.0, dps.1 - b.1, dps.2 - b.2)
+    }
+
+    pub fn bias(&self) -> Option<(f32, f32, f32)> {
+        self.bias
+    }
+
+    pub fn age(&self) -> Option<u32> {
+        self.bias.map(|_| self.age)
+    }
+}
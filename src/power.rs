@@ -0,0 +1,253 @@
+use embedded_hal::delay::DelayNs;
+
+use crate::{config::PowerMode, error::Error, Icm42688p, Interface, MillisDurationU32};
+
+/// State of a [`PowerPolicyEngine`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerState {
+    /// No motion, the sensor runs in [`PowerPolicy::idle_mode`] waiting for wake on motion.
+    Idle,
+    /// Motion was detected, waiting for it to be sustained before escalating.
+    Waking,
+    /// Sustained motion, the sensor runs in [`PowerPolicy::active_mode`].
+    Active,
+}
+
+/// Transitions of a [`PowerPolicyEngine`]
+///
+/// Idle → Waking on a wake on motion event, Waking → Active once motion lasted
+/// `sustained`, Waking → Idle if motion stops for `sustained`, Active → Idle after `inactivity`
+/// without motion.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PowerPolicy {
+    /// Power mode of the idle and waking states, must keep the accelerometer running.
+    pub idle_mode: PowerMode,
+    /// Power mode of the active state.
+    pub active_mode: PowerMode,
+    /// Wake on motion threshold, unit: mg
+    pub wake_threshold_mg: f32,
+    /// Duration of motion required to become active.
+    pub sustained: MillisDurationU32,
+    /// Duration without motion before going back to idle.
+    ///
+    /// Should be at least [`crate::limits::GYRO_MIN_ON_TIME_MS`] when the active mode turns the
+    /// gyroscope on.
+    pub inactivity: MillisDurationU32,
+}
+
+impl Default for PowerPolicy {
+    fn default() -> Self {
+        Self {
+            idle_mode: PowerMode::AccelLowPower,
+            active_mode: PowerMode::SixAxisLowNoise,
+            wake_threshold_mg: 50.0,
+            sustained: MillisDurationU32::millis(500),
+            inactivity: MillisDurationU32::millis(5000),
+        }
+    }
+}
+
+/// Runs a [`PowerPolicy`] from wake on motion events.
+///
+/// [`Self::update`] is meant to be called periodically, or from the WOM interrupt handler, with
+/// a millisecond clock. Gyro data are only valid [`crate::limits::GYRO_STARTUP_TIME_MS`] after
+/// entering a power mode turning the gyroscope on.
+pub struct PowerPolicyEngine {
+    policy: PowerPolicy,
+    state: PowerState,
+    /// Time the current state was entered, unit: ms
+    entered_ms: u32,
+    /// Time of the last wake on motion event, unit: ms
+    last_motion_ms: u32,
+}
+
+impl PowerPolicyEngine {
+    /// Enters the idle state and enables wake on motion.
+    pub fn new<I: Interface>(
+        imu: &mut Icm42688p<I>,
+        policy: PowerPolicy,
+        now_ms: u32,
+        delay: &mut impl DelayNs,
+    ) -> Result<Self, Error> {
+        imu.set_power_mode(policy.idle_mode, delay)?;
+        delay.delay_ms(1);
+        imu.enable_wake_on_motion(policy.wake_threshold_mg, delay)?;
+        Ok(Self {
+            policy,
+            state: PowerState::Idle,
+            entered_ms: now_ms,
+            last_motion_ms: now_ms,
+        })
+    }
+
+    pub fn state(&self) -> PowerState {
+        self.state
+    }
+
+    pub fn policy(&self) -> PowerPolicy {
+        self.policy
+    }
+
+    /// Polls the wake on motion event and runs the resulting transition, if any.
+    ///
+    /// `on_transition` is called with the previous and the new state after the power mode was
+    /// changed.
+    pub fn update<I: Interface>(
+        &mut self,
+        imu: &mut Icm42688p<I>,
+        now_ms: u32,
+        delay: &mut impl DelayNs,
+        mut on_transition: impl FnMut(PowerState, PowerState),
+    ) -> Result<PowerState, Error> {
+        let motion = imu.wake_on_motion_event()?;
+        if motion {
+            self.last_motion_ms = now_ms;
+        }
+        let in_state = now_ms.wrapping_sub(self.entered_ms);
+        let still = now_ms.wrapping_sub(self.last_motion_ms);
+        let next = match self.state {
+            PowerState::Idle if motion => PowerState::Waking,
+            PowerState::Waking if still >= self.policy.sustained.to_millis() => PowerState::Idle,
+            PowerState::Waking if in_state >= self.policy.sustained.to_millis() => {
+                PowerState::Active
+            }
+            PowerState::Active if still >= self.policy.inactivity.to_millis() => PowerState::Idle,
+            state => state,
+        };
+        if next != self.state {
+            match next {
+                PowerState::Active => imu.set_power_mode(self.policy.active_mode, delay)?,
+                PowerState::Idle if self.state == PowerState::Active => {
+                    imu.set_power_mode(self.policy.idle_mode, delay)?
+                }
+                _ => {}
+            }
+            let previous = self.state;
+            self.state = next;
+            self.entered_ms = now_ms;
+            on_transition(previous, next);
+        }
+        Ok(self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::AccelODR,
+        driver::tests::RegisterFile,
+        register::{Bank0, Register, INT_STATUS2},
+        tests::{imu, NoDelay},
+    };
+
+    /// Driver with the accelerometer at 100Hz, an ODR of both power modes of the policy.
+    fn low_power_imu() -> Icm42688p<RegisterFile> {
+        let mut imu = imu();
+        imu.interface.banks[0][Bank0::AccelConfig0.address().0 as usize] = AccelODR::Hz100 as u8;
+        imu
+    }
+
+    /// Runs an update with a wake on motion event if `motion`, returning the new state and the
+    /// transition reported.
+    fn update(
+        engine: &mut PowerPolicyEngine,
+        imu: &mut Icm42688p<RegisterFile>,
+        now_ms: u32,
+        motion: bool,
+    ) -> (PowerState, Option<(PowerState, PowerState)>) {
+        let status = if motion {
+            INT_STATUS2::WOM_INT.put(0b111)
+        } else {
+            0
+        };
+        imu.interface.banks[0][Bank0::IntStatus2.address().0 as usize] = status;
+        let mut transition = None;
+        let state = engine
+            .update(imu, now_ms, &mut NoDelay, |from, to| {
+                transition = Some((from, to))
+            })
+            .unwrap();
+        (state, transition)
+    }
+
+    #[test]
+    fn power_policy_escalates_on_sustained_motion() {
+        let mut imu = low_power_imu();
+        let mut engine =
+            PowerPolicyEngine::new(&mut imu, PowerPolicy::default(), 0, &mut NoDelay).unwrap();
+        assert_eq!(engine.state(), PowerState::Idle);
+        assert_eq!(imu.power_mode().unwrap(), PowerMode::AccelLowPower);
+
+        assert_eq!(
+            update(&mut engine, &mut imu, 100, true),
+            (
+                PowerState::Waking,
+                Some((PowerState::Idle, PowerState::Waking))
+            )
+        );
+        assert_eq!(imu.power_mode().unwrap(), PowerMode::AccelLowPower);
+        assert_eq!(
+            update(&mut engine, &mut imu, 599, true),
+            (PowerState::Waking, None)
+        );
+        assert_eq!(
+            update(&mut engine, &mut imu, 600, true),
+            (
+                PowerState::Active,
+                Some((PowerState::Waking, PowerState::Active))
+            )
+        );
+        assert_eq!(imu.power_mode().unwrap(), PowerMode::SixAxisLowNoise);
+    }
+
+    #[test]
+    fn power_policy_falls_back_when_motion_stops() {
+        let mut imu = low_power_imu();
+        let mut engine =
+            PowerPolicyEngine::new(&mut imu, PowerPolicy::default(), 0, &mut NoDelay).unwrap();
+
+        update(&mut engine, &mut imu, 100, true);
+        assert_eq!(
+            update(&mut engine, &mut imu, 599, false),
+            (PowerState::Waking, None)
+        );
+        assert_eq!(
+            update(&mut engine, &mut imu, 600, false),
+            (
+                PowerState::Idle,
+                Some((PowerState::Waking, PowerState::Idle))
+            )
+        );
+        assert_eq!(imu.power_mode().unwrap(), PowerMode::AccelLowPower);
+    }
+
+    #[test]
+    fn power_policy_goes_idle_after_inactivity_across_wraparound() {
+        let mut imu = low_power_imu();
+        let start = u32::MAX - 1000;
+        let at = |ms: u32| start.wrapping_add(ms);
+        let mut engine =
+            PowerPolicyEngine::new(&mut imu, PowerPolicy::default(), start, &mut NoDelay).unwrap();
+
+        update(&mut engine, &mut imu, at(100), true);
+        assert_eq!(
+            update(&mut engine, &mut imu, at(600), true).0,
+            PowerState::Active
+        );
+        // The millisecond clock wraps during the inactivity.
+        assert_eq!(
+            update(&mut engine, &mut imu, at(5599), false),
+            (PowerState::Active, None)
+        );
+        assert_eq!(imu.power_mode().unwrap(), PowerMode::SixAxisLowNoise);
+        assert_eq!(
+            update(&mut engine, &mut imu, at(5600), false),
+            (
+                PowerState::Idle,
+                Some((PowerState::Active, PowerState::Idle))
+            )
+        );
+        assert_eq!(imu.power_mode().unwrap(), PowerMode::AccelLowPower);
+    }
+}
@@ -0,0 +1,10 @@
+//! Common imports: `use icm42688p::prelude::*;`
+//!
+//! Exports the driver, the bus interfaces, the configuration types and the sample types. Less
+//! common types, e.g. FIFO packets or APEX events, are imported from the crate root.
+
+pub use crate::{
+    AccelODR, AccelRange, AccelUnit, Config, DataSource, Error, GyroODR, GyroRange, GyroUnit,
+    I2cAddress, I2cInterface, Icm42688p, Interface, InterruptPin, PowerMode, Sample,
+    SampleWithTemp, SensorData, SpiInterface, TempUnit, Units,
+};
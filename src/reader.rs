@@ -0,0 +1,373 @@
+use embedded_hal::delay::DelayNs;
+
+use crate::{
+    config::Resolution, driver::Driver, error::Error, Icm42688p, Interface, MicrosDurationU32,
+};
+
+/// Sample read by [`Icm42688p::read_sample`], in the configured units.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sample {
+    pub acceleration: (f32, f32, f32),
+    pub angular_velocity: (f32, f32, f32),
+    /// Resolution of the data the sample was converted from.
+    pub resolution: Resolution,
+    /// Incremented by one for each sample the driver returns, from the registers or the FIFO,
+    /// wrapping after `u32::MAX`. A gap or a step back downstream means samples were dropped or
+    /// reordered.
+    pub sequence: u32,
+}
+
+impl Sample {
+    /// Size of [`Self::to_bytes`], in bytes.
+    pub const ENCODED_SIZE: usize = 29;
+    /// Size of [`Self::to_bytes_with_crc`], in bytes.
+    pub const ENCODED_SIZE_WITH_CRC: usize = Self::ENCODED_SIZE + 2;
+
+    /// Packs the sample for a byte stream.
+    ///
+    /// Layout: acceleration X, Y, Z then angular velocity X, Y, Z as little endian `f32` at
+    /// offsets 0 to 20, then the resolution at offset 24, 0 for 16-bit and 1 for 20-bit data,
+    /// then the sequence number as little endian `u32` at offset 25.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_SIZE] {
+        let (ax, ay, az) = self.acceleration;
+        let (gx, gy, gz) = self.angular_velocity;
+        let mut bytes = [0; Self::ENCODED_SIZE];
+        for (chunk, value) in bytes.chunks_exact_mut(4).zip([ax, ay, az, gx, gy, gz]) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes[24] = match self.resolution {
+            Resolution::Bits16 => 0,
+            Resolution::Bits20 => 1,
+        };
+        bytes[25..].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes
+    }
+
+    /// Unpacks a sample packed by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8; Self::ENCODED_SIZE]) -> Result<Self, Error> {
+        let value =
+            |i: usize| f32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let resolution = match bytes[24] {
+            0 => Resolution::Bits16,
+            1 => Resolution::Bits20,
+            _ => return Err(Error::DataCorrupted),
+        };
+        Ok(Self {
+            acceleration: (value(0), value(4), value(8)),
+            angular_velocity: (value(12), value(16), value(20)),
+            resolution,
+            sequence: u32::from_le_bytes([bytes[25], bytes[26], bytes[27], bytes[28]]),
+        })
+    }
+
+    /// Same as [`Self::to_bytes`], followed by the little endian CRC-16/CCITT-FALSE of the
+    /// packed sample.
+    pub fn to_bytes_with_crc(&self) -> [u8; Self::ENCODED_SIZE_WITH_CRC] {
+        let mut bytes = [0; Self::ENCODED_SIZE_WITH_CRC];
+        let (sample, crc) = bytes.split_at_mut(Self::ENCODED_SIZE);
+        sample.copy_from_slice(&self.to_bytes());
+        crc.copy_from_slice(&crc16(sample).to_le_bytes());
+        bytes
+    }
+
+    /// Unpacks a sample packed by [`Self::to_bytes_with_crc`].
+    ///
+    /// Returns [`Error::DataCorrupted`] on a CRC mismatch.
+    pub fn from_bytes_with_crc(bytes: &[u8; Self::ENCODED_SIZE_WITH_CRC]) -> Result<Self, Error> {
+        let (sample, crc) = bytes.split_at(Self::ENCODED_SIZE);
+        if crc16(sample).to_le_bytes() != crc {
+            return Err(Error::DataCorrupted);
+        }
+        let mut packed = [0; Self::ENCODED_SIZE];
+        packed.copy_from_slice(sample);
+        Self::from_bytes(&packed)
+    }
+}
+
+/// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xFFFF, no reflection.
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFF_u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Sample and temperature read in one transaction by [`Icm42688p::read_sample_with_temp`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SampleWithTemp {
+    pub sample: Sample,
+    /// In the configured temperature unit.
+    pub temperature: f32,
+}
+
+/// Sample read by [`Icm42688p::sample`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SensorData {
+    /// In the configured acceleration unit.
+    pub accel: (f32, f32, f32),
+    /// In the configured angular velocity unit.
+    pub gyro: (f32, f32, f32),
+    /// In the configured temperature unit.
+    pub temperature: f32,
+    /// Device time latched right after the data registers were read, unit: µs
+    ///
+    /// The 20-bit counter wraps, after about 1s at 1µs resolution.
+    pub timestamp: u32,
+}
+
+/// Content of the sensor data registers read in one transaction by
+/// [`Icm42688p::read_sensor_data`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawSensorData {
+    pub temperature: RawTemperature,
+    /// Unit: LSB of the accel full-scale range
+    pub acceleration: (i16, i16, i16),
+    /// Unit: LSB of the gyro full-scale range
+    pub angular_velocity: (i16, i16, i16),
+}
+
+/// Content of the TEMP_DATA registers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawTemperature(pub i16);
+
+impl RawTemperature {
+    /// Unit: °C
+    pub fn celsius(self) -> f32 {
+        (self.0 as f32 / 132.48) + 25.0
+    }
+}
+
+/// Sample read by [`MixedRateReader`], in the configured units.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MixedSample {
+    /// `None` on ticks where no new accel sample is due.
+    pub acceleration: Option<(f32, f32, f32)>,
+    /// `None` on ticks where no new gyro sample is due.
+    pub angular_velocity: Option<(f32, f32, f32)>,
+}
+
+/// Reader for accel and gyro running at different ODRs.
+///
+/// [`Self::read`] is meant to be called once per sample of the faster sensor, the slower sensor
+/// is only read every Nth call, N being the ratio of the two ODRs. E.g. with the gyro at 32kHz and
+/// the accel at 1kHz, the accel data registers are read once every 32 calls. The sensitivity of
+/// both sensors is read once at construction, so each tick only reads data registers. The accel
+/// data feeds the motion detector and the tracked gyro bias is removed, as in
+/// [`Icm42688p::sample`].
+pub struct MixedRateReader {
+    accel_factor: f32,
+    gyro_factor: f32,
+    /// Whether the accelerometer is the faster sensor.
+    accel_is_fast: bool,
+    /// Number of ticks between two reads of the slower sensor.
+    slow_every: u32,
+    tick: u32,
+}
+
+impl MixedRateReader {
+    /// Plans the reads from the current ODRs and ranges of `imu`.
+    ///
+    /// The reader must be created again after changing the ODR or range of either sensor.
+    pub fn new<I>(imu: &mut Icm42688p<I>) -> Result<Self, Error>
+    where
+        I: Interface,
+    {
+        Self::plan(&mut imu.driver())
+    }
+
+    /// Reads the data due at this tick.
+    pub fn read<I>(&mut self, imu: &mut Icm42688p<I>) -> Result<MixedSample, Error>
+    where
+        I: Interface,
+    {
+        self.read_driver(&mut imu.driver())
+    }
+
+    /// Number of ticks between two reads of the slower sensor.
+    pub fn ratio(&self) -> u32 {
+        self.slow_every
+    }
+
+    fn plan(driver: &mut Driver) -> Result<Self, Error> {
+        let accel_hz = driver.accel_odr()?.hz();
+        let gyro_hz = driver.gyro_odr()?.hz();
+        let accel_is_fast = accel_hz > gyro_hz;
+        let ratio = if accel_is_fast {
+            accel_hz / gyro_hz
+        } else {
+            gyro_hz / accel_hz
+        };
+        Ok(Self {
+            accel_factor: driver.accel_sensitivity()?,
+            gyro_factor: driver.gyro_sensitivity()?,
+            accel_is_fast,
+            slow_every: (ratio + 0.5) as u32,
+            tick: 0,
+        })
+    }
+
+    fn read_driver(&mut self, driver: &mut Driver) -> Result<MixedSample, Error> {
+        let slow_due = self.tick == 0;
+        self.tick = (self.tick + 1) % self.slow_every;
+
+        let accel_due = self.accel_is_fast || slow_due;
+        let gyro_due = !self.accel_is_fast || slow_due;
+        let acceleration = if accel_due {
+            let (x, y, z) = driver.raw_acceleration()?;
+            let raw = (x.into(), y.into(), z.into());
+            Some(driver.scale_tracked_acceleration(raw, self.accel_factor))
+        } else {
+            None
+        };
+        let angular_velocity = if gyro_due {
+            let (x, y, z) = driver.raw_angular_velocity()?;
+            let raw = (x.into(), y.into(), z.into());
+            Some(driver.scale_corrected_angular_velocity(raw, self.gyro_factor))
+        } else {
+            None
+        };
+        Ok(MixedSample {
+            acceleration,
+            angular_velocity,
+        })
+    }
+}
+
+/// Iterator over new samples, created by [`Icm42688p::samples`].
+///
+/// Never ends, an item is [`Error::Timeout`] if no new sample arrived within two periods of the
+/// slower ODR, the next call waits again.
+pub struct Samples<'a, I, D> {
+    imu: &'a mut Icm42688p<I>,
+    delay: &'a mut D,
+    accel_factor: f32,
+    gyro_factor: f32,
+    timeout: MicrosDurationU32,
+}
+
+impl<'a, I, D> Samples<'a, I, D>
+where
+    I: Interface,
+    D: DelayNs,
+{
+    pub(crate) fn new(imu: &'a mut Icm42688p<I>, delay: &'a mut D) -> Result<Self, Error> {
+        let timeout = imu.data_ready_timeout()?;
+        let accel_factor = imu.driver().accel_sensitivity()?;
+        let gyro_factor = imu.driver().gyro_sensitivity()?;
+        Ok(Self {
+            imu,
+            delay,
+            accel_factor,
+            gyro_factor,
+            timeout,
+        })
+    }
+}
+
+impl<I, D> Iterator for Samples<'_, I, D>
+where
+    I: Interface,
+    D: DelayNs,
+{
+    type Item = Result<SensorData, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self
+            .imu
+            .wait_for_data_ready(self.delay, self.timeout)
+            .and_then(|()| {
+                self.imu
+                    .driver()
+                    .sample_scaled(self.accel_factor, self.gyro_factor)
+            });
+        Some(sample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: Sample = Sample {
+        acceleration: (1.0, -2.0, 0.5),
+        angular_velocity: (-250.0, 0.125, 1e-3),
+        resolution: Resolution::Bits20,
+        sequence: 0x0102_0304,
+    };
+
+    #[test]
+    fn sample_bytes_layout() {
+        let bytes = SAMPLE.to_bytes();
+        assert_eq!(bytes[0..4], 1.0f32.to_le_bytes());
+        assert_eq!(bytes[4..8], (-2.0f32).to_le_bytes());
+        assert_eq!(bytes[12..16], (-250.0f32).to_le_bytes());
+        assert_eq!(bytes[20..24], 1e-3f32.to_le_bytes());
+        assert_eq!(bytes[24], 1);
+        assert_eq!(bytes[25..], [0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(Sample::from_bytes(&bytes), Ok(SAMPLE));
+
+        let sample = Sample {
+            resolution: Resolution::Bits16,
+            ..SAMPLE
+        };
+        assert_eq!(sample.to_bytes()[24], 0);
+        assert_eq!(Sample::from_bytes(&sample.to_bytes()), Ok(sample));
+    }
+
+    #[test]
+    fn sample_bytes_invalid_resolution() {
+        let mut bytes = SAMPLE.to_bytes();
+        bytes[24] = 2;
+        assert_eq!(Sample::from_bytes(&bytes), Err(Error::DataCorrupted));
+    }
+
+    #[test]
+    fn crc16_check_value() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+        assert_eq!(crc16(&[]), 0xFFFF);
+    }
+
+    #[test]
+    fn sample_bytes_with_crc() {
+        let bytes = SAMPLE.to_bytes_with_crc();
+        assert_eq!(bytes[..Sample::ENCODED_SIZE], SAMPLE.to_bytes());
+        assert_eq!(
+            bytes[Sample::ENCODED_SIZE..],
+            crc16(&SAMPLE.to_bytes()).to_le_bytes()
+        );
+        assert_eq!(Sample::from_bytes_with_crc(&bytes), Ok(SAMPLE));
+
+        for i in [0, 24, Sample::ENCODED_SIZE] {
+            let mut corrupted = bytes;
+            corrupted[i] ^= 0x01;
+            assert_eq!(
+                Sample::from_bytes_with_crc(&corrupted),
+                Err(Error::DataCorrupted)
+            );
+        }
+    }
+
+    #[cfg(feature = "motion")]
+    #[test]
+    fn mixed_rate_reader_removes_gyro_bias() {
+        let mut imu = crate::tests::imu();
+        imu.interface = crate::driver::tests::turning_at_10_dps();
+        imu.state.gyro_bias.enabled = true;
+        let mut reader = MixedRateReader::new(&mut imu).unwrap();
+
+        let mut gyro_x = || reader.read(&mut imu).unwrap().angular_velocity.unwrap().0;
+        assert!((gyro_x() - 10.0).abs() < 1e-3);
+        for _ in 1..crate::motion::MOTION_WINDOW {
+            gyro_x();
+        }
+        assert!(gyro_x().abs() < 1e-3);
+    }
+}
@@ -0,0 +1,436 @@
+//! Several sensors mounted on one board for redundancy.
+
+use core::{
+    cell::RefCell,
+    ops::{Deref, DerefMut},
+};
+
+use embedded_hal::{
+    delay::DelayNs,
+    spi::{ErrorType, SpiBus},
+};
+
+use crate::{
+    config::{Config, ConfigReport},
+    error::Error,
+    reader::SensorData,
+    Icm42688p, Interface,
+};
+
+/// SPI bus shared by sensors with distinct chip select pins.
+///
+/// Each transfer borrows the bus for its duration only, so several [`crate::SpiInterface`]s can
+/// be built on clones of the same `SharedSpiBus`. The sensors must be used from a single
+/// context, a transfer while another one is in progress panics.
+pub struct SharedSpiBus<'a, SPI> {
+    bus: &'a RefCell<SPI>,
+}
+
+impl<'a, SPI> SharedSpiBus<'a, SPI> {
+    pub fn new(bus: &'a RefCell<SPI>) -> Self {
+        Self { bus }
+    }
+}
+
+impl<SPI> Clone for SharedSpiBus<'_, SPI> {
+    fn clone(&self) -> Self {
+        Self { bus: self.bus }
+    }
+}
+
+impl<SPI> ErrorType for SharedSpiBus<'_, SPI>
+where
+    SPI: ErrorType,
+{
+    type Error = SPI::Error;
+}
+
+impl<SPI> SpiBus<u8> for SharedSpiBus<'_, SPI>
+where
+    SPI: SpiBus<u8>,
+{
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().read(words)
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().write(words)
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().transfer(read, write)
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().transfer_in_place(words)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().flush()
+    }
+}
+
+/// Error of one sensor of a [`RedundantImus`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InstanceError {
+    /// Index of the sensor in the array.
+    pub index: usize,
+    pub error: Error,
+}
+
+/// Largest difference between two sensors still considered in agreement, in the configured
+/// units.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Tolerance {
+    pub acceleration: f32,
+    pub angular_velocity: f32,
+}
+
+/// Samples of all sensors read by [`RedundantImus::cross_check`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CrossCheck<const N: usize> {
+    pub samples: [SensorData; N],
+    pub tolerance: Tolerance,
+}
+
+impl<const N: usize> CrossCheck<N> {
+    /// Whether the samples of sensors `a` and `b` differ by at most the tolerance on every axis.
+    pub fn agrees(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (&self.samples[a], &self.samples[b]);
+        max_difference(a.accel, b.accel) <= self.tolerance.acceleration
+            && max_difference(a.gyro, b.gyro) <= self.tolerance.angular_velocity
+    }
+
+    /// Whether all sensors agree with each other.
+    pub fn is_consistent(&self) -> bool {
+        self.is_consistent_among(&[true; N])
+    }
+
+    /// Sensor disagreeing with all the others while those agree with each other, i.e. the
+    /// faulty one by majority vote.
+    ///
+    /// Always `None` with fewer than three sensors: two disagreeing sensors tell a fault apart
+    /// but not which one.
+    pub fn outlier(&self) -> Option<usize> {
+        self.outlier_among(&[true; N])
+    }
+
+    fn is_consistent_among(&self, active: &[bool; N]) -> bool {
+        let sensors = || (0..N).filter(|&i| active[i]);
+        sensors().all(|a| sensors().filter(|&b| b > a).all(|b| self.agrees(a, b)))
+    }
+
+    fn outlier_among(&self, active: &[bool; N]) -> Option<usize> {
+        if active.iter().filter(|&&active| active).count() < 3 {
+            return None;
+        }
+        (0..N).filter(|&i| active[i]).find(|&suspect| {
+            let others = || (0..N).filter(move |&i| active[i] && i != suspect);
+            others().all(|i| !self.agrees(suspect, i))
+                && others().all(|a| others().all(|b| self.agrees(a, b)))
+        })
+    }
+}
+
+/// Output of a [`Voter`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Vote {
+    /// In the configured acceleration unit.
+    pub accel: (f32, f32, f32),
+    /// In the configured angular velocity unit.
+    pub gyro: (f32, f32, f32),
+    /// Some of the voting sensors diverge beyond the tolerance and no majority singles out the
+    /// faulty one, the output is the per-axis median, or the mean of two sensors.
+    pub divergent: bool,
+    /// Sensor left out of this vote as the [`CrossCheck::outlier`].
+    pub excluded: Option<usize>,
+}
+
+/// Selects the output of redundant sensors, the usual voting of redundant flight control sensor
+/// suites.
+///
+/// The output is the mean of the sensors agreeing within the tolerance. With three sensors or
+/// more, one disagreeing with all the others is left out, and after `fail_after` consecutive
+/// votes doing so it is marked failed and ignored until [`Self::reset`].
+pub struct Voter<const N: usize> {
+    tolerance: Tolerance,
+    fail_after: u8,
+    strikes: [u8; N],
+    failed: [bool; N],
+}
+
+impl<const N: usize> Voter<N> {
+    pub fn new(tolerance: Tolerance, fail_after: u8) -> Self {
+        Self {
+            tolerance,
+            fail_after,
+            strikes: [0; N],
+            failed: [false; N],
+        }
+    }
+
+    pub fn tolerance(&self) -> Tolerance {
+        self.tolerance
+    }
+
+    /// Whether sensor `index` was marked failed and no longer votes.
+    pub fn is_failed(&self, index: usize) -> bool {
+        self.failed[index]
+    }
+
+    /// Lets all sensors vote again.
+    pub fn reset(&mut self) {
+        self.strikes = [0; N];
+        self.failed = [false; N];
+    }
+
+    /// Votes on the samples of `check`, its tolerance is ignored in favor of the voter's.
+    pub fn vote(&mut self, check: &CrossCheck<N>) -> Vote {
+        let check = CrossCheck {
+            tolerance: self.tolerance,
+            ..*check
+        };
+        // With every sensor failed, voting with all of them beats having no output.
+        let mut active = self.failed.map(|failed| !failed);
+        if !active.contains(&true) {
+            active = [true; N];
+        }
+
+        let excluded = check.outlier_among(&active);
+        for (index, strikes) in self.strikes.iter_mut().enumerate() {
+            if excluded == Some(index) {
+                *strikes = strikes.saturating_add(1);
+                if *strikes >= self.fail_after {
+                    self.failed[index] = true;
+                }
+            } else if active[index] {
+                *strikes = 0;
+            }
+        }
+        if let Some(index) = excluded {
+            active[index] = false;
+        }
+
+        let divergent = !check.is_consistent_among(&active);
+        let used = active.iter().filter(|&&active| active).count();
+        let combine = |axis: fn(&SensorData) -> f32| {
+            let mut values = [0.0; N];
+            let voting = (0..N)
+                .filter(|&i| active[i])
+                .map(|i| axis(&check.samples[i]));
+            for (value, sample) in values.iter_mut().zip(voting) {
+                *value = sample;
+            }
+            let values = &mut values[..used];
+            if divergent && used >= 3 {
+                median(values)
+            } else {
+                values.iter().sum::<f32>() / used as f32
+            }
+        };
+        Vote {
+            accel: (
+                combine(|s| s.accel.0),
+                combine(|s| s.accel.1),
+                combine(|s| s.accel.2),
+            ),
+            gyro: (
+                combine(|s| s.gyro.0),
+                combine(|s| s.gyro.1),
+                combine(|s| s.gyro.2),
+            ),
+            divergent,
+            excluded,
+        }
+    }
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    for i in 1..values.len() {
+        let mut j = i;
+        while j > 0 && values[j - 1] > values[j] {
+            values.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+fn max_difference(a: (f32, f32, f32), b: (f32, f32, f32)) -> f32 {
+    let diff = |a: f32, b: f32| if a > b { a - b } else { b - a };
+    let (x, y, z) = (diff(a.0, b.0), diff(a.1, b.1), diff(a.2, b.2));
+    x.max(y).max(z)
+}
+
+/// Sensors mounted on one board for redundancy, e.g. two chips on the same SPI bus with distinct
+/// chip select pins, see [`SharedSpiBus`].
+///
+/// Dereferences to the array of drivers for per-sensor access.
+pub struct RedundantImus<I, const N: usize> {
+    imus: [Icm42688p<I>; N],
+}
+
+impl<I, const N: usize> RedundantImus<I, N>
+where
+    I: Interface,
+{
+    pub fn new(imus: [Icm42688p<I>; N]) -> Self {
+        Self { imus }
+    }
+
+    pub fn release(self) -> [Icm42688p<I>; N] {
+        self.imus
+    }
+
+    /// Applies `config` to every sensor, see [`Icm42688p::apply_config`].
+    ///
+    /// Stops at the first sensor failing, the following ones keep their previous configuration.
+    pub fn configure_all(
+        &mut self,
+        config: &Config,
+        delay: &mut impl DelayNs,
+    ) -> Result<[ConfigReport; N], InstanceError> {
+        let mut reports = [None; N];
+        for (index, (imu, report)) in self.imus.iter_mut().zip(&mut reports).enumerate() {
+            *report = Some(
+                imu.apply_config(config, delay)
+                    .map_err(|error| InstanceError { index, error })?,
+            );
+        }
+        Ok(reports.map(|report| report.unwrap()))
+    }
+
+    /// Reads a sample from every sensor, see [`Icm42688p::sample`], to compare their outputs.
+    ///
+    /// The sensors are read one after the other, with ODRs above the bus time of a read the
+    /// samples may come from consecutive ODR periods, which the tolerance must allow for.
+    pub fn cross_check(&mut self, tolerance: Tolerance) -> Result<CrossCheck<N>, InstanceError> {
+        let mut samples = [None; N];
+        for (index, (imu, sample)) in self.imus.iter_mut().zip(&mut samples).enumerate() {
+            *sample = Some(
+                imu.sample()
+                    .map_err(|error| InstanceError { index, error })?,
+            );
+        }
+        Ok(CrossCheck {
+            samples: samples.map(|sample| sample.unwrap()),
+            tolerance,
+        })
+    }
+
+    /// Reads a sample from every sensor and votes on them, see [`Voter::vote`].
+    pub fn vote(&mut self, voter: &mut Voter<N>) -> Result<Vote, InstanceError> {
+        let check = self.cross_check(voter.tolerance())?;
+        Ok(voter.vote(&check))
+    }
+}
+
+impl<I, const N: usize> Deref for RedundantImus<I, N> {
+    type Target = [Icm42688p<I>; N];
+
+    fn deref(&self) -> &Self::Target {
+        &self.imus
+    }
+}
+
+impl<I, const N: usize> DerefMut for RedundantImus<I, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.imus
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: Tolerance = Tolerance {
+        acceleration: 0.1,
+        angular_velocity: 1.0,
+    };
+
+    /// Samples with `accel` on every accel axis and `gyro` on every gyro axis.
+    fn check<const N: usize>(values: [(f32, f32); N]) -> CrossCheck<N> {
+        CrossCheck {
+            samples: values.map(|(accel, gyro)| SensorData {
+                accel: (accel, accel, accel),
+                gyro: (gyro, gyro, gyro),
+                temperature: 25.0,
+                timestamp: 0,
+            }),
+            tolerance: TOLERANCE,
+        }
+    }
+
+    #[test]
+    fn vote_averages_agreeing_sensors() {
+        let mut voter = Voter::new(TOLERANCE, 3);
+        let vote = voter.vote(&check([(1.0, 2.0), (1.05, 2.5), (0.95, 1.5)]));
+        assert!((vote.accel.0 - 1.0).abs() < 1e-6);
+        assert!((vote.gyro.2 - 2.0).abs() < 1e-6);
+        assert!(!vote.divergent);
+        assert_eq!(vote.excluded, None);
+    }
+
+    #[test]
+    fn vote_excludes_then_fails_outlier() {
+        let mut voter = Voter::new(TOLERANCE, 2);
+        let check = check([(1.0, 2.0), (5.0, 2.0), (1.0, 2.0)]);
+
+        let vote = voter.vote(&check);
+        assert_eq!(vote.excluded, Some(1));
+        assert!(!vote.divergent);
+        assert_eq!(vote.accel, (1.0, 1.0, 1.0));
+        assert!(!voter.is_failed(1));
+
+        voter.vote(&check);
+        assert!(voter.is_failed(1));
+        // A failed sensor no longer votes, so it is not excluded again.
+        let vote = voter.vote(&check);
+        assert_eq!(vote.excluded, None);
+        assert_eq!(vote.accel, (1.0, 1.0, 1.0));
+
+        voter.reset();
+        assert!(!voter.is_failed(1));
+    }
+
+    #[test]
+    fn vote_outlier_strikes_reset_on_agreement() {
+        let mut voter = Voter::new(TOLERANCE, 2);
+        voter.vote(&check([(1.0, 2.0), (5.0, 2.0), (1.0, 2.0)]));
+        voter.vote(&check([(1.0, 2.0); 3]));
+        voter.vote(&check([(1.0, 2.0), (5.0, 2.0), (1.0, 2.0)]));
+        assert!(!voter.is_failed(1));
+    }
+
+    #[test]
+    fn vote_divergent_sensors() {
+        // Two sensors tell a fault apart but not which one, the output is their mean.
+        let mut voter = Voter::new(TOLERANCE, 1);
+        let vote = voter.vote(&check([(1.0, 0.0), (2.0, 0.0)]));
+        assert!(vote.divergent);
+        assert_eq!(vote.excluded, None);
+        assert_eq!(vote.accel, (1.5, 1.5, 1.5));
+
+        // Three sensors without a majority, the output is the median.
+        let mut voter = Voter::new(TOLERANCE, 1);
+        let vote = voter.vote(&check([(3.0, 0.0), (1.0, 0.0), (2.0, 0.0)]));
+        assert!(vote.divergent);
+        assert_eq!(vote.excluded, None);
+        assert_eq!(vote.accel, (2.0, 2.0, 2.0));
+        assert!(!voter.is_failed(0));
+    }
+
+    #[test]
+    fn vote_with_every_sensor_failed() {
+        let mut voter = Voter::new(TOLERANCE, 1);
+        voter.failed = [true; 3];
+        let vote = voter.vote(&check([(1.0, 0.0), (1.0, 0.0), (1.0, 0.0)]));
+        assert_eq!(vote.accel, (1.0, 1.0, 1.0));
+        assert!(!vote.divergent);
+    }
+}
@@ -1,12 +1,21 @@
+use crate::{
+    config::{
+        AccelLpClock, AccelODR, AccelRange, ClockSource, DisabledInterface, Endian, GyroODR,
+        GyroRange, InterruptPulse, LowLatencyFilter, PowerMode, TimestampResolution,
+    },
+    error::Error,
+    interface::{Bank, RegAddr},
+};
+
 pub trait Register {
-    fn address(&self) -> u8;
+    fn address(&self) -> RegAddr;
     fn bank_selection(&self) -> BankSelection;
     fn readable(&self) -> bool;
     fn writable(&self) -> bool;
 }
 
 // Register bank selection
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum BankSelection {
     Bank0 = 0,
     Bank1 = 1,
@@ -15,6 +24,12 @@ pub enum BankSelection {
     Bank4 = 4,
 }
 
+impl From<BankSelection> for Bank {
+    fn from(bank: BankSelection) -> Self {
+        Bank(bank as u8)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum Bank0 {
     DeviceConfig = 0x11,
@@ -45,6 +60,11 @@ pub enum Bank0 {
     FifoCountL = 0x2F,
     FifoData = 0x30,
 
+    ApexData4 = 0x35,
+    ApexData5 = 0x36,
+    IntStatus2 = 0x37,
+    IntStatus3 = 0x38,
+
     SignalPathReset = 0x4B,
     IntfConfig0 = 0x4C,
     IntfConfig1 = 0x4D,
@@ -56,6 +76,9 @@ pub enum Bank0 {
     AccelConfig1 = 0x53,
     TmstConfig = 0x54,
 
+    ApexConfig0 = 0x56,
+    SmdConfig = 0x57,
+
     FifoConfig1 = 0x5F,
     FifoConfig2 = 0x60,
     FifoConfig3 = 0x61,
@@ -64,6 +87,13 @@ pub enum Bank0 {
     IntConfig1 = 0x64,
 
     IntSource0 = 0x65,
+    IntSource1 = 0x66,
+
+    IntSource3 = 0x68,
+    IntSource4 = 0x69,
+
+    FifoLostPkt0 = 0x6C,
+    FifoLostPkt1 = 0x6D,
 
     SelfTestConfig = 0x70,
 
@@ -72,8 +102,8 @@ pub enum Bank0 {
 }
 
 impl Register for Bank0 {
-    fn address(&self) -> u8 {
-        *self as u8
+    fn address(&self) -> RegAddr {
+        RegAddr(*self as u8)
     }
 
     fn bank_selection(&self) -> BankSelection {
@@ -81,57 +111,142 @@ impl Register for Bank0 {
     }
 
     fn readable(&self) -> bool {
-        matches!(
-            self,
-            Bank0::DeviceConfig
-                | Bank0::FifoConfig
-                | Bank0::TempData1
-                | Bank0::TempData0
-                | Bank0::AccelDataX1
-                | Bank0::AccelDataX0
-                | Bank0::AccelDataY1
-                | Bank0::AccelDataY0
-                | Bank0::AccelDataZ1
-                | Bank0::AccelDataZ0
-                | Bank0::GyroDataX1
-                | Bank0::GyroDataX0
-                | Bank0::GyroDataY1
-                | Bank0::GyroDataY0
-                | Bank0::GyroDataZ1
-                | Bank0::GyroDataZ0
-                | Bank0::FifoCountH
-                | Bank0::FifoCountL
-        )
+        !matches!(self, Bank0::SignalPathReset)
     }
 
     fn writable(&self) -> bool {
         matches!(
             self,
             Bank0::DeviceConfig
+                | Bank0::IntConfig
                 | Bank0::FifoConfig
+                | Bank0::SignalPathReset
+                | Bank0::IntfConfig0
+                | Bank0::IntfConfig1
                 | Bank0::PwrMgmt0
                 | Bank0::GyroConfig0
                 | Bank0::AccelConfig0
                 | Bank0::GyroConfig1
                 | Bank0::GyroAccelConfig0
                 | Bank0::AccelConfig1
+                | Bank0::TmstConfig
+                | Bank0::ApexConfig0
+                | Bank0::SmdConfig
+                | Bank0::FifoConfig1
+                | Bank0::FifoConfig2
+                | Bank0::FifoConfig3
+                | Bank0::IntConfig0
+                | Bank0::IntConfig1
+                | Bank0::IntSource0
+                | Bank0::IntSource1
+                | Bank0::IntSource3
+                | Bank0::IntSource4
+                | Bank0::SelfTestConfig
+                | Bank0::RegBankSel
         )
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum Bank1 {
     GyroConfigStatic2 = 0x0B,
     GyroConfigStatic3 = 0x0C,
     GyroConfigStatic4 = 0x0D,
     GyroConfigStatic5 = 0x0E,
 
+    XgStData = 0x5F,
+    YgStData = 0x60,
+    ZgStData = 0x61,
+    TmstVal0 = 0x62,
+    TmstVal1 = 0x63,
+    TmstVal2 = 0x64,
+
+    IntfConfig4 = 0x7A,
     IntfConfig5 = 0x7B,
+    IntfConfig6 = 0x7C,
+}
+
+impl Register for Bank1 {
+    fn address(&self) -> RegAddr {
+        RegAddr(*self as u8)
+    }
+
+    fn bank_selection(&self) -> BankSelection {
+        BankSelection::Bank1
+    }
+
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
 }
 
+#[derive(Clone, Copy)]
 pub enum Bank2 {
     AccelConfigStatic2 = 0x03,
     AccelConfigStatic3 = 0x04,
     AccelConfigStatic4 = 0x05,
+
+    XaStData = 0x3B,
+    YaStData = 0x3C,
+    ZaStData = 0x3D,
+}
+
+impl Register for Bank2 {
+    fn address(&self) -> RegAddr {
+        RegAddr(*self as u8)
+    }
+
+    fn bank_selection(&self) -> BankSelection {
+        BankSelection::Bank2
+    }
+
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Bank4 {
+    ApexConfig7 = 0x46,
+    ApexConfig8 = 0x47,
+
+    AccelWomXThr = 0x4A,
+    AccelWomYThr = 0x4B,
+    AccelWomZThr = 0x4C,
+    IntSource6 = 0x4D,
+    IntSource7 = 0x4E,
+
+    OffsetUser4 = 0x7B,
+    OffsetUser5 = 0x7C,
+    OffsetUser6 = 0x7D,
+    OffsetUser7 = 0x7E,
+    OffsetUser8 = 0x7F,
+}
+
+impl Register for Bank4 {
+    fn address(&self) -> RegAddr {
+        RegAddr(*self as u8)
+    }
+
+    fn bank_selection(&self) -> BankSelection {
+        BankSelection::Bank4
+    }
+
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
 }
 
 pub struct BitRange {
@@ -140,14 +255,31 @@ pub struct BitRange {
 }
 
 impl BitRange {
-    pub fn mask(&self) -> u8 {
+    pub const fn mask(&self) -> u8 {
         debug_assert!(self.length >= 1);
         let mut mask: u8 = 0;
-        for i in self.offset..self.offset + self.length {
-            mask &= 1 << i;
+        let mut i = self.offset;
+        while i < self.offset + self.length {
+            mask |= 1 << i;
+            i += 1;
         }
         mask
     }
+
+    /// Extracts the field from a register value.
+    pub const fn get(&self, value: u8) -> u8 {
+        (value & self.mask()) >> self.offset
+    }
+
+    /// Whether `field` fits in the field width.
+    pub const fn fits(&self, field: u8) -> bool {
+        field <= self.mask() >> self.offset
+    }
+
+    /// Places `field` in the field position, bits not fitting the field are dropped.
+    pub const fn put(&self, field: u8) -> u8 {
+        (field << self.offset) & self.mask()
+    }
 }
 
 #[allow(non_camel_case_types)]
@@ -166,6 +298,33 @@ impl ACCEL_CONFIG0 {
     };
 }
 
+#[allow(non_camel_case_types)]
+pub struct OFFSET_USER4;
+
+impl OFFSET_USER4 {
+    /// Upper bits of the X accel offset.
+    pub const ACCEL_X_OFFUSER_HI: BitRange = BitRange {
+        offset: 4,
+        length: 4,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct OFFSET_USER7;
+
+impl OFFSET_USER7 {
+    /// Upper bits of the Z accel offset.
+    pub const ACCEL_Z_OFFUSER_HI: BitRange = BitRange {
+        offset: 4,
+        length: 4,
+    };
+    /// Upper bits of the Y accel offset.
+    pub const ACCEL_Y_OFFUSER_HI: BitRange = BitRange {
+        offset: 0,
+        length: 4,
+    };
+}
+
 #[allow(non_camel_case_types)]
 pub struct GYRO_CONFIG0;
 
@@ -183,25 +342,275 @@ impl GYRO_CONFIG0 {
 }
 
 #[allow(non_camel_case_types)]
-pub struct FIFO_CONFIG0;
+pub struct INTF_CONFIG0;
 
-impl GYRO_CONFIG0 {
+impl INTF_CONFIG0 {
+    /// Invalid samples in the FIFO, 0: replaced by the invalid code, 1: replaced by the last
+    /// valid sample.
+    pub const FIFO_HOLD_LAST_DATA_EN: BitRange = BitRange {
+        offset: 7,
+        length: 1,
+    };
+    /// FIFO count unit, 0: bytes, 1: records.
+    pub const FIFO_COUNT_REC: BitRange = BitRange {
+        offset: 6,
+        length: 1,
+    };
+    /// FIFO count byte order, 0: little endian, 1: big endian.
+    pub const FIFO_COUNT_ENDIAN: BitRange = BitRange {
+        offset: 5,
+        length: 1,
+    };
+    /// Sensor data byte order, 0: little endian, 1: big endian.
+    pub const SENSOR_DATA_ENDIAN: BitRange = BitRange {
+        offset: 4,
+        length: 1,
+    };
+    /// Host interface to disable, 0b10: SPI, 0b11: I2C.
+    pub const UI_SIFS_CFG: BitRange = BitRange {
+        offset: 0,
+        length: 2,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct SELF_TEST_CONFIG;
+
+impl SELF_TEST_CONFIG {
+    /// Powers the accel self-test, must be cleared once the self-test is completed.
+    pub const ACCEL_ST_POWER: BitRange = BitRange {
+        offset: 6,
+        length: 1,
+    };
+    /// Enables the X, Y and Z accel self-test.
+    pub const EN_ACCEL_ST: BitRange = BitRange {
+        offset: 3,
+        length: 3,
+    };
+    /// Enables the X, Y and Z gyro self-test.
+    pub const EN_GYRO_ST: BitRange = BitRange {
+        offset: 0,
+        length: 3,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INTF_CONFIG1;
+
+impl INTF_CONFIG1 {
+    /// Clock of the accelerometer in low power mode, 0: wake-up oscillator, 1: RC oscillator.
+    pub const ACCEL_LP_CLK_SEL: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+    /// Requires an RTC clock input.
+    pub const RTC_MODE: BitRange = BitRange {
+        offset: 2,
+        length: 1,
+    };
+    /// Clock source.
+    pub const CLKSEL: BitRange = BitRange {
+        offset: 0,
+        length: 2,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INTF_CONFIG4;
+
+impl INTF_CONFIG4 {
+    /// SPI mode, 0: 3-wire, 1: 4-wire.
+    pub const SPI_AP_4WIRE: BitRange = BitRange {
+        offset: 1,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INTF_CONFIG5;
+
+impl INTF_CONFIG5 {
+    /// Function of pin 9, 0: INT2, 1: FSYNC, 2: CLKIN.
+    pub const PIN9_FUNCTION: BitRange = BitRange {
+        offset: 1,
+        length: 2,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct PWR_MGMT0;
+
+impl PWR_MGMT0 {
+    /// Disables the temperature sensor.
+    pub const TEMP_DIS: BitRange = BitRange {
+        offset: 5,
+        length: 1,
+    };
+    /// Keeps the RC oscillator on with accel and gyro off.
+    pub const IDLE: BitRange = BitRange {
+        offset: 4,
+        length: 1,
+    };
+    /// Gyroscope mode.
+    pub const GYRO_MODE: BitRange = BitRange {
+        offset: 2,
+        length: 2,
+    };
+    /// Accelerometer mode.
+    pub const ACCEL_MODE: BitRange = BitRange {
+        offset: 0,
+        length: 2,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct GYRO_ACCEL_CONFIG0;
+
+impl GYRO_ACCEL_CONFIG0 {
+    /// Bandwidth for accel LPF.
+    pub const ACCEL_UI_FILT_BW: BitRange = BitRange {
+        offset: 4,
+        length: 4,
+    };
+    /// Bandwidth for gyro LPF.
+    pub const GYRO_UI_FILT_BW: BitRange = BitRange {
+        offset: 0,
+        length: 4,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct GYRO_CONFIG1;
+
+impl GYRO_CONFIG1 {
+    /// Selects order of gyro UI filter.
+    pub const GYRO_UI_FILT_ORD: BitRange = BitRange {
+        offset: 2,
+        length: 2,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct GYRO_CONFIG_STATIC2;
+
+impl GYRO_CONFIG_STATIC2 {
+    /// Disables the gyro anti-alias filter.
+    pub const GYRO_AAF_DIS: BitRange = BitRange {
+        offset: 1,
+        length: 1,
+    };
+    /// Disables the gyro notch filter.
+    pub const GYRO_NF_DIS: BitRange = BitRange {
+        offset: 0,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct GYRO_CONFIG_STATIC3;
+
+impl GYRO_CONFIG_STATIC3 {
+    /// Controls bandwidth of the gyro anti-alias filter.
+    pub const GYRO_AAF_DELT: BitRange = BitRange {
+        offset: 0,
+        length: 6,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct ACCEL_CONFIG_STATIC2;
+
+impl ACCEL_CONFIG_STATIC2 {
+    /// Controls bandwidth of the accel anti-alias filter.
+    pub const ACCEL_AAF_DELT: BitRange = BitRange {
+        offset: 1,
+        length: 6,
+    };
+    /// Disables the accel anti-alias filter.
+    pub const ACCEL_AAF_DIS: BitRange = BitRange {
+        offset: 0,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INTF_CONFIG6;
+
+impl INTF_CONFIG6 {
+    /// Enables the I3C slave.
+    pub const I3C_EN: BitRange = BitRange {
+        offset: 4,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct ACCEL_CONFIG1;
+
+impl ACCEL_CONFIG1 {
+    /// Selects order of accel UI filter.
+    pub const ACCEL_UI_FILT_ORD: BitRange = BitRange {
+        offset: 3,
+        length: 2,
+    };
+    /// Order of accelerometer DEC2_M2 filter.
+    pub const ACCEL_DEC2_M2_ORD: BitRange = BitRange {
+        offset: 1,
+        length: 2,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct FIFO_CONFIG;
+
+impl FIFO_CONFIG {
+    /// 0b00: bypass, 0b01: stream-to-FIFO, 0b10 and 0b11: stop-on-full.
+    pub const FIFO_MODE: BitRange = BitRange {
+        offset: 6,
+        length: 2,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct FIFO_CONFIG1;
+
+impl FIFO_CONFIG1 {
     /// Enable accelerometer packets to go to FIFO.
-    pub const FIFO_ACCEL_EN: u8 = 0;
+    pub const FIFO_ACCEL_EN: BitRange = BitRange {
+        offset: 0,
+        length: 1,
+    };
     /// Enable gyroscope packets to go to FIFO.
-    pub const FIFO_GYRO_EN: u8 = 1;
+    pub const FIFO_GYRO_EN: BitRange = BitRange {
+        offset: 1,
+        length: 1,
+    };
     /// Enable temperature sensor packets to go to FIFO.
-    pub const FIFO_TEMP_EN: u8 = 2;
-
+    pub const FIFO_TEMP_EN: BitRange = BitRange {
+        offset: 2,
+        length: 1,
+    };
     /// Must be set to 1 for all FIFO use cases when FSYNC is used.
-    pub const FIFO_TMST_FSYNC_EN: u8 = 3;
+    pub const FIFO_TMST_FSYNC_EN: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
     /// Enable 3 bytes of extended 20-bits accel, gyro data + 1 byte of extended 16-bit temperature sensor data to be placed into the FIFO.
-    pub const FIFO_HIRES_EN: u8 = 4;
+    pub const FIFO_HIRES_EN: BitRange = BitRange {
+        offset: 4,
+        length: 1,
+    };
     /// Trigger FIFO watermark interrupt on every ODR (DMA write) if FIFO_COUNT ≥ FIFO_WM_TH.
-    pub const FIFO_WM_GT_TH: u8 = 5;
+    pub const FIFO_WM_GT_TH: BitRange = BitRange {
+        offset: 5,
+        length: 1,
+    };
     /// 0: Partial FIFO read disabled, requires re-reading of the entire FIFO.
     /// 1: FIFO read can be partial, and resume from last read point.
-    pub const FIFO_RESUME_PARTIAL_RD: u8 = 6;
+    pub const FIFO_RESUME_PARTIAL_RD: BitRange = BitRange {
+        offset: 6,
+        length: 1,
+    };
 }
 
 #[allow(non_camel_case_types)]
@@ -219,3 +628,826 @@ impl SIGNAL_PATH_RESET {
     /// When set to 1, FIFO will get flushed.
     pub const FIFO_FLUSH: u8 = 1 << 1;
 }
+
+#[allow(non_camel_case_types)]
+pub struct INT_CONFIG;
+
+impl INT_CONFIG {
+    /// INT2 interrupt mode, 0: pulsed, 1: latched.
+    pub const INT2_MODE: BitRange = BitRange {
+        offset: 5,
+        length: 1,
+    };
+    /// INT2 drive circuit, 0: open drain, 1: push pull.
+    pub const INT2_DRIVE_CIRCUIT: BitRange = BitRange {
+        offset: 4,
+        length: 1,
+    };
+    /// INT2 interrupt polarity, 0: active low, 1: active high.
+    pub const INT2_POLARITY: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+    /// INT1 interrupt mode, 0: pulsed, 1: latched.
+    pub const INT1_MODE: BitRange = BitRange {
+        offset: 2,
+        length: 1,
+    };
+    /// INT1 drive circuit, 0: open drain, 1: push pull.
+    pub const INT1_DRIVE_CIRCUIT: BitRange = BitRange {
+        offset: 1,
+        length: 1,
+    };
+    /// INT1 interrupt polarity, 0: active low, 1: active high.
+    pub const INT1_POLARITY: BitRange = BitRange {
+        offset: 0,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INT_CONFIG1;
+
+impl INT_CONFIG1 {
+    /// Interrupt pulse duration, 0: 100µs, 1: 8µs.
+    pub const INT_TPULSE_DURATION: BitRange = BitRange {
+        offset: 6,
+        length: 1,
+    };
+    /// Interrupt de-assertion duration, 0: at least 100µs, 1: disabled.
+    pub const INT_TDEASSERT_DISABLE: BitRange = BitRange {
+        offset: 5,
+        length: 1,
+    };
+    /// Should be changed to 0 from the default of 1 for proper INT1 and INT2 pin operation.
+    pub const INT_ASYNC_RESET: BitRange = BitRange {
+        offset: 4,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INT_STATUS;
+
+impl INT_STATUS {
+    /// Data ready interrupt, clears on read.
+    pub const DATA_RDY_INT: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+    /// FIFO threshold interrupt, clears on read.
+    pub const FIFO_THS_INT: BitRange = BitRange {
+        offset: 2,
+        length: 1,
+    };
+    /// FIFO full interrupt, clears on read.
+    pub const FIFO_FULL_INT: BitRange = BitRange {
+        offset: 1,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INT_STATUS2;
+
+impl INT_STATUS2 {
+    /// Significant motion detection interrupt, clears on read.
+    pub const SMD_INT: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+    /// Wake on motion interrupt of the X, Y and Z axes, clears on read.
+    pub const WOM_INT: BitRange = BitRange {
+        offset: 0,
+        length: 3,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INT_STATUS3;
+
+impl INT_STATUS3 {
+    /// Tap detection interrupt, clears on read.
+    pub const TAP_DET_INT: BitRange = BitRange {
+        offset: 0,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct APEX_CONFIG0;
+
+impl APEX_CONFIG0 {
+    /// Tap detection enable.
+    pub const TAP_ENABLE: BitRange = BitRange {
+        offset: 6,
+        length: 1,
+    };
+    /// Pedometer enable.
+    pub const PED_ENABLE: BitRange = BitRange {
+        offset: 5,
+        length: 1,
+    };
+    /// Tilt detection enable.
+    pub const TILT_ENABLE: BitRange = BitRange {
+        offset: 4,
+        length: 1,
+    };
+    /// Raise to wake/sleep enable.
+    pub const R2W_EN: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct SMD_CONFIG;
+
+impl SMD_CONFIG {
+    /// 0: WOM interrupt on the OR of the axis thresholds, 1: on the AND.
+    pub const WOM_INT_MODE: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+    /// 0: compare to the initial sample, 1: compare to the previous sample.
+    pub const WOM_MODE: BitRange = BitRange {
+        offset: 2,
+        length: 1,
+    };
+    /// 0: SMD disabled, 1: WOM only, 2: SMD short, 3: SMD long.
+    pub const SMD_MODE: BitRange = BitRange {
+        offset: 0,
+        length: 2,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct FIFO_CONFIG3;
+
+impl FIFO_CONFIG3 {
+    /// FIFO_WM[11:8], FIFO_WM[7:0] being FIFO_CONFIG2.
+    pub const FIFO_WM: BitRange = BitRange {
+        offset: 0,
+        length: 4,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INT_SOURCE0;
+
+impl INT_SOURCE0 {
+    /// UI data ready interrupt routed to INT1 (INT2 for INT_SOURCE3).
+    pub const UI_DRDY_INT1_EN: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+    /// FIFO threshold interrupt routed to INT1 (INT2 for INT_SOURCE3).
+    pub const FIFO_THS_INT1_EN: BitRange = BitRange {
+        offset: 2,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INT_SOURCE1;
+
+impl INT_SOURCE1 {
+    /// SMD interrupt routed to INT1 (INT2 for INT_SOURCE4).
+    pub const SMD_INT1_EN: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+    /// WOM interrupts of the Z, Y and X axes routed to INT1 (INT2 for INT_SOURCE4).
+    pub const WOM_INT1_EN: BitRange = BitRange {
+        offset: 0,
+        length: 3,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct APEX_DATA4;
+
+impl APEX_DATA4 {
+    /// Number of taps in the current tap event.
+    pub const TAP_NUM: BitRange = BitRange {
+        offset: 3,
+        length: 2,
+    };
+    /// Accelerometer axis on which tap energy is concentrated.
+    pub const TAP_AXIS: BitRange = BitRange {
+        offset: 1,
+        length: 2,
+    };
+    /// Polarity of tap pulse.
+    pub const TAP_DIR: BitRange = BitRange {
+        offset: 0,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct APEX_CONFIG7;
+
+impl APEX_CONFIG7 {
+    /// Tap detection minimum jerk threshold.
+    pub const TAP_MIN_JERK_THR: BitRange = BitRange {
+        offset: 2,
+        length: 6,
+    };
+    /// Tap detection maximum peak tolerance.
+    pub const TAP_MAX_PEAK_TOL: BitRange = BitRange {
+        offset: 0,
+        length: 2,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct APEX_CONFIG8;
+
+impl APEX_CONFIG8 {
+    /// Tap measurement window (number of samples).
+    pub const TAP_TMAX: BitRange = BitRange {
+        offset: 5,
+        length: 2,
+    };
+    /// Tap energy measurement window (number of samples).
+    pub const TAP_TAVG: BitRange = BitRange {
+        offset: 3,
+        length: 2,
+    };
+    /// Single tap window (number of samples).
+    pub const TAP_TMIN: BitRange = BitRange {
+        offset: 0,
+        length: 3,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct INT_SOURCE6;
+
+impl INT_SOURCE6 {
+    /// Step detect and step count overflow interrupts routed to INT1 (INT2 for INT_SOURCE7).
+    pub const STEP_INT1_EN: BitRange = BitRange {
+        offset: 4,
+        length: 2,
+    };
+    /// Tilt detect interrupt routed to INT1 (INT2 for INT_SOURCE7).
+    pub const TILT_DET_INT1_EN: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+    /// Wake and sleep detect interrupts routed to INT1 (INT2 for INT_SOURCE7).
+    pub const R2W_INT1_EN: BitRange = BitRange {
+        offset: 1,
+        length: 2,
+    };
+    /// Tap detect interrupt routed to INT1 (INT2 for INT_SOURCE7).
+    pub const TAP_DET_INT_EN: BitRange = BitRange {
+        offset: 0,
+        length: 1,
+    };
+}
+
+#[allow(non_camel_case_types)]
+pub struct TMST_CONFIG;
+
+impl TMST_CONFIG {
+    /// TMST_VALUE is updated with the time stamp counter on TMST_STROBE, it reads 0 otherwise.
+    pub const TMST_TO_REGS_EN: BitRange = BitRange {
+        offset: 4,
+        length: 1,
+    };
+    /// Time stamp resolution, 0: 1µs, 1: 16µs or one RTC clock period.
+    pub const TMST_RES: BitRange = BitRange {
+        offset: 3,
+        length: 1,
+    };
+    /// Time stamp field contains the time since the last ODR.
+    pub const TMST_DELTA_EN: BitRange = BitRange {
+        offset: 2,
+        length: 1,
+    };
+}
+
+/// Decoded ACCEL_CONFIG0.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AccelConfig0 {
+    pub range: AccelRange,
+    pub odr: AccelODR,
+}
+
+impl AccelConfig0 {
+    pub fn from_bits(bits: u8) -> Result<Self, Error> {
+        Ok(Self {
+            range: ACCEL_CONFIG0::FS_SEL.get(bits).try_into()?,
+            odr: ACCEL_CONFIG0::ODR.get(bits).try_into()?,
+        })
+    }
+
+    pub fn to_bits(self) -> u8 {
+        ACCEL_CONFIG0::FS_SEL.put(self.range as u8) | ACCEL_CONFIG0::ODR.put(self.odr as u8)
+    }
+}
+
+/// Decoded GYRO_CONFIG0.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GyroConfig0 {
+    pub range: GyroRange,
+    pub odr: GyroODR,
+}
+
+impl GyroConfig0 {
+    pub fn from_bits(bits: u8) -> Result<Self, Error> {
+        Ok(Self {
+            range: GYRO_CONFIG0::FS_SEL.get(bits).try_into()?,
+            odr: GYRO_CONFIG0::ODR.get(bits).try_into()?,
+        })
+    }
+
+    pub fn to_bits(self) -> u8 {
+        GYRO_CONFIG0::FS_SEL.put(self.range as u8) | GYRO_CONFIG0::ODR.put(self.odr as u8)
+    }
+}
+
+/// Decoded PWR_MGMT0.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PwrMgmt0 {
+    pub temp_disabled: bool,
+    pub idle: bool,
+    pub mode: PowerMode,
+}
+
+impl PwrMgmt0 {
+    pub fn from_bits(bits: u8) -> Self {
+        let mode = PWR_MGMT0::GYRO_MODE.put(PWR_MGMT0::GYRO_MODE.get(bits))
+            | PWR_MGMT0::ACCEL_MODE.put(PWR_MGMT0::ACCEL_MODE.get(bits));
+        Self {
+            temp_disabled: PWR_MGMT0::TEMP_DIS.get(bits) != 0,
+            idle: PWR_MGMT0::IDLE.get(bits) != 0,
+            mode: mode.into(),
+        }
+    }
+
+    pub fn to_bits(self) -> u8 {
+        PWR_MGMT0::TEMP_DIS.put(self.temp_disabled as u8)
+            | PWR_MGMT0::IDLE.put(self.idle as u8)
+            | self.mode.bits()
+    }
+}
+
+// Field layouts from the datasheet register map.
+const _: () = {
+    assert!(ACCEL_CONFIG0::FS_SEL.mask() == 0b1110_0000);
+    assert!(ACCEL_CONFIG0::ODR.mask() == 0b0000_1111);
+    assert!(GYRO_CONFIG0::FS_SEL.mask() == 0b1110_0000);
+    assert!(GYRO_CONFIG0::ODR.mask() == 0b0000_1111);
+    assert!(PWR_MGMT0::TEMP_DIS.mask() == 0b0010_0000);
+    assert!(PWR_MGMT0::IDLE.mask() == 0b0001_0000);
+    assert!(PWR_MGMT0::GYRO_MODE.mask() == 0b0000_1100);
+    assert!(PWR_MGMT0::ACCEL_MODE.mask() == 0b0000_0011);
+    // Reset values: ±16g / ±2000dps at 1kHz.
+    assert!(ACCEL_CONFIG0::FS_SEL.get(0x06) == AccelRange::G16 as u8);
+    assert!(ACCEL_CONFIG0::ODR.get(0x06) == AccelODR::Hz1k as u8);
+    assert!(GYRO_CONFIG0::FS_SEL.get(0x06) == GyroRange::Dps2000 as u8);
+    assert!(GYRO_CONFIG0::ODR.get(0x06) == GyroODR::Hz1k as u8);
+};
+
+/// Whether every value of `fields` fits in `range`.
+const fn all_fit(range: &BitRange, fields: &[u8]) -> bool {
+    let mut i = 0;
+    while i < fields.len() {
+        if !range.fits(fields[i]) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// Enum discriminants written to register fields must not spill into neighboring fields.
+const _: () = {
+    use AccelODR as A;
+    assert!(all_fit(
+        &ACCEL_CONFIG0::ODR,
+        &[
+            A::Hz32k as u8,
+            A::Hz16k as u8,
+            A::Hz8k as u8,
+            A::Hz4k as u8,
+            A::Hz2k as u8,
+            A::Hz1k as u8,
+            A::Hz200 as u8,
+            A::Hz100 as u8,
+            A::Hz50 as u8,
+            A::Hz25 as u8,
+            A::Hz12_5 as u8,
+            A::Hz6_25 as u8,
+            A::Hz3_125 as u8,
+            A::Hz1_5625 as u8,
+            A::Hz500 as u8,
+        ],
+    ));
+    use GyroODR as G;
+    assert!(all_fit(
+        &GYRO_CONFIG0::ODR,
+        &[
+            G::Hz32k as u8,
+            G::Hz16k as u8,
+            G::Hz8k as u8,
+            G::Hz4k as u8,
+            G::Hz2k as u8,
+            G::Hz1k as u8,
+            G::Hz200 as u8,
+            G::Hz100 as u8,
+            G::Hz50 as u8,
+            G::Hz25 as u8,
+            G::Hz12_5 as u8,
+            G::Hz500 as u8,
+        ],
+    ));
+    use AccelRange as AR;
+    assert!(all_fit(
+        &ACCEL_CONFIG0::FS_SEL,
+        &[AR::G16 as u8, AR::G8 as u8, AR::G4 as u8, AR::G2 as u8],
+    ));
+    use GyroRange as GR;
+    assert!(all_fit(
+        &GYRO_CONFIG0::FS_SEL,
+        &[
+            GR::Dps2000 as u8,
+            GR::Dps1000 as u8,
+            GR::Dps500 as u8,
+            GR::Dps250 as u8,
+            GR::Dps125 as u8,
+            GR::Dps62_5 as u8,
+            GR::Dps31_25 as u8,
+            GR::Dps15_625 as u8,
+        ],
+    ));
+    use PowerMode as P;
+    let modes = PWR_MGMT0::GYRO_MODE.mask() | PWR_MGMT0::ACCEL_MODE.mask();
+    let mut i = 0;
+    let all = [
+        P::Sleep,
+        P::Standby,
+        P::AccelLowPower,
+        P::AccelLowNoise,
+        P::GyroLowNoise,
+        P::SixAxisLowNoise,
+        P::Unknown(0xFF),
+    ];
+    while i < all.len() {
+        assert!(all[i].bits() & !modes == 0);
+        i += 1;
+    }
+    assert!(all_fit(
+        &TMST_CONFIG::TMST_RES,
+        &[
+            TimestampResolution::Us1 as u8,
+            TimestampResolution::Us16 as u8
+        ],
+    ));
+    assert!(all_fit(
+        &GYRO_ACCEL_CONFIG0::GYRO_UI_FILT_BW,
+        &[
+            LowLatencyFilter::Dec2AtOdr as u8,
+            LowLatencyFilter::Dec2At8xOdr as u8
+        ],
+    ));
+    assert!(all_fit(
+        &INT_CONFIG1::INT_TPULSE_DURATION,
+        &[InterruptPulse::Us100 as u8, InterruptPulse::Us8 as u8],
+    ));
+    assert!(all_fit(
+        &INTF_CONFIG0::SENSOR_DATA_ENDIAN,
+        &[Endian::Little as u8, Endian::Big as u8],
+    ));
+    #[cfg(feature = "fifo")]
+    assert!(all_fit(
+        &INTF_CONFIG0::FIFO_HOLD_LAST_DATA_EN,
+        &[
+            crate::fifo::InvalidSampleMode::InvalidCode as u8,
+            crate::fifo::InvalidSampleMode::HoldLast as u8
+        ],
+    ));
+    assert!(all_fit(
+        &INTF_CONFIG0::FIFO_COUNT_ENDIAN,
+        &[Endian::Little as u8, Endian::Big as u8],
+    ));
+    assert!(all_fit(
+        &INTF_CONFIG0::UI_SIFS_CFG,
+        &[DisabledInterface::Spi as u8, DisabledInterface::I2c as u8],
+    ));
+    assert!(all_fit(
+        &INTF_CONFIG1::CLKSEL,
+        &[
+            ClockSource::Rc as u8,
+            ClockSource::PllOrRc as u8,
+            ClockSource::Disabled as u8
+        ],
+    ));
+    assert!(all_fit(
+        &INTF_CONFIG1::ACCEL_LP_CLK_SEL,
+        &[
+            AccelLpClock::WakeUpOscillator as u8,
+            AccelLpClock::RcOscillator as u8
+        ],
+    ));
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "fifo")]
+    use crate::fifo::{FifoPacket, InvalidSampleMode};
+    use crate::{
+        config::{AccelODR, GyroODR, PowerMode, Units},
+        driver::{tests::RegisterFile, Driver, State},
+    };
+
+    #[test]
+    fn reset_values() {
+        assert_eq!(
+            AccelConfig0::from_bits(0x06).unwrap(),
+            AccelConfig0 {
+                range: AccelRange::G16,
+                odr: AccelODR::Hz1k,
+            }
+        );
+        assert_eq!(
+            GyroConfig0::from_bits(0x06).unwrap(),
+            GyroConfig0 {
+                range: GyroRange::Dps2000,
+                odr: GyroODR::Hz1k,
+            }
+        );
+        assert_eq!(
+            PwrMgmt0::from_bits(0x00),
+            PwrMgmt0 {
+                temp_disabled: false,
+                idle: false,
+                mode: PowerMode::Sleep,
+            }
+        );
+    }
+
+    #[test]
+    fn accel_config0_round_trip() {
+        for bits in 0..=u8::MAX {
+            if let Ok(config) = AccelConfig0::from_bits(bits) {
+                // Bit 4 is reserved.
+                assert_eq!(config.to_bits(), bits & !0b0001_0000, "{bits:#04x}");
+                assert_eq!(AccelConfig0::from_bits(config.to_bits()).unwrap(), config);
+            }
+        }
+    }
+
+    #[test]
+    fn gyro_config0_round_trip() {
+        for bits in 0..=u8::MAX {
+            if let Ok(config) = GyroConfig0::from_bits(bits) {
+                assert_eq!(config.to_bits(), bits & !0b0001_0000, "{bits:#04x}");
+                assert_eq!(GyroConfig0::from_bits(config.to_bits()).unwrap(), config);
+            }
+        }
+    }
+
+    #[test]
+    fn pwr_mgmt0_round_trip() {
+        // Bits 7:6 are reserved.
+        for bits in 0..=0b0011_1111 {
+            assert_eq!(PwrMgmt0::from_bits(bits).to_bits(), bits, "{bits:#04x}");
+        }
+    }
+
+    #[test]
+    fn odr_is_the_low_nibble() {
+        // ODR in bits 3:0, not 7:4.
+        let config = AccelConfig0::from_bits(0b0110_0110).unwrap();
+        assert_eq!(config.odr, AccelODR::Hz1k);
+        let config = GyroConfig0::from_bits(0b0110_0110).unwrap();
+        assert_eq!(config.odr, GyroODR::Hz1k);
+        assert!(AccelConfig0::from_bits(0b0110_0000).is_err());
+    }
+
+    #[test]
+    fn range_is_bits_7_to_5() {
+        // FS_SEL shifted down by 5, not 4.
+        assert_eq!(ACCEL_CONFIG0::FS_SEL.get(0b0110_0110), 3);
+        let config = AccelConfig0::from_bits(0b0110_0110).unwrap();
+        assert_eq!(config.range, AccelRange::G2);
+        let config = GyroConfig0::from_bits(0b1110_0110).unwrap();
+        assert_eq!(config.range, GyroRange::Dps15_625);
+        assert_eq!(
+            AccelConfig0 {
+                range: AccelRange::G2,
+                odr: AccelODR::Hz1k,
+            }
+            .to_bits(),
+            0b0110_0110
+        );
+    }
+
+    /// Every value accepted by `TryFrom<u8>` fits its field, so new variants are checked too.
+    macro_rules! assert_variants_fit {
+        ($range:expr, $ty:ty) => {
+            for value in 0..=u8::MAX {
+                if let Ok(variant) = <$ty>::try_from(value) {
+                    assert!($range.fits(variant as u8), "{variant:?}");
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn variants_fit_their_fields() {
+        assert_variants_fit!(ACCEL_CONFIG0::ODR, AccelODR);
+        assert_variants_fit!(ACCEL_CONFIG0::FS_SEL, AccelRange);
+        assert_variants_fit!(GYRO_CONFIG0::ODR, GyroODR);
+        assert_variants_fit!(GYRO_CONFIG0::FS_SEL, GyroRange);
+        assert_variants_fit!(TMST_CONFIG::TMST_RES, TimestampResolution);
+        assert_variants_fit!(INTF_CONFIG1::CLKSEL, ClockSource);
+    }
+
+    const ENDIANS: [Endian; 2] = [Endian::Big, Endian::Little];
+
+    /// Bytes of `word` in address order.
+    fn encode(word: i16, endian: Endian) -> [u8; 2] {
+        match endian {
+            Endian::Big => word.to_be_bytes(),
+            Endian::Little => word.to_le_bytes(),
+        }
+    }
+
+    /// Spread of 16-bit values including both ends and the sign change.
+    fn words() -> impl Iterator<Item = i16> {
+        (i16::MIN..=i16::MAX)
+            .step_by(251)
+            .chain([i16::MAX, -1, 0, 1, 0x00FF, 0x0100])
+    }
+
+    fn driver<'a>(interface: &'a mut RegisterFile, state: &'a mut State) -> Driver<'a> {
+        Driver { interface, state }
+    }
+
+    #[test]
+    fn word_round_trip() {
+        for endian in ENDIANS {
+            for word in i16::MIN..=i16::MAX {
+                assert_eq!(endian.decode_i16(encode(word, endian)), word);
+                assert_eq!(endian.decode(encode(word, endian)), word as u16);
+            }
+        }
+    }
+
+    #[test]
+    fn sensor_data_round_trip() {
+        let mut interface = RegisterFile::new();
+        let mut state = State::new(Units::default());
+        for endian in ENDIANS {
+            state.sensor_data_endian = endian;
+            for word in words() {
+                let data = [word, !word, word.wrapping_add(1), -(word / 2), 7, word, 0];
+                let mut buf = [0u8; 14];
+                for (bytes, word) in buf.chunks_mut(2).zip(data) {
+                    bytes.copy_from_slice(&encode(word, endian));
+                }
+                interface.banks[0][Bank0::TempData1.address().0 as usize..][..14]
+                    .copy_from_slice(&buf);
+
+                let read = driver(&mut interface, &mut state)
+                    .read_sensor_data()
+                    .unwrap();
+                assert_eq!(read.temperature.0, data[0], "{endian:?}");
+                assert_eq!(read.acceleration, (data[1], data[2], data[3]));
+                assert_eq!(read.angular_velocity, (data[4], data[5], data[6]));
+            }
+        }
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn fifo_count_round_trip() {
+        let mut interface = RegisterFile::new();
+        let mut state = State::new(Units::default());
+        for endian in ENDIANS {
+            state.fifo_count_endian = endian;
+            for count in 0..=crate::limits::FIFO_SIZE as u16 {
+                interface.banks[0][Bank0::FifoCountH.address().0 as usize..][..2]
+                    .copy_from_slice(&encode(count as i16, endian));
+                let read = driver(&mut interface, &mut state).fifo_count().unwrap();
+                assert_eq!(read, count, "{endian:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn timestamp_round_trip() {
+        let mut interface = RegisterFile::new();
+        let mut state = State::new(Units::default());
+        for endian in ENDIANS {
+            // TMST_VALUE does not follow the sensor data endian.
+            state.sensor_data_endian = endian;
+            for ticks in (0..1 << 20).step_by(4099).chain([0xF_FFFF]) {
+                let [low, mid, high, _] = u32::to_le_bytes(ticks);
+                // Bits 7:4 of TMSTVAL2 are reserved.
+                interface.banks[1][Bank1::TmstVal0.address().0 as usize..][..3].copy_from_slice(&[
+                    low,
+                    mid,
+                    high | 0xA0,
+                ]);
+                let read = driver(&mut interface, &mut state)
+                    .latch_timestamp()
+                    .unwrap();
+                assert_eq!(read, ticks, "{endian:?}");
+            }
+        }
+    }
+
+    #[cfg(feature = "fifo")]
+    fn parse(buf: &[u8], endian: Endian) -> FifoPacket {
+        FifoPacket::parse(buf, endian, InvalidSampleMode::HoldLast)
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn fifo_packet_8_round_trip() {
+        for endian in ENDIANS {
+            for word in words() {
+                let data = (word, !word, word.wrapping_add(1));
+                for header in [0x40, 0x20] {
+                    let mut buf = [header, 0, 0, 0, 0, 0, 0, word as u8];
+                    buf[1..3].copy_from_slice(&encode(data.0, endian));
+                    buf[3..5].copy_from_slice(&encode(data.1, endian));
+                    buf[5..7].copy_from_slice(&encode(data.2, endian));
+
+                    let packet = parse(&buf, endian);
+                    let read = packet.accelerometer().or(packet.gyroscope());
+                    assert_eq!(read, Some(data), "{endian:?}");
+                    assert_eq!(packet.raw_temperature(), word as i8 as i16);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn fifo_packet_16_round_trip() {
+        for endian in ENDIANS {
+            for word in words() {
+                let accel = (word, !word, word.wrapping_add(1));
+                let gyro = (-(word / 2), word.wrapping_mul(3), word.rotate_left(8));
+                let mut buf = [0u8; 16];
+                buf[0] = 0x68;
+                let words = [accel.0, accel.1, accel.2, gyro.0, gyro.1, gyro.2];
+                for (bytes, word) in buf[1..13].chunks_mut(2).zip(words) {
+                    bytes.copy_from_slice(&encode(word, endian));
+                }
+                buf[13] = word as u8;
+                buf[14..].copy_from_slice(&encode(!word, endian));
+
+                let packet = parse(&buf, endian);
+                assert_eq!(packet.accelerometer(), Some(accel), "{endian:?}");
+                assert_eq!(packet.gyroscope(), Some(gyro));
+                assert_eq!(packet.raw_temperature(), word as i8 as i16);
+                assert_eq!(packet.odr_timestamp(), Some(!word as u16));
+            }
+        }
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn fifo_packet_20_round_trip() {
+        // 18-bit accel and 19-bit gyro data, stored as the 20 most significant bits.
+        let accel = (-(1 << 17)..1 << 17).step_by(1021).chain([(1 << 17) - 1]);
+        let gyro = (-(1 << 18)..1 << 18).step_by(2039).chain([(1 << 18) - 1]);
+        for endian in ENDIANS {
+            for (a, g) in accel.clone().zip(gyro.clone()) {
+                let accel = (a, -a - 1, a / 3);
+                let gyro = (g, -g - 1, g / 3);
+                let mut buf = [0u8; 20];
+                buf[0] = 0x78;
+                let mut put = |i: usize, value: i32, shift: u32| {
+                    let bits = value << shift;
+                    buf[1 + 2 * i..][..2].copy_from_slice(&encode((bits >> 4) as i16, endian));
+                    let nibble = (bits & 0x0F) as u8;
+                    buf[17 + i % 3] |= if i < 3 { nibble << 4 } else { nibble };
+                };
+                for (i, value) in [accel.0, accel.1, accel.2].into_iter().enumerate() {
+                    put(i, value, 2);
+                }
+                for (i, value) in [gyro.0, gyro.1, gyro.2].into_iter().enumerate() {
+                    put(3 + i, value, 1);
+                }
+                buf[13..15].copy_from_slice(&encode(a as i16, endian));
+                buf[15..17].copy_from_slice(&encode(g as i16, endian));
+
+                let packet = parse(&buf, endian);
+                assert_eq!(packet.hires_accelerometer(), Some(accel), "{endian:?}");
+                assert_eq!(packet.hires_gyroscope(), Some(gyro));
+                assert_eq!(packet.raw_temperature(), a as i16);
+                assert_eq!(packet.odr_timestamp(), Some(g as u16));
+            }
+        }
+    }
+}
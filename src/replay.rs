@@ -0,0 +1,439 @@
+//! Reconstruction of the data a firmware saw from a log of its bus transactions.
+
+use crate::{
+    config::{AccelRange, DataSource, Endian, GyroRange, Resolution, Units},
+    reader::Sample,
+    register::{Bank0, ACCEL_CONFIG0, GYRO_CONFIG0, INTF_CONFIG0},
+};
+#[cfg(feature = "fifo")]
+use crate::{
+    fifo::{FifoPacket, InvalidSampleMode, HIRES_ACCEL_SENSITIVITY, HIRES_GYRO_SENSITIVITY},
+    register::FIFO_CONFIG1,
+};
+
+const SPI_READ_OPERATION: u8 = 0b10000000;
+
+/// Register access captured on the bus, in bank 0 unless preceded by a REG_BANK_SEL write.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Transaction<'a> {
+    /// Bytes read from consecutive registers starting at `address`, or from FIFO_DATA.
+    Read { address: u8, data: &'a [u8] },
+    /// Byte written to the register at `address`.
+    Write { address: u8, data: u8 },
+}
+
+impl<'a> Transaction<'a> {
+    /// Decodes an SPI frame, the address byte followed by the data bytes.
+    ///
+    /// Returns `None` for frames without data and for writes of more than one byte.
+    pub fn from_spi(frame: &'a [u8]) -> Option<Self> {
+        let (&command, data) = frame.split_first()?;
+        let address = command & !SPI_READ_OPERATION;
+        if command & SPI_READ_OPERATION != 0 {
+            (!data.is_empty()).then_some(Self::Read { address, data })
+        } else {
+            match data {
+                &[data] => Some(Self::Write { address, data }),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Configuration decoded from the register accesses.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ReplayConfig {
+    pub accel_range: AccelRange,
+    pub gyro_range: GyroRange,
+    pub endian: Endian,
+    /// Whether the FIFO stores 20-bit packets.
+    #[cfg(feature = "fifo")]
+    pub fifo_hires: bool,
+    #[cfg(feature = "fifo")]
+    pub fifo_invalid_samples: InvalidSampleMode,
+}
+
+/// Event reconstructed by [`Replay`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReplayEvent {
+    /// The configuration changed, or was read for the first time.
+    Config(ReplayConfig),
+    /// A sample was read from the data registers or the FIFO.
+    Sample { sample: Sample, source: DataSource },
+}
+
+/// Decoder replaying a bus log through the driver decode logic.
+///
+/// Register samples are emitted when GYRO_DATA_Z0 is read, with the accel data from the latest
+/// reads of its registers. FIFO packets without both accel and gyro data are skipped, like
+/// [`crate::Icm42688p::read_sample`] does. Samples are in the sensor frame, without gyro bias
+/// correction.
+pub struct Replay {
+    units: Units,
+    bank: u8,
+    /// Last known value of the bank 0 registers.
+    registers: [u8; 0x80],
+    config: Option<ReplayConfig>,
+    /// Sequence number of the next sample.
+    sequence: u32,
+    #[cfg(feature = "fifo")]
+    packet: [u8; FifoPacket::MAX_SIZE],
+    #[cfg(feature = "fifo")]
+    packet_len: usize,
+}
+
+impl Replay {
+    /// Starts from the reset state of the sensor, with samples converted to `units`.
+    pub fn new(units: Units) -> Self {
+        let mut registers = [0; 0x80];
+        registers[Bank0::GyroConfig0 as usize] = 0x06;
+        registers[Bank0::AccelConfig0 as usize] = 0x06;
+        registers[Bank0::IntfConfig0 as usize] = 0x30;
+        Self {
+            units,
+            bank: 0,
+            registers,
+            config: None,
+            sequence: 0,
+            #[cfg(feature = "fifo")]
+            packet: [0; FifoPacket::MAX_SIZE],
+            #[cfg(feature = "fifo")]
+            packet_len: 0,
+        }
+    }
+
+    /// Feeds the next transaction of the log.
+    pub fn feed(&mut self, transaction: Transaction, mut on_event: impl FnMut(ReplayEvent)) {
+        match transaction {
+            Transaction::Write { address, data } if address == Bank0::RegBankSel as u8 => {
+                self.bank = data & 0x07;
+            }
+            _ if self.bank != 0 => {}
+            Transaction::Write { address, data } => {
+                self.registers[address as usize & 0x7F] = data;
+                self.update_config(&mut on_event);
+            }
+            #[cfg(feature = "fifo")]
+            Transaction::Read { address, data } if address == Bank0::FifoData as u8 => {
+                for &byte in data {
+                    self.feed_fifo(byte, &mut on_event);
+                }
+            }
+            Transaction::Read { address, data } => {
+                let start = address as usize;
+                for (offset, &byte) in data.iter().enumerate() {
+                    if let Some(register) = self.registers.get_mut(start + offset) {
+                        *register = byte;
+                    }
+                }
+                self.update_config(&mut on_event);
+                let end = start + data.len();
+                if (start..end).contains(&(Bank0::GyroDataZ0 as usize)) {
+                    let sample = self.sequenced(self.register_sample());
+                    on_event(ReplayEvent::Sample {
+                        sample,
+                        source: DataSource::Registers,
+                    });
+                }
+            }
+        }
+    }
+
+    fn decode_config(&self) -> ReplayConfig {
+        let accel_config0 = self.registers[Bank0::AccelConfig0 as usize];
+        let gyro_config0 = self.registers[Bank0::GyroConfig0 as usize];
+        let intf_config0 = self.registers[Bank0::IntfConfig0 as usize];
+        ReplayConfig {
+            accel_range: AccelRange::try_from(ACCEL_CONFIG0::FS_SEL.get(accel_config0))
+                .unwrap_or_default(),
+            gyro_range: GyroRange::try_from(GYRO_CONFIG0::FS_SEL.get(gyro_config0))
+                .unwrap_or_default(),
+            endian: match INTF_CONFIG0::SENSOR_DATA_ENDIAN.get(intf_config0) {
+                0 => Endian::Little,
+                _ => Endian::Big,
+            },
+            #[cfg(feature = "fifo")]
+            fifo_hires: FIFO_CONFIG1::FIFO_HIRES_EN
+                .get(self.registers[Bank0::FifoConfig1 as usize])
+                != 0,
+            #[cfg(feature = "fifo")]
+            fifo_invalid_samples: INTF_CONFIG0::FIFO_HOLD_LAST_DATA_EN
+                .get(intf_config0)
+                .into(),
+        }
+    }
+
+    fn update_config(&mut self, on_event: &mut impl FnMut(ReplayEvent)) {
+        let config = self.decode_config();
+        if self.config != Some(config) {
+            self.config = Some(config);
+            on_event(ReplayEvent::Config(config));
+        }
+    }
+
+    fn register_sample(&self) -> Sample {
+        let config = self.decode_config();
+        let word = |register: Bank0| {
+            let i = register as usize;
+            config
+                .endian
+                .decode_i16([self.registers[i], self.registers[i + 1]]) as i32
+        };
+        let accel = (
+            word(Bank0::AccelDataX1),
+            word(Bank0::AccelDataY1),
+            word(Bank0::AccelDataZ1),
+        );
+        let gyro = (
+            word(Bank0::GyroDataX1),
+            word(Bank0::GyroDataY1),
+            word(Bank0::GyroDataZ1),
+        );
+        self.scale(
+            accel,
+            gyro,
+            config.accel_range.sensitivity_scale_factor(),
+            config.gyro_range.sensitivity_scale_factor(),
+            Resolution::Bits16,
+        )
+    }
+
+    #[cfg(feature = "fifo")]
+    fn feed_fifo(&mut self, byte: u8, on_event: &mut impl FnMut(ReplayEvent)) {
+        if self.packet_len == 0 && FifoPacket::size(byte).is_none() {
+            return;
+        }
+        self.packet[self.packet_len] = byte;
+        self.packet_len += 1;
+        let size = FifoPacket::size(self.packet[0]).unwrap_or(1);
+        if self.packet_len < size {
+            return;
+        }
+        self.packet_len = 0;
+
+        let config = self.decode_config();
+        let packet = FifoPacket::parse(
+            &self.packet[..size],
+            config.endian,
+            config.fifo_invalid_samples,
+        );
+        let (Some(accel), Some(gyro)) = (packet.accelerometer(), packet.gyroscope()) else {
+            return;
+        };
+        let sample = if size == FifoPacket::MAX_SIZE {
+            let (accel, gyro) = packet.hires_data();
+            self.scale(
+                accel,
+                gyro,
+                HIRES_ACCEL_SENSITIVITY,
+                HIRES_GYRO_SENSITIVITY,
+                Resolution::Bits20,
+            )
+        } else {
+            let widen = |(x, y, z): (i16, i16, i16)| (x as i32, y as i32, z as i32);
+            self.scale(
+                widen(accel),
+                widen(gyro),
+                config.accel_range.sensitivity_scale_factor(),
+                config.gyro_range.sensitivity_scale_factor(),
+                Resolution::Bits16,
+            )
+        };
+        let sample = self.sequenced(sample);
+        on_event(ReplayEvent::Sample {
+            sample,
+            source: DataSource::Fifo,
+        });
+    }
+
+    fn scale(
+        &self,
+        (ax, ay, az): (i32, i32, i32),
+        (gx, gy, gz): (i32, i32, i32),
+        accel_factor: f32,
+        gyro_factor: f32,
+        resolution: Resolution,
+    ) -> Sample {
+        let accel = |raw: i32| self.units.accel.from_g(raw as f32 / accel_factor);
+        let gyro = |raw: i32| self.units.gyro.from_dps(raw as f32 / gyro_factor);
+        Sample {
+            acceleration: (accel(ax), accel(ay), accel(az)),
+            angular_velocity: (gyro(gx), gyro(gy), gyro(gz)),
+            resolution,
+            sequence: 0,
+        }
+    }
+
+    /// Numbers the sample like the driver does.
+    fn sequenced(&mut self, sample: Sample) -> Sample {
+        let sequence = self.sequence;
+        self.sequence = sequence.wrapping_add(1);
+        Sample { sequence, ..sample }
+    }
+}
+
+/// Replays a whole bus log, see [`Replay`].
+pub fn replay<'a>(
+    transactions: impl IntoIterator<Item = Transaction<'a>>,
+    units: Units,
+    mut on_event: impl FnMut(ReplayEvent),
+) {
+    let mut replay = Replay::new(units);
+    for transaction in transactions {
+        replay.feed(transaction, &mut on_event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes `words` big endian, the reset data endianness.
+    fn encode<const N: usize>(words: &[i16]) -> [u8; N] {
+        let mut out = [0; N];
+        for (chunk, word) in out.chunks_exact_mut(2).zip(words) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+
+    /// Replays `transactions`, returning the events and their count.
+    fn events(transactions: &[Transaction]) -> ([Option<ReplayEvent>; 8], usize) {
+        let mut events = [None; 8];
+        let mut len = 0;
+        replay(transactions.iter().copied(), Units::default(), |event| {
+            events[len] = Some(event);
+            len += 1;
+        });
+        (events, len)
+    }
+
+    fn sample(event: Option<ReplayEvent>) -> (Sample, DataSource) {
+        match event {
+            Some(ReplayEvent::Sample { sample, source }) => (sample, source),
+            event => panic!("expected a sample, got {event:?}"),
+        }
+    }
+
+    #[test]
+    fn transaction_from_spi() {
+        assert_eq!(
+            Transaction::from_spi(&[0x9F, 1, 2]),
+            Some(Transaction::Read {
+                address: 0x1F,
+                data: &[1, 2]
+            })
+        );
+        assert_eq!(
+            Transaction::from_spi(&[0x50, 0x66]),
+            Some(Transaction::Write {
+                address: 0x50,
+                data: 0x66
+            })
+        );
+        assert_eq!(Transaction::from_spi(&[]), None);
+        assert_eq!(Transaction::from_spi(&[0x9F]), None);
+        assert_eq!(Transaction::from_spi(&[0x50, 1, 2]), None);
+    }
+
+    #[test]
+    fn register_samples_follow_written_ranges() {
+        let data: [u8; 12] = encode(&[16384, 0, -8192, 262, 0, -131]);
+        let (events, len) = events(&[
+            // ±2g and ±250°/s at 1kHz.
+            Transaction::Write {
+                address: Bank0::AccelConfig0 as u8,
+                data: 0x66,
+            },
+            Transaction::Write {
+                address: Bank0::GyroConfig0 as u8,
+                data: 0x66,
+            },
+            Transaction::Read {
+                address: Bank0::AccelDataX1 as u8,
+                data: &data,
+            },
+            Transaction::Read {
+                address: Bank0::AccelDataX1 as u8,
+                data: &data,
+            },
+        ]);
+
+        assert_eq!(len, 4);
+        let Some(ReplayEvent::Config(config)) = events[1] else {
+            panic!("expected a config, got {:?}", events[1]);
+        };
+        assert_eq!(config.accel_range, AccelRange::G2);
+        assert_eq!(config.gyro_range, GyroRange::Dps250);
+        assert_eq!(config.endian, Endian::Big);
+
+        for (sequence, event) in events[2..4].iter().enumerate() {
+            let (sample, source) = sample(*event);
+            assert_eq!(source, DataSource::Registers);
+            assert_eq!(sample.acceleration, (1.0, 0.0, -0.5));
+            assert_eq!(sample.angular_velocity, (2.0, 0.0, -1.0));
+            assert_eq!(sample.resolution, Resolution::Bits16);
+            assert_eq!(sample.sequence, sequence as u32);
+        }
+    }
+
+    #[test]
+    fn other_banks_are_ignored() {
+        let data: [u8; 12] = encode(&[2048, 0, 0, 0, 0, 0]);
+        let (events, len) = events(&[
+            Transaction::Write {
+                address: Bank0::RegBankSel as u8,
+                data: 1,
+            },
+            // Bank 1 register at the address of ACCEL_CONFIG0.
+            Transaction::Write {
+                address: Bank0::AccelConfig0 as u8,
+                data: 0x66,
+            },
+            Transaction::Write {
+                address: Bank0::RegBankSel as u8,
+                data: 0,
+            },
+            Transaction::Read {
+                address: Bank0::AccelDataX1 as u8,
+                data: &data,
+            },
+        ]);
+
+        assert_eq!(len, 2);
+        let Some(ReplayEvent::Config(config)) = events[0] else {
+            panic!("expected a config, got {:?}", events[0]);
+        };
+        assert_eq!(config.accel_range, AccelRange::G16);
+        assert_eq!(sample(events[1]).0.acceleration, (1.0, 0.0, 0.0));
+    }
+
+    #[cfg(feature = "fifo")]
+    #[test]
+    fn fifo_packets_split_across_reads() {
+        // Packet 1 with accel data only, skipped, then packet 3.
+        let mut data = [0; 24];
+        data[0] = 0x40;
+        data[1..7].copy_from_slice(&encode::<6>(&[2048, 0, 0]));
+        data[8] = 0x68;
+        data[9..21].copy_from_slice(&encode::<12>(&[0, -4096, 0, 0, 0, 164]));
+        let (first, second) = data.split_at(13);
+        let (events, len) = events(&[
+            Transaction::Read {
+                address: Bank0::FifoData as u8,
+                data: first,
+            },
+            Transaction::Read {
+                address: Bank0::FifoData as u8,
+                data: second,
+            },
+        ]);
+
+        assert_eq!(len, 1);
+        let (sample, source) = sample(events[0]);
+        assert_eq!(source, DataSource::Fifo);
+        assert_eq!(sample.acceleration, (0.0, -2.0, 0.0));
+        assert!((sample.angular_velocity.2 - 10.0).abs() < 1e-4);
+        assert_eq!(sample.sequence, 0);
+    }
+}
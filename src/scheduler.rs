@@ -0,0 +1,376 @@
+use crate::{error::Error, Icm42688p, Interface, MillisDurationU32};
+
+/// Register read run by a [`PollScheduler`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PollTask {
+    /// Temperature, accel and gyro data registers, 14 bytes in one burst so accel and gyro data
+    /// belong to the same sample.
+    Data,
+    /// Temperature data registers, 2 bytes.
+    Temperature,
+    /// INT_STATUS, 1 byte.
+    Status,
+}
+
+impl PollTask {
+    const ALL: [PollTask; 3] = [PollTask::Data, PollTask::Temperature, PollTask::Status];
+
+    /// Number of registers and of read transactions.
+    const fn reads(self) -> (u32, u32) {
+        match self {
+            PollTask::Data => (14, 1),
+            PollTask::Temperature => (2, 1),
+            PollTask::Status => (1, 1),
+        }
+    }
+}
+
+/// How often a [`PollTask`] runs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PollRate {
+    pub period: MillisDurationU32,
+    /// Due tasks run by decreasing priority.
+    pub priority: u8,
+}
+
+/// Tasks of a [`PollScheduler`] and the bus capacity they share, `None` disables a task.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PollPlan {
+    pub data: Option<PollRate>,
+    pub temperature: Option<PollRate>,
+    pub status: Option<PollRate>,
+    /// Bus bytes the reads may use per millisecond, e.g. 11 for the whole of a 100kHz I2C bus.
+    pub bytes_per_ms: u32,
+    /// Bus bytes of each read besides the register content, e.g. 3 for I2C (the device address
+    /// twice and the register address) or 1 for SPI.
+    pub overhead_bytes: u32,
+}
+
+impl PollPlan {
+    fn rate(&self, task: PollTask) -> Option<PollRate> {
+        match task {
+            PollTask::Data => self.data,
+            PollTask::Temperature => self.temperature,
+            PollTask::Status => self.status,
+        }
+    }
+
+    /// Bus bytes of one run of `task`.
+    pub fn cost(&self, task: PollTask) -> u32 {
+        let (registers, reads) = task.reads();
+        registers + reads * self.overhead_bytes
+    }
+
+    /// Share of [`Self::bytes_per_ms`] the tasks need at their periods.
+    ///
+    /// Above 1.0 the budget cannot keep up, the lowest priority tasks run late or never.
+    pub fn load(&self) -> f32 {
+        let needed: f32 = PollTask::ALL
+            .iter()
+            .filter_map(|&task| {
+                let rate = self.rate(task)?;
+                Some(self.cost(task) as f32 / rate.period.to_millis().max(1) as f32)
+            })
+            .sum();
+        needed / self.bytes_per_ms as f32
+    }
+}
+
+/// Data read by a [`PollScheduler`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PollOutput {
+    /// In the configured units.
+    Data {
+        accel: (f32, f32, f32),
+        gyro: (f32, f32, f32),
+    },
+    /// Unit: °C
+    Temperature(f32),
+    Status {
+        data_ready: bool,
+    },
+}
+
+/// Outcome of a [`PollScheduler::tick`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PollTick {
+    /// Bus bytes used by the reads of this tick.
+    pub bytes: u32,
+    /// Due tasks left for a later tick for lack of budget.
+    pub deferred: u8,
+}
+
+/// Staggers register reads over ticks so they stay within a bus byte budget, for buses too slow
+/// to read everything at every tick, e.g. 100kHz I2C.
+///
+/// The budget accrues [`PollPlan::bytes_per_ms`] per elapsed millisecond, capped to one
+/// millisecond or to the most expensive task. Due tasks run by decreasing priority, then by
+/// lateness, until one does not fit in the budget: it and the following ones wait for a later
+/// tick, so a costly high priority task is never starved by cheaper ones.
+///
+/// The sensitivities are read once at construction, the scheduler must be created again after
+/// changing the accel or gyro range.
+pub struct PollScheduler {
+    plan: PollPlan,
+    accel_factor: f32,
+    gyro_factor: f32,
+    /// Time each task last ran, unit: ms
+    last_run_ms: [Option<u32>; 3],
+    /// Bus bytes available, unit: byte
+    credit: u32,
+    last_tick_ms: u32,
+}
+
+impl PollScheduler {
+    pub fn new<I: Interface>(
+        imu: &mut Icm42688p<I>,
+        plan: PollPlan,
+        now_ms: u32,
+    ) -> Result<Self, Error> {
+        let mut driver = imu.driver();
+        Ok(Self {
+            plan,
+            accel_factor: driver.accel_sensitivity()?,
+            gyro_factor: driver.gyro_sensitivity()?,
+            last_run_ms: [None; 3],
+            credit: 0,
+            last_tick_ms: now_ms,
+        })
+    }
+
+    pub fn plan(&self) -> PollPlan {
+        self.plan
+    }
+
+    /// Runs the due tasks the budget allows, and passes their data to `on_output`.
+    ///
+    /// Meant to be called periodically with a millisecond clock.
+    pub fn tick<I: Interface>(
+        &mut self,
+        imu: &mut Icm42688p<I>,
+        now_ms: u32,
+        mut on_output: impl FnMut(PollOutput),
+    ) -> Result<PollTick, Error> {
+        let max_cost = PollTask::ALL
+            .iter()
+            .filter(|&&task| self.plan.rate(task).is_some())
+            .map(|&task| self.plan.cost(task))
+            .max()
+            .unwrap_or(0);
+        let elapsed = now_ms.wrapping_sub(self.last_tick_ms);
+        self.last_tick_ms = now_ms;
+        self.credit = elapsed
+            .saturating_mul(self.plan.bytes_per_ms)
+            .saturating_add(self.credit)
+            .min(self.plan.bytes_per_ms.max(max_cost));
+
+        // (task index, priority, lateness), `None` for tasks not due.
+        let mut due = [None; 3];
+        for (slot, (index, &task)) in due.iter_mut().zip(PollTask::ALL.iter().enumerate()) {
+            let Some(rate) = self.plan.rate(task) else {
+                continue;
+            };
+            let lateness = match self.last_run_ms[index] {
+                Some(last) => now_ms
+                    .wrapping_sub(last)
+                    .checked_sub(rate.period.to_millis()),
+                None => Some(u32::MAX),
+            };
+            *slot = lateness.map(|lateness| (index, rate.priority, lateness));
+        }
+        due.sort_unstable_by(|a, b| {
+            let key = |entry: &Option<(usize, u8, u32)>| entry.map(|(_, p, l)| (p, l));
+            key(b).cmp(&key(a))
+        });
+
+        let mut tick = PollTick {
+            bytes: 0,
+            deferred: 0,
+        };
+        for (position, &(index, _, _)) in due.iter().flatten().enumerate() {
+            let task = PollTask::ALL[index];
+            let cost = self.plan.cost(task);
+            if cost > self.credit {
+                tick.deferred = (due.iter().flatten().count() - position) as u8;
+                break;
+            }
+            on_output(self.run(imu, task)?);
+            self.credit -= cost;
+            self.last_run_ms[index] = Some(now_ms);
+            tick.bytes += cost;
+        }
+        Ok(tick)
+    }
+
+    fn run<I: Interface>(
+        &mut self,
+        imu: &mut Icm42688p<I>,
+        task: PollTask,
+    ) -> Result<PollOutput, Error> {
+        let mut driver = imu.driver();
+        Ok(match task {
+            PollTask::Data => {
+                let data = driver.read_sensor_data()?;
+                let widen = |(x, y, z): (i16, i16, i16)| (x.into(), y.into(), z.into());
+                let (accel, gyro) = driver.scale_sample(
+                    (widen(data.acceleration), self.accel_factor),
+                    (widen(data.angular_velocity), self.gyro_factor),
+                );
+                PollOutput::Data { accel, gyro }
+            }
+            PollTask::Temperature => PollOutput::Temperature(driver.temperature_celsius()?),
+            PollTask::Status => PollOutput::Status {
+                data_ready: driver.data_ready()?,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        register::{Bank0, Register},
+        tests::imu,
+    };
+
+    /// Data every ms, status every 10ms and temperature every 100ms over I2C.
+    fn plan(bytes_per_ms: u32) -> PollPlan {
+        let rate = |period, priority| {
+            Some(PollRate {
+                period: MillisDurationU32::from_ticks(period),
+                priority,
+            })
+        };
+        PollPlan {
+            data: rate(1, 2),
+            temperature: rate(100, 0),
+            status: rate(10, 1),
+            bytes_per_ms,
+            overhead_bytes: 3,
+        }
+    }
+
+    /// Runs a tick, returning its outcome, outputs and their count.
+    fn run_tick<I: Interface>(
+        scheduler: &mut PollScheduler,
+        imu: &mut Icm42688p<I>,
+        now_ms: u32,
+    ) -> (PollTick, [Option<PollOutput>; 3], usize) {
+        let mut outputs = [None; 3];
+        let mut len = 0;
+        let tick = scheduler
+            .tick(imu, now_ms, |output| {
+                outputs[len] = Some(output);
+                len += 1;
+            })
+            .unwrap();
+        (tick, outputs, len)
+    }
+
+    #[test]
+    fn poll_plan_cost_and_load() {
+        let plan = plan(11);
+        assert_eq!(plan.cost(PollTask::Data), 17);
+        assert_eq!(plan.cost(PollTask::Temperature), 5);
+        assert_eq!(plan.cost(PollTask::Status), 4);
+        assert!((plan.load() - (17.0 + 0.05 + 0.4) / 11.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn poll_runs_due_tasks_by_priority() {
+        let mut imu = imu();
+        // 1g on Z at ±16g.
+        imu.interface.banks[0][Bank0::AccelDataZ1.address().0 as usize] = 0x08;
+        let mut scheduler = PollScheduler::new(&mut imu, plan(100), 0).unwrap();
+
+        let (tick, outputs, len) = run_tick(&mut scheduler, &mut imu, 1);
+        assert_eq!(
+            tick,
+            PollTick {
+                bytes: 26,
+                deferred: 0
+            }
+        );
+        assert_eq!(len, 3);
+        assert_eq!(
+            outputs[0],
+            Some(PollOutput::Data {
+                accel: (0.0, 0.0, 1.0),
+                gyro: (0.0, 0.0, 0.0),
+            })
+        );
+        assert_eq!(outputs[1], Some(PollOutput::Status { data_ready: false }));
+        assert!(matches!(outputs[2], Some(PollOutput::Temperature(_))));
+
+        // Only the data is due again.
+        let (tick, _, len) = run_tick(&mut scheduler, &mut imu, 2);
+        assert_eq!(
+            tick,
+            PollTick {
+                bytes: 17,
+                deferred: 0
+            }
+        );
+        assert_eq!(len, 1);
+    }
+
+    #[test]
+    fn poll_defers_tasks_over_budget() {
+        let mut imu = imu();
+        let mut scheduler = PollScheduler::new(&mut imu, plan(11), 0).unwrap();
+
+        // No budget accrued yet.
+        let (tick, _, len) = run_tick(&mut scheduler, &mut imu, 0);
+        assert_eq!(
+            tick,
+            PollTick {
+                bytes: 0,
+                deferred: 3
+            }
+        );
+        assert_eq!(len, 0);
+
+        // 11 bytes do not cover the data read, which holds back the cheaper tasks.
+        let (tick, _, len) = run_tick(&mut scheduler, &mut imu, 1);
+        assert_eq!(
+            tick,
+            PollTick {
+                bytes: 0,
+                deferred: 3
+            }
+        );
+        assert_eq!(len, 0);
+
+        // The budget is capped to the data read.
+        let (tick, outputs, len) = run_tick(&mut scheduler, &mut imu, 2);
+        assert_eq!(
+            tick,
+            PollTick {
+                bytes: 17,
+                deferred: 2
+            }
+        );
+        assert_eq!(len, 1);
+        assert!(matches!(outputs[0], Some(PollOutput::Data { .. })));
+    }
+
+    #[cfg(feature = "motion")]
+    #[test]
+    fn poll_data_removes_gyro_bias() {
+        let mut imu = imu();
+        imu.interface = crate::driver::tests::turning_at_10_dps();
+        imu.state.gyro_bias.enabled = true;
+        let mut scheduler = PollScheduler::new(&mut imu, plan(100), 0).unwrap();
+
+        let mut gyro_x = |now_ms| match run_tick(&mut scheduler, &mut imu, now_ms).1[0] {
+            Some(PollOutput::Data { gyro, .. }) => gyro.0,
+            output => panic!("{output:?}"),
+        };
+        assert!((gyro_x(1) - 10.0).abs() < 1e-3);
+        for now_ms in 2..=crate::motion::MOTION_WINDOW as u32 {
+            gyro_x(now_ms);
+        }
+        assert!(gyro_x(crate::motion::MOTION_WINDOW as u32 + 1).abs() < 1e-3);
+    }
+}
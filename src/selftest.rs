@@ -0,0 +1,87 @@
+use crate::MillisDurationU32;
+
+/// Settings of [`crate::Icm42688p::self_test_online`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SelfTestConfig {
+    /// Number of samples averaged with the self-test disabled, then enabled.
+    ///
+    /// The full factory procedure averages 200 samples, fewer samples shorten the idle window
+    /// needed at the cost of a noisier response.
+    pub samples: u16,
+    /// Time given to the sensor output to settle after enabling or disabling the self-test.
+    pub settle: MillisDurationU32,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        Self {
+            samples: 25,
+            settle: MillisDurationU32::millis(20),
+        }
+    }
+}
+
+/// Outcome of a self-test, per X, Y and Z axis.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SelfTestReport {
+    pub accel: [bool; 3],
+    pub gyro: [bool; 3],
+}
+
+impl SelfTestReport {
+    /// Whether all axes of both sensors passed.
+    pub fn passed(&self) -> bool {
+        self.accel.iter().chain(&self.gyro).all(|passed| *passed)
+    }
+
+    /// Sensors whose axes all passed, see [`crate::Icm42688p::restrict_capabilities`].
+    pub fn capabilities(&self) -> crate::Capabilities {
+        crate::Capabilities {
+            accel: self.accel.iter().all(|passed| *passed),
+            gyro: self.gyro.iter().all(|passed| *passed),
+        }
+    }
+}
+
+/// Minimum gyro response relative to the factory response.
+const GYRO_MIN_RATIO: f32 = 0.5;
+/// Accepted accel response relative to the factory response.
+const ACCEL_RATIO_RANGE: (f32, f32) = (0.5, 1.5);
+
+/// Self-test response recorded during manufacturing for a self-test code, unit: LSB
+///
+/// `fs_sel` is the full scale the test runs at, ±250°/s for the gyro and ±4g for the accel.
+fn factory_response(fs_sel: u8, code: u8) -> f32 {
+    if code == 0 {
+        return 0.0;
+    }
+    let mut response = 2620.0 / (1 << (3 - fs_sel)) as f32;
+    for _ in 1..code {
+        response *= 1.01;
+    }
+    response
+}
+
+/// Compares the measured self-test responses, unit: LSB, with the factory ones.
+pub(crate) fn evaluate(
+    accel_response: [f32; 3],
+    gyro_response: [f32; 3],
+    accel_codes: [u8; 3],
+    gyro_codes: [u8; 3],
+) -> SelfTestReport {
+    let mut report = SelfTestReport {
+        accel: [false; 3],
+        gyro: [false; 3],
+    };
+    for axis in 0..3 {
+        let factory = factory_response(crate::AccelRange::G4 as u8, accel_codes[axis]);
+        let ratio = accel_response[axis].abs() / factory;
+        report.accel[axis] =
+            factory > 0.0 && ratio > ACCEL_RATIO_RANGE.0 && ratio < ACCEL_RATIO_RANGE.1;
+
+        let factory = factory_response(crate::GyroRange::Dps250 as u8, gyro_codes[axis]);
+        let ratio = gyro_response[axis].abs() / factory;
+        report.gyro[axis] = factory > 0.0 && ratio > GYRO_MIN_RATIO;
+    }
+    report
+}
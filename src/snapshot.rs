@@ -0,0 +1,108 @@
+use crate::{
+    interface::{Bank, RegAddr},
+    register::{Bank0, Bank1, Bank2, Bank4, Register},
+};
+
+/// Configuration registers captured by [`ConfigSnapshot`], grouped by bank.
+pub(crate) static CONFIG_REGISTERS: [(&str, &(dyn Register + Sync)); 39] = [
+    ("DEVICE_CONFIG", &Bank0::DeviceConfig),
+    ("INT_CONFIG", &Bank0::IntConfig),
+    ("FIFO_CONFIG", &Bank0::FifoConfig),
+    ("INTF_CONFIG0", &Bank0::IntfConfig0),
+    ("INTF_CONFIG1", &Bank0::IntfConfig1),
+    ("PWR_MGMT0", &Bank0::PwrMgmt0),
+    ("GYRO_CONFIG0", &Bank0::GyroConfig0),
+    ("ACCEL_CONFIG0", &Bank0::AccelConfig0),
+    ("GYRO_CONFIG1", &Bank0::GyroConfig1),
+    ("GYRO_ACCEL_CONFIG0", &Bank0::GyroAccelConfig0),
+    ("ACCEL_CONFIG1", &Bank0::AccelConfig1),
+    ("TMST_CONFIG", &Bank0::TmstConfig),
+    ("APEX_CONFIG0", &Bank0::ApexConfig0),
+    ("SMD_CONFIG", &Bank0::SmdConfig),
+    ("FIFO_CONFIG1", &Bank0::FifoConfig1),
+    ("FIFO_CONFIG2", &Bank0::FifoConfig2),
+    ("FIFO_CONFIG3", &Bank0::FifoConfig3),
+    ("INT_CONFIG0", &Bank0::IntConfig0),
+    ("INT_CONFIG1", &Bank0::IntConfig1),
+    ("INT_SOURCE0", &Bank0::IntSource0),
+    ("INT_SOURCE1", &Bank0::IntSource1),
+    ("INT_SOURCE3", &Bank0::IntSource3),
+    ("INT_SOURCE4", &Bank0::IntSource4),
+    ("SELF_TEST_CONFIG", &Bank0::SelfTestConfig),
+    ("GYRO_CONFIG_STATIC2", &Bank1::GyroConfigStatic2),
+    ("GYRO_CONFIG_STATIC3", &Bank1::GyroConfigStatic3),
+    ("GYRO_CONFIG_STATIC4", &Bank1::GyroConfigStatic4),
+    ("GYRO_CONFIG_STATIC5", &Bank1::GyroConfigStatic5),
+    ("INTF_CONFIG5", &Bank1::IntfConfig5),
+    ("ACCEL_CONFIG_STATIC2", &Bank2::AccelConfigStatic2),
+    ("ACCEL_CONFIG_STATIC3", &Bank2::AccelConfigStatic3),
+    ("ACCEL_CONFIG_STATIC4", &Bank2::AccelConfigStatic4),
+    ("APEX_CONFIG7", &Bank4::ApexConfig7),
+    ("APEX_CONFIG8", &Bank4::ApexConfig8),
+    ("ACCEL_WOM_X_THR", &Bank4::AccelWomXThr),
+    ("ACCEL_WOM_Y_THR", &Bank4::AccelWomYThr),
+    ("ACCEL_WOM_Z_THR", &Bank4::AccelWomZThr),
+    ("INT_SOURCE6", &Bank4::IntSource6),
+    ("INT_SOURCE7", &Bank4::IntSource7),
+];
+
+/// Configuration register of a [`ConfigSnapshot`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ConfigRegister {
+    /// Datasheet name, e.g. `"GYRO_CONFIG0"`.
+    pub name: &'static str,
+    pub bank: Bank,
+    pub address: RegAddr,
+}
+
+/// Values of the configuration registers, read by [`crate::Icm42688p::config_snapshot`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ConfigSnapshot {
+    pub(crate) values: [u8; CONFIG_REGISTERS.len()],
+}
+
+impl ConfigSnapshot {
+    /// Captured registers and their values.
+    pub fn registers(&self) -> impl Iterator<Item = (ConfigRegister, u8)> + '_ {
+        CONFIG_REGISTERS
+            .iter()
+            .zip(self.values)
+            .map(|((name, register), value)| {
+                let register = ConfigRegister {
+                    name,
+                    bank: register.bank_selection().into(),
+                    address: register.address(),
+                };
+                (register, value)
+            })
+    }
+
+    /// Registers whose value differs between `self` and `other`.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = FieldDiff> + 'a {
+        self.registers()
+            .zip(other.values)
+            .filter(|((_, before), after)| before != after)
+            .map(|((register, before), after)| FieldDiff {
+                register,
+                before,
+                after,
+            })
+    }
+}
+
+/// Register found different by [`ConfigSnapshot::diff`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FieldDiff {
+    pub register: ConfigRegister,
+    /// Value in the snapshot `diff` was called on.
+    pub before: u8,
+    /// Value in the other snapshot.
+    pub after: u8,
+}
+
+impl FieldDiff {
+    /// Bits that differ, to locate the fields in the register description.
+    pub fn changed_bits(&self) -> u8 {
+        self.before ^ self.after
+    }
+}
@@ -0,0 +1,66 @@
+//! Durations of the public API.
+//!
+//! With the `fugit` feature these are the `fugit` duration types, so `100.millis()` and the
+//! conversions between units of `fugit` work. Without it, minimal stand-ins with the same names,
+//! constructors and accessors are provided, code sticking to `from_ticks`, `ticks`, the unit
+//! shorthands (e.g. `MillisDurationU32::millis(100)`) and `to_micros`/`to_millis` builds either
+//! way.
+
+#[cfg(feature = "fugit")]
+pub use fugit::{MicrosDurationU32, MicrosDurationU64, MillisDurationU32};
+
+#[cfg(not(feature = "fugit"))]
+pub use fallback::*;
+
+#[cfg(not(feature = "fugit"))]
+mod fallback {
+    macro_rules! duration {
+        ($name:ident, $int:ty, $unit:ident, $doc:literal) => {
+            #[doc = concat!("Duration in ", $doc, ", stand-in for the `fugit` type of the same name")]
+            #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+            pub struct $name($int);
+
+            impl $name {
+                pub const fn from_ticks(ticks: $int) -> Self {
+                    Self(ticks)
+                }
+
+                #[doc = concat!("Unit: ", $doc)]
+                pub const fn ticks(&self) -> $int {
+                    self.0
+                }
+
+                #[doc = concat!("Shorthand for a duration of `val` ", $doc, ".")]
+                pub const fn $unit(val: $int) -> Self {
+                    Self(val)
+                }
+            }
+        };
+    }
+
+    duration!(MicrosDurationU32, u32, micros, "microseconds");
+    duration!(MicrosDurationU64, u64, micros, "microseconds");
+    duration!(MillisDurationU32, u32, millis, "milliseconds");
+
+    impl MicrosDurationU32 {
+        pub const fn to_micros(&self) -> u32 {
+            self.0
+        }
+    }
+
+    impl MicrosDurationU64 {
+        pub const fn to_micros(&self) -> u64 {
+            self.0
+        }
+    }
+
+    impl MillisDurationU32 {
+        pub const fn to_millis(&self) -> u32 {
+            self.0
+        }
+
+        pub const fn to_micros(&self) -> u32 {
+            self.0 * 1000
+        }
+    }
+}
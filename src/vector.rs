@@ -0,0 +1,28 @@
+/// Vector type a reading can be returned as, see [`crate::Icm42688p::acceleration_vector`].
+///
+/// Implemented for every type built from an array, e.g. nalgebra's `Vector3<f32>`, glam's `Vec3`
+/// or mint's `Vector3<f32>`, and with the `micromath` feature for `micromath::vector::F32x3`. `M`
+/// only tells these implementations apart and is always inferred.
+pub trait FromAxes<M> {
+    fn from_axes(x: f32, y: f32, z: f32) -> Self;
+}
+
+/// [`FromAxes`] marker of the types built from an array.
+pub enum ArrayVector {}
+
+impl<V: From<[f32; 3]>> FromAxes<ArrayVector> for V {
+    fn from_axes(x: f32, y: f32, z: f32) -> Self {
+        V::from([x, y, z])
+    }
+}
+
+/// [`FromAxes`] marker of `micromath::vector::F32x3`.
+#[cfg(feature = "micromath")]
+pub enum MicromathVector {}
+
+#[cfg(feature = "micromath")]
+impl FromAxes<MicromathVector> for micromath::vector::F32x3 {
+    fn from_axes(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+}